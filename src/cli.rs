@@ -0,0 +1,185 @@
+use jisaku_riscv_sim::{
+    error::{SimError, SimResult},
+    isa::Isa,
+    memory::{DEFAULT_RAM_BASE, DEFAULT_RAM_SIZE},
+    syscall::Personality,
+};
+
+pub const USAGE: &str = "\
+usage: jisaku-riscv-sim [options] <elf> [guest args...]
+
+options:
+  --isa <string>             ISA to simulate, e.g. rv64imafdc, rv32gc or rv64gcv_zvl256b
+                             (default: the ELF's XLEN with imafdc)
+  --memory-base <addr>       physical address RAM starts at (default: 0)
+  --memory-size <bytes>      RAM size, with an optional K, M or G suffix (default: 4G)
+  --max-instructions <n>     stop with a failure after executing n instructions
+  --syscalls <linux|newlib>  emulate Linux or newlib/riscv-pk system calls instead of
+                             trapping on ECALL
+  --env <KEY=VALUE>          add a variable to the guest's environment; may be repeated
+  --signature <file>         after the run, dump memory from begin_signature to
+                             end_signature to the file, one word per line
+  --signature-granularity <bytes>
+                             bytes per signature line, 4 or 8 (default: 4)
+  --trace / --no-trace       print each instruction and the registers (default: off)
+  --entropy-seed <n>         seed for the entropy returned by the Zkr seed CSR
+  -h, --help                 print this message";
+
+/// Command-line options. Everything after the ELF path is passed to the guest untouched.
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub isa: Option<Isa>,
+    pub memory_base: u64,
+    pub memory_size: u64,
+    pub max_instructions: Option<u64>,
+    pub syscalls: Option<Personality>,
+    /// Guest environment, as `KEY=VALUE` strings.
+    pub env: Vec<String>,
+    pub signature: Option<String>,
+    pub signature_granularity: u64,
+    pub trace: bool,
+    pub entropy_seed: Option<u64>,
+    pub help: bool,
+    /// Guest argv; `args[0]` is the ELF path.
+    pub args: Vec<String>,
+}
+
+impl Options {
+    pub fn elf(&self) -> &str {
+        &self.args[0]
+    }
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> SimResult<Options> {
+    let mut options = Options {
+        isa: None,
+        memory_base: DEFAULT_RAM_BASE,
+        memory_size: DEFAULT_RAM_SIZE,
+        max_instructions: None,
+        syscalls: None,
+        env: vec![],
+        signature: None,
+        signature_granularity: 4,
+        trace: false,
+        entropy_seed: None,
+        help: false,
+        args: vec![],
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // Accept both `--flag value` and `--flag=value`.
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || match inline {
+            Some(value) => Ok(value.to_string()),
+            None => args
+                .next()
+                .ok_or_else(|| SimError::ParseError(format!("{} needs a value", flag))),
+        };
+        match flag.as_str() {
+            "--isa" => options.isa = Some(value()?.parse()?),
+            "--memory-base" => options.memory_base = parse_number(&value()?)?,
+            "--memory-size" => options.memory_size = parse_size(&value()?)?,
+            "--max-instructions" => options.max_instructions = Some(parse_number(&value()?)?),
+            "--syscalls" => options.syscalls = Some(value()?.parse()?),
+            "--env" => options.env.push(parse_env(value()?)?),
+            "--signature" => options.signature = Some(value()?),
+            "--signature-granularity" => {
+                options.signature_granularity = match parse_number(&value()?)? {
+                    granularity @ (4 | 8) => granularity,
+                    granularity => {
+                        return Err(SimError::ParseError(format!(
+                            "signature granularity must be 4 or 8, not {}",
+                            granularity
+                        )))
+                    }
+                }
+            }
+            "--trace" => options.trace = true,
+            "--no-trace" => options.trace = false,
+            "--entropy-seed" => options.entropy_seed = Some(parse_number(&value()?)?),
+            "-h" | "--help" => options.help = true,
+            "--" => {
+                options.args.extend(args);
+                break;
+            }
+            _ if arg.starts_with('-') => {
+                return Err(SimError::ParseError(format!("unknown option {}", arg)))
+            }
+            _ => {
+                options.args.push(arg);
+                options.args.extend(args);
+                break;
+            }
+        }
+    }
+    if options.args.is_empty() && !options.help {
+        return Err(SimError::ParseError("missing ELF path".into()));
+    }
+    Ok(options)
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal number.
+fn parse_number(value: &str) -> SimResult<u64> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| SimError::ParseError(format!("invalid number {:?}", value)))
+}
+
+/// Checks that an environment entry has the `KEY=VALUE` shape.
+fn parse_env(value: String) -> SimResult<String> {
+    match value.split_once('=') {
+        Some((key, _)) if !key.is_empty() => Ok(value),
+        _ => Err(SimError::ParseError(format!(
+            "invalid environment entry {:?}, expected KEY=VALUE",
+            value
+        ))),
+    }
+}
+
+/// Parses a byte count such as `4096`, `64M` or `4G`.
+fn parse_size(value: &str) -> SimResult<u64> {
+    let (digits, shift) = match value.as_bytes().last() {
+        Some(b'k' | b'K') => (&value[..value.len() - 1], 10),
+        Some(b'm' | b'M') => (&value[..value.len() - 1], 20),
+        Some(b'g' | b'G') => (&value[..value.len() - 1], 30),
+        _ => (value, 0),
+    };
+    parse_number(digits)?
+        .checked_mul(1 << shift)
+        .filter(|&size| size > 0)
+        .ok_or_else(|| SimError::ParseError(format!("invalid memory size {:?}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn env_entries_accumulate_and_stop_at_the_elf() {
+        let options = parse(args(&[
+            "--env",
+            "HOME=/",
+            "--env=PATH=/bin:/usr/bin",
+            "prog",
+            "--env",
+            "X=1",
+        ]))
+        .unwrap();
+        assert_eq!(options.env, ["HOME=/", "PATH=/bin:/usr/bin"]);
+        // Everything after the ELF belongs to the guest.
+        assert_eq!(options.args, ["prog", "--env", "X=1"]);
+
+        for bad in ["HOME", "=value"] {
+            assert!(parse(args(&["--env", bad, "prog"])).is_err());
+        }
+    }
+}
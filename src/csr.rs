@@ -0,0 +1,478 @@
+use enumn::N;
+
+use crate::processor::{Privilege, State, XLEN};
+
+#[derive(Debug, PartialEq, N, Clone, Copy)]
+#[repr(u16)]
+pub enum CsrName {
+    fflags = 0x001,
+    frm = 0x002,
+    fcsr = 0x003,
+    vstart = 0x008,
+    vxsat = 0x009,
+    vxrm = 0x00a,
+    vcsr = 0x00f,
+    seed = 0x015,
+    jvt = 0x017,
+
+    cycle = 0xc00,
+    time = 0xc01,
+    instret = 0xc02,
+    cycleh = 0xc80,
+    timeh = 0xc81,
+    instreth = 0xc82,
+    vl = 0xc20,
+    vtype = 0xc21,
+    vlenb = 0xc22,
+
+    sstatus = 0x100,
+    sie = 0x104,
+    stvec = 0x105,
+    scounteren = 0x106,
+    sscratch = 0x140,
+    sepc = 0x141,
+    scause = 0x142,
+    stval = 0x143,
+    sip = 0x144,
+    satp = 0x180,
+
+    mvendorid = 0xf11,
+    marchid = 0xf12,
+    mimpid = 0xf13,
+    mhartid = 0xf14,
+    mstatus = 0x300,
+    misa = 0x301,
+    medeleg = 0x302,
+    mideleg = 0x303,
+    mie = 0x304,
+    mtvec = 0x305,
+    mcounteren = 0x306,
+    mstatush = 0x310,
+    mscratch = 0x340,
+    mepc = 0x341,
+    mcause = 0x342,
+    mtval = 0x343,
+    mip = 0x344,
+    mcycle = 0xb00,
+    minstret = 0xb02,
+    mcycleh = 0xb80,
+    minstreth = 0xb82,
+}
+impl CsrName {
+    pub fn from_num(value: i64) -> Option<Self> {
+        Self::n(value as u16)
+    }
+    /// The top two address bits mark read-only CSRs.
+    pub fn read_only(&self) -> bool {
+        (*self as u16) >> 10 == 0b11
+    }
+    /// Lowest privilege level allowed to access the CSR.
+    pub fn privilege(&self) -> u8 {
+        ((*self as u16) >> 8 & 0b11) as u8
+    }
+}
+
+pub const MSTATUS_SIE: u64 = 1 << 1;
+pub const MSTATUS_MIE: u64 = 1 << 3;
+pub const MSTATUS_SPIE: u64 = 1 << 5;
+pub const MSTATUS_MPIE: u64 = 1 << 7;
+pub const MSTATUS_SPP: u64 = 1 << 8;
+pub const MSTATUS_VS: u64 = 0b11 << 9;
+/// VS in the Initial state, as a kernel hands the vector unit to a new process.
+pub const MSTATUS_VS_INITIAL: u64 = 0b01 << 9;
+pub const MSTATUS_MPP: u64 = 0b11 << 11;
+pub const MSTATUS_FS: u64 = 0b11 << 13;
+/// FS in the Initial state, as a kernel hands the FP unit to a new process.
+pub const MSTATUS_FS_INITIAL: u64 = 0b01 << 13;
+pub const MSTATUS_XS: u64 = 0b11 << 15;
+pub const MSTATUS_MPRV: u64 = 1 << 17;
+pub const MSTATUS_SUM: u64 = 1 << 18;
+pub const MSTATUS_MXR: u64 = 1 << 19;
+pub const MSTATUS_TVM: u64 = 1 << 20;
+pub const MSTATUS_TW: u64 = 1 << 21;
+pub const MSTATUS_TSR: u64 = 1 << 22;
+pub const MSTATUS_UXL: u64 = 0b11 << 32;
+
+const MSTATUS_WRITABLE: u64 = MSTATUS_SIE
+    | MSTATUS_MIE
+    | MSTATUS_SPIE
+    | MSTATUS_MPIE
+    | MSTATUS_SPP
+    | MSTATUS_MPP
+    | MSTATUS_VS
+    | MSTATUS_FS
+    | MSTATUS_MPRV
+    | MSTATUS_SUM
+    | MSTATUS_MXR
+    | MSTATUS_TVM
+    | MSTATUS_TW
+    | MSTATUS_TSR;
+const SSTATUS_VISIBLE: u64 = MSTATUS_SIE
+    | MSTATUS_SPIE
+    | MSTATUS_SPP
+    | MSTATUS_VS
+    | MSTATUS_FS
+    | MSTATUS_XS
+    | MSTATUS_SUM
+    | MSTATUS_MXR
+    | MSTATUS_UXL;
+
+/// Exceptions that may be handed to S-mode; ecall from M-mode (11) and reserved causes stay put.
+const DELEGABLE_EXCEPTIONS: u64 = 0xb3ff;
+/// SSIP, STIP and SEIP.
+const SUPERVISOR_INTERRUPTS: u64 = 0x222;
+/// SSIE, MSIE, STIE, MTIE, SEIE and MEIE.
+const ALL_INTERRUPTS: u64 = 0xaaa;
+/// The seed OPST field reporting ES16: the low 16 bits hold fresh entropy.
+const SEED_ES16: u64 = 0b10 << 30;
+/// Increment of the splitmix64 counter behind `State::entropy`.
+const SPLITMIX_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+impl State {
+    /// Decides whether the current privilege level may read the CSR and, if `write` is set,
+    /// modify it. A `false` result means the access raises an illegal-instruction exception.
+    pub fn csr_accessible(&self, csr: CsrName, write: bool) -> bool {
+        use CsrName::*;
+        if (self.prv as u8) < csr.privilege() || (write && csr.read_only()) {
+            return false;
+        }
+        match csr {
+            fflags | frm | fcsr if !self.has_extension("f") || !self.fp_enabled() => false,
+            jvt if !self.has_extension("zcmt") => false,
+            // seed may only be accessed by instructions that write it. mseccfg is not
+            // implemented, so its USEED and SSEED bits are clear and only M-mode may.
+            seed => write && self.has_extension("zkr") && self.prv == Privilege::Machine,
+            vstart | vxsat | vxrm | vcsr | vl | vtype | vlenb
+                if !self.has_vector() || !self.vector_enabled() =>
+            {
+                false
+            }
+            cycleh | timeh | instreth | mstatush | mcycleh | minstreth
+                if self.xlen != XLEN::RV32 =>
+            {
+                false
+            }
+            // TVM traps S-mode attempts to manage address translation.
+            satp if self.prv == Privilege::Supervisor => self.mstatus as u64 & MSTATUS_TVM == 0,
+            cycle | time | instret | cycleh | timeh | instreth => {
+                let bit = 1 << ((csr as u16) & 0x1f);
+                match self.prv {
+                    Privilege::Machine => true,
+                    Privilege::Supervisor => self.mcounteren as u64 & bit != 0,
+                    Privilege::User => self.mcounteren as u64 & self.scounteren as u64 & bit != 0,
+                }
+            }
+            _ => true,
+        }
+    }
+
+    pub fn csr_read(&self, csr: CsrName) -> u64 {
+        use CsrName::*;
+        let value = match csr {
+            fflags => self.fflags as u64,
+            frm => self.frm as u64,
+            fcsr => (self.frm as u64) << 5 | self.fflags as u64,
+            jvt => self.jvt as u64,
+            seed => SEED_ES16 | splitmix64(self.entropy) & 0xffff,
+            vstart => self.vstart as u64,
+            vxsat => self.vxsat as u64,
+            vxrm => self.vxrm as u64,
+            vcsr => (self.vxrm as u64) << 1 | self.vxsat as u64,
+            vl => self.vl as u64,
+            vtype => self.vtype as u64,
+            vlenb => self.vregs.vlenb() as u64,
+
+            cycle | time | instret | mcycle | minstret => self.minstret as u64,
+            cycleh | timeh | instreth | mcycleh | minstreth => self.minstret as u64 >> 32,
+
+            sstatus => self.read_mstatus() & (SSTATUS_VISIBLE | self.mstatus_sd()),
+            sie => self.mie as u64 & self.mideleg as u64,
+            stvec => self.stvec as u64,
+            scounteren => self.scounteren as u64,
+            sscratch => self.sscratch as u64,
+            sepc => self.sepc as u64,
+            scause => self.scause as u64,
+            stval => self.stval as u64,
+            sip => self.mip as u64 & self.mideleg as u64,
+            satp => self.satp as u64,
+
+            mvendorid | marchid | mimpid | mhartid | mstatush => 0,
+            mstatus => self.read_mstatus(),
+            misa => self.read_misa(),
+            medeleg => self.medeleg as u64,
+            mideleg => self.mideleg as u64,
+            mie => self.mie as u64,
+            mtvec => self.mtvec as u64,
+            mcounteren => self.mcounteren as u64,
+            mscratch => self.mscratch as u64,
+            mepc => self.mepc as u64,
+            mcause => self.mcause as u64,
+            mtval => self.mtval as u64,
+            mip => self.mip as u64,
+        };
+        self.zext_xlen(value as i64)
+    }
+
+    /// Writes a CSR, keeping only the legal values of its WARL fields.
+    pub fn csr_write(&mut self, csr: CsrName, value: u64) {
+        use CsrName::*;
+        let value = self.zext_xlen(value as i64);
+        match csr {
+            fflags => {
+                self.fflags = (value & 0x1f) as i64;
+                self.mark_fs_dirty();
+            }
+            frm => {
+                self.frm = (value & 0x7) as i64;
+                self.mark_fs_dirty();
+            }
+            fcsr => {
+                self.fflags = (value & 0x1f) as i64;
+                self.frm = (value >> 5 & 0x7) as i64;
+                self.mark_fs_dirty();
+            }
+            // Only the jump-table mode (0) exists, so the mode field is hardwired to zero.
+            jvt => self.jvt = (value & !0x3f) as i64,
+            // Every legal access writes seed, which moves the source on to its next sample.
+            seed => self.entropy = self.entropy.wrapping_add(SPLITMIX_GAMMA),
+            // vstart only needs to hold the largest element index, VLEN - 1.
+            vstart => {
+                self.vstart = (value & (self.vlen() as u64 - 1)) as i64;
+                self.mark_vs_dirty();
+            }
+            vxsat => {
+                self.vxsat = (value & 1) as i64;
+                self.mark_vs_dirty();
+            }
+            vxrm => {
+                self.vxrm = (value & 0b11) as i64;
+                self.mark_vs_dirty();
+            }
+            vcsr => {
+                self.vxsat = (value & 1) as i64;
+                self.vxrm = (value >> 1 & 0b11) as i64;
+                self.mark_vs_dirty();
+            }
+
+            mcycle | minstret => {
+                self.minstret = match self.xlen {
+                    XLEN::RV32 => (self.minstret as u64 & !0xffff_ffff | value) as i64,
+                    XLEN::RV64 | XLEN::RV128 => value as i64,
+                }
+            }
+            mcycleh | minstreth => {
+                self.minstret = (self.minstret as u64 & 0xffff_ffff | value << 32) as i64
+            }
+
+            sstatus => {
+                let mask = SSTATUS_VISIBLE & MSTATUS_WRITABLE;
+                self.write_mstatus(self.mstatus as u64 & !mask | value & mask)
+            }
+            sie => {
+                let mask = self.mideleg as u64;
+                self.mie = (self.mie as u64 & !mask | value & mask) as i64
+            }
+            stvec => self.stvec = legalize_tvec(self.stvec, value),
+            scounteren => self.scounteren = (value & 0x7) as i64,
+            sscratch => self.sscratch = value as i64,
+            sepc => self.sepc = self.legalize_epc(value),
+            scause => self.scause = value as i64,
+            stval => self.stval = value as i64,
+            sip => {
+                // Only the software interrupt is writable from S-mode.
+                let mask = self.mideleg as u64 & 0x2;
+                self.mip = (self.mip as u64 & !mask | value & mask) as i64
+            }
+            satp if self.satp_mode_supported(value) => self.satp = value as i64,
+
+            mstatus => self.write_mstatus(value),
+            medeleg => self.medeleg = (value & DELEGABLE_EXCEPTIONS) as i64,
+            mideleg => self.mideleg = (value & SUPERVISOR_INTERRUPTS) as i64,
+            mie => self.mie = (value & ALL_INTERRUPTS) as i64,
+            mtvec => self.mtvec = legalize_tvec(self.mtvec, value),
+            mcounteren => self.mcounteren = (value & 0x7) as i64,
+            mscratch => self.mscratch = value as i64,
+            mepc => self.mepc = self.legalize_epc(value),
+            mcause => self.mcause = value as i64,
+            mtval => self.mtval = value as i64,
+            mip => {
+                let mask = SUPERVISOR_INTERRUPTS;
+                self.mip = (self.mip as u64 & !mask | value & mask) as i64
+            }
+
+            // misa is WARL with no writable fields here, and the remaining CSRs are read-only
+            // or hardwired to zero.
+            _ => {}
+        }
+    }
+
+    fn read_mstatus(&self) -> u64 {
+        let mstatus = self.mstatus as u64;
+        match self.xlen {
+            XLEN::RV32 => mstatus | self.mstatus_sd(),
+            // UXL and SXL are fixed at 64 bits.
+            XLEN::RV64 | XLEN::RV128 => mstatus | 2 << 32 | 2 << 34 | self.mstatus_sd(),
+        }
+    }
+    fn write_mstatus(&mut self, value: u64) {
+        let mut mstatus = self.mstatus as u64 & !MSTATUS_WRITABLE | value & MSTATUS_WRITABLE;
+        // MPP is WARL: the reserved level 2 reads back as U-mode.
+        if mstatus & MSTATUS_MPP == 2 << 11 {
+            mstatus &= !MSTATUS_MPP;
+        }
+        self.mstatus = mstatus as i64;
+    }
+    /// SD summarizes whether FS, VS or XS is dirty.
+    fn mstatus_sd(&self) -> u64 {
+        let mstatus = self.mstatus as u64;
+        let dirty = mstatus & MSTATUS_FS == MSTATUS_FS
+            || mstatus & MSTATUS_VS == MSTATUS_VS
+            || mstatus & MSTATUS_XS == MSTATUS_XS;
+        if dirty {
+            1 << (self.xlen_bits() - 1)
+        } else {
+            0
+        }
+    }
+
+    /// xepc holds only addresses aligned to IALIGN: 16 bits with C, 32 bits without.
+    fn legalize_epc(&self, value: u64) -> i64 {
+        let mask = if self.misa as u64 & misa_extensions("c") != 0 {
+            !1
+        } else {
+            !3
+        };
+        (value & mask) as i64
+    }
+
+    fn read_misa(&self) -> u64 {
+        let mxl: u64 = match self.xlen {
+            XLEN::RV32 => 1,
+            XLEN::RV64 => 2,
+            XLEN::RV128 => 3,
+        };
+        mxl << (self.xlen_bits() - 2) | self.misa as u64
+    }
+
+    fn satp_mode_supported(&self, value: u64) -> bool {
+        match self.xlen {
+            // Bare or Sv32.
+            XLEN::RV32 => true,
+            // Bare, Sv39, Sv48 or Sv57.
+            XLEN::RV64 | XLEN::RV128 => matches!(value >> 60, 0 | 8 | 9 | 10),
+        }
+    }
+}
+
+/// Only the direct (0) and vectored (1) trap-vector modes exist, so other writes are ignored.
+fn legalize_tvec(old: i64, value: u64) -> i64 {
+    if value & 0b11 < 2 {
+        value as i64
+    } else {
+        old
+    }
+}
+
+/// The splitmix64 output function, which turns a counter into well-mixed bits.
+fn splitmix64(counter: u64) -> u64 {
+    let mut z = counter.wrapping_add(SPLITMIX_GAMMA);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The `misa` extension bits for a name such as "imafdc".
+pub fn misa_extensions(extensions: &str) -> u64 {
+    extensions
+        .bytes()
+        .filter(u8::is_ascii_lowercase)
+        .fold(0, |misa, ext| misa | 1 << (ext - b'a'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::tests::{machine, BASE};
+
+    #[test]
+    fn warl_fields_keep_only_legal_values() {
+        let mut state = State::new();
+        state.csr_write(CsrName::mtvec, 0x8000_0101);
+        // Mode 2 is reserved, so the write is dropped.
+        state.csr_write(CsrName::mtvec, 0x9000_0002);
+        assert_eq!(state.csr_read(CsrName::mtvec), 0x8000_0101);
+        state.csr_write(CsrName::medeleg, u64::MAX);
+        assert_eq!(state.csr_read(CsrName::medeleg), DELEGABLE_EXCEPTIONS);
+        // MPP = 2 is reserved and reads back as U-mode.
+        state.csr_write(CsrName::mstatus, 2 << 11 | MSTATUS_MIE);
+        assert_eq!(
+            state.csr_read(CsrName::mstatus) & (MSTATUS_MPP | MSTATUS_MIE),
+            MSTATUS_MIE
+        );
+        let misa = state.csr_read(CsrName::misa);
+        state.csr_write(CsrName::misa, 0);
+        assert_eq!(state.csr_read(CsrName::misa), misa);
+    }
+
+    #[test]
+    fn epc_alignment_follows_c() {
+        let mut state = State::new();
+        state.csr_write(CsrName::mepc, 0x8000_0003);
+        state.csr_write(CsrName::sepc, 0x8000_0007);
+        assert_eq!(state.csr_read(CsrName::mepc), 0x8000_0002);
+        assert_eq!(state.csr_read(CsrName::sepc), 0x8000_0006);
+
+        // Without C, instructions are 4-byte aligned and so are the exception PCs.
+        state.misa = misa_extensions("imafdsu") as i64;
+        state.csr_write(CsrName::mepc, 0x8000_0003);
+        state.csr_write(CsrName::sepc, 0x8000_0006);
+        assert_eq!(state.csr_read(CsrName::mepc), 0x8000_0000);
+        assert_eq!(state.csr_read(CsrName::sepc), 0x8000_0004);
+    }
+
+    #[test]
+    fn read_only_and_higher_privilege_csrs_are_illegal() {
+        let mut machine = machine(&[
+            0xf140_2573, // csrr a0, mhartid
+            0xf140_1073, // csrw mhartid, zero
+            0x3000_25f3, // csrr a1, mstatus
+        ]);
+        machine.run(1);
+        assert_eq!(machine.pc(), BASE + 4);
+        machine.run(1);
+        assert_eq!(machine.state.mcause, 2);
+        assert_eq!(machine.state.mtval, 0xf140_1073);
+
+        machine.state.prv = Privilege::User;
+        machine.set_pc(BASE + 8);
+        machine.run(1);
+        assert_eq!(machine.state.mcause, 2);
+        assert_eq!(machine.state.mtval, 0x3000_25f3);
+    }
+
+    #[test]
+    fn fp_csrs_follow_fs() {
+        let mut machine = machine(&[
+            0x0010_2573, // csrr a0, fflags
+            0x0020_d073, // csrwi frm, 1
+        ]);
+        // FS is Off out of reset, so the FP CSRs are illegal.
+        machine.run(1);
+        assert_eq!(machine.state.mcause, 2);
+        assert_eq!(machine.state.mtval, 0x0010_2573);
+
+        // Reading fflags leaves FS alone; writing frm makes it Dirty, which SD reports.
+        machine.state.mstatus = MSTATUS_FS_INITIAL as i64;
+        machine.set_pc(BASE);
+        machine.run(1);
+        assert_eq!(
+            machine.state.mstatus as u64 & MSTATUS_FS,
+            MSTATUS_FS_INITIAL
+        );
+        assert_eq!(machine.state.csr_read(CsrName::mstatus) >> 63, 0);
+        machine.run(1);
+        assert_eq!(machine.state.frm, 1);
+        assert_eq!(machine.state.mstatus as u64 & MSTATUS_FS, MSTATUS_FS);
+        assert_eq!(machine.state.csr_read(CsrName::mstatus) >> 63, 1);
+    }
+}
@@ -1,198 +1,296 @@
 use crate::{
-    error::{SimError, SimResult},
+    csr::{CsrName, MSTATUS_TSR, MSTATUS_TVM, MSTATUS_TW},
     instructions::{
+        privileged::{MRET, SFENCE_VMA, SRET, WFI},
         rv32i::{
-            ADDI, ANDI, AUIPC, BEQ, BGE, BGEU, BLT, BLTU, BNE, JAL, JALR, LB, LUI, ORI, SLTI,
-            SLTIU, XORI,
+            ADD, ADDI, AND, ANDI, AUIPC, BEQ, BGE, BGEU, BLT, BLTU, BNE, EBREAK, ECALL, FENCE,
+            FENCE_I, JAL, JALR, LB, LBU, LH, LHU, LUI, LW, OR, ORI, SB, SH, SLL, SLLI, SLT, SLTI,
+            SLTIU, SLTU, SRA, SRAI, SRL, SRLI, SUB, SW, XOR, XORI,
+        },
+        rv64a::{
+            AMOADD_D, AMOADD_W, AMOAND_D, AMOAND_W, AMOMAXU_D, AMOMAXU_W, AMOMAX_D, AMOMAX_W,
+            AMOMINU_D, AMOMINU_W, AMOMIN_D, AMOMIN_W, AMOOR_D, AMOOR_W, AMOSWAP_D, AMOSWAP_W,
+            AMOXOR_D, AMOXOR_W, LR_D, LR_W, SC_D, SC_W,
+        },
+        rv64d::{
+            FADD_D, FCLASS_D, FCVT_D_L, FCVT_D_LU, FCVT_D_S, FCVT_D_W, FCVT_D_WU, FCVT_LU_D,
+            FCVT_L_D, FCVT_S_D, FCVT_WU_D, FCVT_W_D, FDIV_D, FEQ_D, FLD, FLE_D, FLT_D, FMADD_D,
+            FMAX_D, FMIN_D, FMSUB_D, FMUL_D, FMV_D_X, FMV_X_D, FNMADD_D, FNMSUB_D, FSD, FSGNJN_D,
+            FSGNJX_D, FSGNJ_D, FSQRT_D, FSUB_D,
+        },
+        rv64f::{
+            FADD_S, FCLASS_S, FCVT_LU_S, FCVT_L_S, FCVT_S_L, FCVT_S_LU, FCVT_S_W, FCVT_S_WU,
+            FCVT_WU_S, FCVT_W_S, FDIV_S, FEQ_S, FLE_S, FLT_S, FLW, FMADD_S, FMAX_S, FMIN_S,
+            FMSUB_S, FMUL_S, FMV_W_X, FMV_X_W, FNMADD_S, FNMSUB_S, FSGNJN_S, FSGNJX_S, FSGNJ_S,
+            FSQRT_S, FSUB_S, FSW,
+        },
+        rv64i::{ADDIW, ADDW, LD, LWU, SD, SLLIW, SLLW, SRAIW, SRAW, SRLIW, SRLW, SUBW},
+        rv64m::{DIV, DIVU, DIVUW, DIVW, MUL, MULH, MULHSU, MULHU, MULW, REM, REMU, REMUW, REMW},
+        rvb::{
+            ADD_UW, ANDN, BCLR, BCLRI, BEXT, BEXTI, BINV, BINVI, BSET, BSETI, CLMUL, CLMULH,
+            CLMULR, CLZ, CLZW, CPOP, CPOPW, CTZ, CTZW, MAX, MAXU, MIN, MINU, ORC_B, ORN, REV8, ROL,
+            ROLW, ROR, RORI, RORIW, RORW, SEXT_B, SEXT_H, SH1ADD, SH1ADD_UW, SH2ADD, SH2ADD_UW,
+            SH3ADD, SH3ADD_UW, SLLI_UW, XNOR, ZEXT_H,
         },
         rvc::{
-            C_ADD, C_ADDI, C_ADDI4SPN, C_ADDW, C_AND, C_ANDI, C_BEQZ, C_BNEZ, C_EBREAK, C_FLD,
-            C_FLDSP, C_FLWSP, C_FSD, C_FSDSP, C_FSWSP, C_J, C_JAL, C_JALR, C_JR, C_LD, C_LDSP,
-            C_LI, C_LQSP, C_LW, C_LWSP, C_MV, C_NOP, C_OR, C_SD, C_SDSP, C_SLLI, C_SLLI64, C_SQSP,
-            C_SRAI, C_SRAI64, C_SRLI, C_SRLI64, C_SUB, C_SUBW, C_SW, C_SWSP, C_XOR,
+            C_ADD, C_ADDI, C_ADDI16SP, C_ADDI4SPN, C_ADDIW, C_ADDW, C_AND, C_ANDI, C_BEQZ, C_BNEZ,
+            C_EBREAK, C_FLD, C_FLDSP, C_FLW, C_FLWSP, C_FSD, C_FSDSP, C_FSW, C_FSWSP, C_J, C_JAL,
+            C_JALR, C_JR, C_LD, C_LDSP, C_LI, C_LQSP, C_LUI, C_LW, C_LWSP, C_MV, C_NOP, C_OR, C_SD,
+            C_SDSP, C_SLLI, C_SQSP, C_SRAI, C_SRLI, C_SUB, C_SUBW, C_SW, C_SWSP, C_XOR,
+        },
+        rvk::{
+            AES32DSI, AES32DSMI, AES32ESI, AES32ESMI, AES64DS, AES64DSM, AES64ES, AES64ESM,
+            AES64IM, AES64KS1I, AES64KS2, BREV8, PACK, PACKH, PACKW, SHA256SIG0, SHA256SIG1,
+            SHA256SUM0, SHA256SUM1, SHA512SIG0, SHA512SIG0H, SHA512SIG0L, SHA512SIG1, SHA512SIG1H,
+            SHA512SIG1L, SHA512SUM0, SHA512SUM0R, SHA512SUM1, SHA512SUM1R, SM3P0, SM3P1, SM4ED,
+            SM4KS, UNZIP, XPERM4, XPERM8, ZIP,
+        },
+        rvv::{VAddressing, VLoad, VStore, VSETIVLI, VSETVL, VSETVLI},
+        rvv_float::{
+            VFloatBinary, VFloatCompare, VFloatCompareOp, VFloatConvert, VFloatConvertOp, VFloatOp,
+            VFloatReduce, VFloatReduceOp, VFloatUnary, VFloatUnaryOp, VFloatWiden, VFloatWidenOp,
+            VFMV_F_S, VFMV_S_F,
+        },
+        rvv_int::{
+            VIntBinary, VIntCompare, VIntCompareOp, VIntExtend, VIntNarrow, VIntNarrowOp, VIntOp,
+            VIntReduce, VIntReduceOp, VIntWiden, VIntWidenOp,
+        },
+        rvv_mask::{
+            VMaskLogical, VMaskOp, VMaskSet, VMaskSetOp, VCPOP_M, VFIRST_M, VID_V, VIOTA_M,
+        },
+        rvv_permute::{VGather, VSlide, VSlideOp, VCOMPRESS_VM, VMV_NR_R, VMV_S_X, VMV_X_S},
+        zcb::{
+            C_LBU, C_LH, C_LHU, C_MUL, C_NOT, C_SB, C_SEXT_B, C_SEXT_H, C_SH, C_ZEXT_B, C_ZEXT_H,
+            C_ZEXT_W,
         },
+        zcmp::{CM_MVA01S, CM_MVSA01, CM_POP, CM_POPRET, CM_POPRETZ, CM_PUSH},
+        zcmt::{CM_JALT, CM_JT},
+        zicsr::{CSRRC, CSRRCI, CSRRS, CSRRSI, CSRRW, CSRRWI},
         Instruction,
     },
     processor::{
-        State,
-        XLEN::{self, RV128, RV32, RV64},
+        Privilege, State, XprName, VR,
+        XLEN::{RV128, RV32, RV64},
     },
+    trap::Exception,
     utils::x,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum InstructionRaw {
     B16(u16),
     B32(u32),
 }
 
 impl InstructionRaw {
-    pub fn get_inst(code: &[u8], pos: usize) -> Self {
-        let length = Self::inst_length(u16::from_le_bytes(code[pos..pos + 2].try_into().unwrap()));
-        assert!(length <= 4);
-        match length {
-            2 => InstructionRaw::B16(u16::from_le_bytes(
-                code[pos..pos + length].try_into().unwrap(),
-            )),
-            4 => InstructionRaw::B32(u32::from_le_bytes(
-                code[pos..pos + length].try_into().unwrap(),
-            )),
-            _ => panic!("test"),
-        }
-    }
-
-    fn inst_length(base: u16) -> usize {
-        if x(base, 0, 2) != 0b11 {
-            2
-        } else if x(base, 2, 3) != 0b111 {
-            4
-        } else if x(base, 5, 1) == 0 {
-            6
-        } else if x(base, 6, 1) == 0 {
-            8
-        } else if x(base, 12, 3) != 0b111 {
-            10 + x(base, 12, 3) as usize * 2
-        } else {
-            26
+    /// The encoding, as an illegal-instruction exception reports it in mtval.
+    pub fn bits(self) -> u64 {
+        match self {
+            InstructionRaw::B16(inst) => inst as u64,
+            InstructionRaw::B32(inst) => inst as u64,
         }
     }
 }
 
 impl State {
-    pub fn decode_inst(&self, inst: InstructionRaw) -> SimResult<Box<dyn Instruction>> {
+    pub fn decode_inst(&self, inst: InstructionRaw) -> Result<Box<dyn Instruction>, Exception> {
         match inst {
             InstructionRaw::B16(code) => self.decode_inst16(code),
             InstructionRaw::B32(code) => self.decode_inst32(code),
         }
     }
 
-    fn decode_inst16(&self, inst: u16) -> SimResult<Box<dyn Instruction>> {
+    fn decode_inst16(&self, inst: u16) -> Result<Box<dyn Instruction>, Exception> {
+        if !self.has_extension("c") {
+            return illegal_instruction(inst);
+        }
         let opcode = x(inst, 0, 2);
         match opcode {
             0b00 => self.decode_inst_c0(inst),
             0b01 => self.decode_inst_c1(inst),
             0b10 => self.decode_inst_c2(inst),
-            _ => {
-                panic!("unexpected branch");
-            }
+            _ => illegal_instruction(inst),
         }
     }
 
-    fn decode_inst32(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
+    fn decode_inst32(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
         let opcode = x(inst, 2, 5);
         match opcode {
             0x00 => self.decode_inst_op_00000(inst),
+            // Vector loads and stores share LOAD-FP and STORE-FP, and work without F.
+            0x01 | 0x09 if self.has_vector() && matches!(x(inst, 12, 3), 0b000 | 0b101..=0b111) => {
+                if !self.vector_enabled() {
+                    return illegal_instruction(inst);
+                }
+                self.decode_inst_vector_memory(inst)
+            }
+            0x01 | 0x09 | 0x10..=0x14 if !self.has_extension("f") || !self.fp_enabled() => {
+                illegal_instruction(inst)
+            }
+            0x0b if !self.has_extension("a") => illegal_instruction(inst),
             0x01 => self.decode_inst_op_00001(inst),
-            0x02 => self.decode_inst_op_00010(inst),
             0x03 => self.decode_inst_op_00011(inst),
             0x04 => self.decode_inst_op_00100(inst),
             0x05 => Ok(Box::new(AUIPC::new(inst))),
-            0x06 => self.decode_inst_op_00110(inst),
+            0x06 if self.xlen != RV32 => self.decode_inst_op_00110(inst),
             0x08 => self.decode_inst_op_01000(inst),
+            0x09 => self.decode_inst_op_01001(inst),
+            0x0b => self.decode_inst_op_01011(inst),
             0x0c => self.decode_inst_op_01100(inst),
             0x0d => Ok(Box::new(LUI::new(inst))),
+            0x0e if self.xlen != RV32 => self.decode_inst_op_01110(inst),
+            0x10..=0x13 => self.decode_inst_op_100xx(inst),
+            0x14 => self.decode_inst_op_10100(inst),
+            0x15 if self.has_vector() && self.vector_enabled() => self.decode_inst_op_10101(inst),
             0x18 => self.decode_inst_op_11000(inst),
             0x19 => Ok(Box::new(JALR::new(inst))),
             0x1b => Ok(Box::new(JAL::new(inst))),
             0x1c => self.decode_inst_op_11100(inst),
-            _ => {
-                panic!("");
-            }
+            _ => illegal_instruction(inst),
         }
     }
 
-    fn decode_inst_c0(&self, inst: u16) -> SimResult<Box<dyn Instruction>> {
+    fn decode_inst_c0(&self, inst: u16) -> Result<Box<dyn Instruction>, Exception> {
         let funct3 = x(inst, 13, 3);
         match funct3 {
-            0b000 => {
-                if inst == 0 {
-                    Err(SimError::ParseError("Illegal instruction".to_string()))
-                } else {
-                    Ok(Box::new(C_ADDI4SPN::new(inst)))
-                }
+            // A zero immediate is reserved, which also makes the all-zero halfword illegal.
+            0b000 if x(inst, 5, 8) == 0 => illegal_instruction(inst),
+            0b000 => Ok(Box::new(C_ADDI4SPN::new(inst))),
+            0b001 | 0b101 if !self.has_extension("d") || !self.fp_enabled() => {
+                illegal_instruction(inst)
+            }
+            0b011 | 0b111
+                if self.xlen == RV32 && (!self.has_extension("f") || !self.fp_enabled()) =>
+            {
+                illegal_instruction(inst)
             }
             0b001 => Ok(Box::new(C_FLD::new(inst))),
             0b010 => Ok(Box::new(C_LW::new(inst))),
-            0b011 => Ok(Box::new(C_LD::new(inst))),
-            0b100 => Err(SimError::ParseError("Reserved".to_string())),
+            0b011 => match self.xlen {
+                RV32 => Ok(Box::new(C_FLW::new(inst))),
+                RV64 | RV128 => Ok(Box::new(C_LD::new(inst))),
+            },
+            0b100 if self.has_extension("zcb") => match (x(inst, 10, 3), x(inst, 6, 1)) {
+                (0b000, _) => Ok(Box::new(C_LBU::new(inst))),
+                (0b001, 0) => Ok(Box::new(C_LHU::new(inst))),
+                (0b001, 1) => Ok(Box::new(C_LH::new(inst))),
+                (0b010, _) => Ok(Box::new(C_SB::new(inst))),
+                (0b011, 0) => Ok(Box::new(C_SH::new(inst))),
+                _ => illegal_instruction(inst),
+            },
             0b101 => Ok(Box::new(C_FSD::new(inst))),
             0b110 => Ok(Box::new(C_SW::new(inst))),
-            0b111 => Ok(Box::new(C_SD::new(inst))),
-            _ => {
-                panic!("unexpected branch");
-            }
+            0b111 => match self.xlen {
+                RV32 => Ok(Box::new(C_FSW::new(inst))),
+                RV64 | RV128 => Ok(Box::new(C_SD::new(inst))),
+            },
+            _ => illegal_instruction(inst),
         }
     }
 
-    fn decode_inst_c1(&self, inst: u16) -> SimResult<Box<dyn Instruction>> {
+    // HINTs (those writing x0 or with a no-op immediate) execute as the no-ops they are;
+    // only reserved encodings raise illegal-instruction exceptions.
+    fn decode_inst_c1(&self, inst: u16) -> Result<Box<dyn Instruction>, Exception> {
         let funct3 = x(inst, 13, 3);
+        let rd = x(inst, 7, 5);
+        let imm = x(inst, 2, 5) | (x(inst, 12, 1) << 5);
         match funct3 {
-            0b000 => {
-                if inst == 1 {
-                    Ok(Box::new(C_NOP::new(inst)))
-                } else {
-                    Ok(Box::new(C_ADDI::new(inst)))
-                }
-            }
-            0b001 => match self.xlen {
-                XLEN::RV32 => Ok(Box::new(C_JAL::new(inst))),
-                _ => Ok(Box::new(C_JAL::new(inst))),
-            },
+            0b000 if rd == 0 => Ok(Box::new(C_NOP::new(inst))),
+            0b000 => Ok(Box::new(C_ADDI::new(inst))),
+            0b001 if self.xlen == RV32 => Ok(Box::new(C_JAL::new(inst))),
+            0b001 if rd == 0 => illegal_instruction(inst),
+            0b001 => Ok(Box::new(C_ADDIW::new(inst))),
             0b010 => Ok(Box::new(C_LI::new(inst))),
-            0b100 => {
-                let flag1 = x(inst, 5, 2);
-                let flag2 = x(inst, 10, 2);
-                let flag3 = x(inst, 12, 1);
-                match (flag1, flag2, flag3) {
-                    (0b00, 0b00, 0) => Ok(Box::new(C_SRLI64::new(inst))),
-                    (_, 0b00, _) => Ok(Box::new(C_SRLI::new(inst))),
-                    (0b00, 0b01, 0) => Ok(Box::new(C_SRAI64::new(inst))),
-                    (_, 0b01, _) => Ok(Box::new(C_SRAI::new(inst))),
-                    (_, 0b10, _) => Ok(Box::new(C_ANDI::new(inst))),
-                    (0b00, 0b11, 0) => Ok(Box::new(C_SUB::new(inst))),
-                    (0b01, 0b11, 0) => Ok(Box::new(C_XOR::new(inst))),
-                    (0b10, 0b11, 0) => Ok(Box::new(C_OR::new(inst))),
-                    (0b11, 0b11, 0) => Ok(Box::new(C_AND::new(inst))),
-                    (0b00, 0b11, 1) => Ok(Box::new(C_SUBW::new(inst))),
-                    (0b01, 0b11, 1) => Ok(Box::new(C_ADDW::new(inst))),
-                    (0b10, 0b11, 1) => Err(SimError::ParseError("Reserved".to_string())),
-                    (0b11, 0b11, 1) => Err(SimError::ParseError("Reserved".to_string())),
-                    _ => panic!("unexpected branch"),
-                }
-            }
+            0b011 if imm == 0 => illegal_instruction(inst),
+            0b011 if rd == 2 => Ok(Box::new(C_ADDI16SP::new(inst))),
+            0b011 => Ok(Box::new(C_LUI::new(inst))),
+            0b100 => self.decode_inst_c1_100(inst),
             0b101 => Ok(Box::new(C_J::new(inst))),
             0b110 => Ok(Box::new(C_BEQZ::new(inst))),
             0b111 => Ok(Box::new(C_BNEZ::new(inst))),
-            _ => panic!("unexpected branch"),
+            _ => illegal_instruction(inst),
+        }
+    }
+
+    fn decode_inst_c1_100(&self, inst: u16) -> Result<Box<dyn Instruction>, Exception> {
+        let funct2 = x(inst, 10, 2);
+        let funct1 = x(inst, 12, 1);
+        let op = x(inst, 5, 2);
+        let zcb = self.has_extension("zcb");
+        match (funct2, funct1, op) {
+            // shamt[5] is reserved on RV32; a zero shift amount is a HINT.
+            (0b00 | 0b01, 1, _) if self.xlen == RV32 => illegal_instruction(inst),
+            (0b00, _, _) => Ok(Box::new(C_SRLI::new(inst))),
+            (0b01, _, _) => Ok(Box::new(C_SRAI::new(inst))),
+            (0b10, _, _) => Ok(Box::new(C_ANDI::new(inst))),
+            (0b11, 0, 0b00) => Ok(Box::new(C_SUB::new(inst))),
+            (0b11, 0, 0b01) => Ok(Box::new(C_XOR::new(inst))),
+            (0b11, 0, 0b10) => Ok(Box::new(C_OR::new(inst))),
+            (0b11, 0, 0b11) => Ok(Box::new(C_AND::new(inst))),
+            (0b11, 1, 0b10) if zcb && self.has_extension("m") => Ok(Box::new(C_MUL::new(inst))),
+            (0b11, 1, 0b11) if zcb => self.decode_inst_zcb_unary(inst),
+            (0b11, 1, _) if self.xlen == RV32 => illegal_instruction(inst),
+            (0b11, 1, 0b00) => Ok(Box::new(C_SUBW::new(inst))),
+            (0b11, 1, 0b01) => Ok(Box::new(C_ADDW::new(inst))),
+            _ => illegal_instruction(inst),
+        }
+    }
+
+    fn decode_inst_zcb_unary(&self, inst: u16) -> Result<Box<dyn Instruction>, Exception> {
+        let zbb = self.has_extension("zbb");
+        match x(inst, 2, 3) {
+            0b000 => Ok(Box::new(C_ZEXT_B::new(inst))),
+            0b001 if zbb => Ok(Box::new(C_SEXT_B::new(inst))),
+            0b010 if zbb => Ok(Box::new(C_ZEXT_H::new(inst))),
+            0b011 if zbb => Ok(Box::new(C_SEXT_H::new(inst))),
+            0b100 if self.xlen != RV32 && self.has_extension("zba") => {
+                Ok(Box::new(C_ZEXT_W::new(inst)))
+            }
+            0b101 => Ok(Box::new(C_NOT::new(inst))),
+            _ => illegal_instruction(inst),
         }
     }
 
-    fn decode_inst_c2(&self, inst: u16) -> SimResult<Box<dyn Instruction>> {
-        let flag1 = x(inst, 2, 5);
+    fn decode_inst_c2(&self, inst: u16) -> Result<Box<dyn Instruction>, Exception> {
+        let rs2 = x(inst, 2, 5);
         let rd = x(inst, 7, 5);
-        let flag2 = x(inst, 12, 1);
+        let flag = x(inst, 12, 1);
         let funct3 = x(inst, 13, 3);
         match funct3 {
-            0b000 => match (flag1, rd, flag2) {
-                (0, _, 0) => Ok(Box::new(C_SLLI64::new(inst))),
-                (_, _, _) => Ok(Box::new(C_SLLI::new(inst))),
-            },
+            // shamt[5] is reserved on RV32; writing x0 or shifting by zero is a HINT.
+            0b000 if flag == 1 && self.xlen == RV32 => illegal_instruction(inst),
+            0b000 => Ok(Box::new(C_SLLI::new(inst))),
+            // Zcmp and Zcmt take over the c.fsdsp slot, so the ISA cannot also have D.
+            0b101 if self.has_extension("zcmp") || self.has_extension("zcmt") => {
+                self.decode_inst_zcm(inst)
+            }
+            0b001 | 0b101 if !self.has_extension("d") || !self.fp_enabled() => {
+                illegal_instruction(inst)
+            }
+            0b011 | 0b111
+                if self.xlen == RV32 && (!self.has_extension("f") || !self.fp_enabled()) =>
+            {
+                illegal_instruction(inst)
+            }
             0b001 => match self.xlen {
                 RV32 | RV64 => Ok(Box::new(C_FLDSP::new(inst))),
                 RV128 => Ok(Box::new(C_LQSP::new(inst))),
             },
+            0b010 if rd == 0 => illegal_instruction(inst),
             0b010 => Ok(Box::new(C_LWSP::new(inst))),
             0b011 => match self.xlen {
                 RV32 => Ok(Box::new(C_FLWSP::new(inst))),
+                _ if rd == 0 => illegal_instruction(inst),
                 RV64 | RV128 => Ok(Box::new(C_LDSP::new(inst))),
             },
-            0b100 => match (flag2, rd, flag1) {
+            0b100 => match (flag, rd, rs2) {
+                (0, 0, 0) => illegal_instruction(inst),
                 (0, _, 0) => Ok(Box::new(C_JR::new(inst))),
+                // With rd = x0, c.mv and c.add are HINTs; c.ntl.* are among the latter.
                 (0, _, _) => Ok(Box::new(C_MV::new(inst))),
                 (1, 0, 0) => Ok(Box::new(C_EBREAK::new(inst))),
                 (1, _, 0) => Ok(Box::new(C_JALR::new(inst))),
-                (1, _, _) => Ok(Box::new(C_ADD::new(inst))),
-                _ => panic!("unexpected branch"),
+                _ => Ok(Box::new(C_ADD::new(inst))),
             },
             0b101 => match self.xlen {
                 RV32 | RV64 => Ok(Box::new(C_FSDSP::new(inst))),
@@ -203,48 +301,746 @@ impl State {
                 RV32 => Ok(Box::new(C_FSWSP::new(inst))),
                 RV64 | RV128 => Ok(Box::new(C_SDSP::new(inst))),
             },
-            _ => panic!("unexpected branch"),
+            _ => illegal_instruction(inst),
+        }
+    }
+
+    fn decode_inst_zcm(&self, inst: u16) -> Result<Box<dyn Instruction>, Exception> {
+        let zcmp = self.has_extension("zcmp");
+        // Register lists below {ra, s0} are reserved.
+        let rlist = x(inst, 4, 4);
+        match (x(inst, 8, 5), x(inst, 5, 2)) {
+            (0b00000..=0b00011, _) if self.has_extension("zcmt") => {
+                if x(inst, 2, 8) < 32 {
+                    Ok(Box::new(CM_JT::new(inst)))
+                } else {
+                    Ok(Box::new(CM_JALT::new(inst)))
+                }
+            }
+            (0b01100..=0b01111, 0b01) if zcmp && x(inst, 7, 3) != x(inst, 2, 3) => {
+                Ok(Box::new(CM_MVSA01::new(inst)))
+            }
+            (0b01100..=0b01111, 0b11) if zcmp => Ok(Box::new(CM_MVA01S::new(inst))),
+            (_, _) if !zcmp || rlist < 4 => illegal_instruction(inst),
+            (0b11000, _) => Ok(Box::new(CM_PUSH::new(inst))),
+            (0b11010, _) => Ok(Box::new(CM_POP::new(inst))),
+            (0b11100, _) => Ok(Box::new(CM_POPRETZ::new(inst))),
+            (0b11110, _) => Ok(Box::new(CM_POPRET::new(inst))),
+            _ => illegal_instruction(inst),
         }
     }
 
-    fn decode_inst_op_00000(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
+    fn decode_inst_op_00000(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
         let funct = x(inst, 12, 3);
         match funct {
             0b000 => Ok(Box::new(LB::new(inst))),
-            _ => panic!(),
+            0b001 => Ok(Box::new(LH::new(inst))),
+            0b010 => Ok(Box::new(LW::new(inst))),
+            0b011 if self.xlen != RV32 => Ok(Box::new(LD::new(inst))),
+            0b100 => Ok(Box::new(LBU::new(inst))),
+            0b101 => Ok(Box::new(LHU::new(inst))),
+            0b110 if self.xlen != RV32 => Ok(Box::new(LWU::new(inst))),
+            _ => illegal_instruction(inst),
         }
     }
-    fn decode_inst_op_00001(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
-        panic!()
+    fn decode_inst_op_00001(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let flag = x(inst, 12, 3);
+        match flag {
+            0b010 => Ok(Box::new(FLW::new(inst))),
+            0b011 if self.has_extension("d") => Ok(Box::new(FLD::new(inst))),
+            _ => illegal_instruction(inst),
+        }
     }
-    fn decode_inst_op_00010(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
-        panic!()
+    fn decode_inst_op_00011(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct = x(inst, 12, 3);
+        match funct {
+            0b000 => Ok(Box::new(FENCE::new(inst))),
+            0b001 => Ok(Box::new(FENCE_I::new(inst))),
+            _ => illegal_instruction(inst),
+        }
     }
-    fn decode_inst_op_00011(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
-        panic!()
+    fn decode_inst_op_00100(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct = x(inst, 12, 3);
+        // shamt[5] is reserved on RV32, so fold it in to reject those encodings.
+        let funct6 = match self.xlen {
+            RV32 => x(inst, 26, 6) | (x(inst, 25, 1) << 6),
+            RV64 | RV128 => x(inst, 26, 6),
+        };
+        match (funct, funct6) {
+            (0b000, _) => Ok(Box::new(ADDI::new(inst))),
+            (0b001, 0b000000) => Ok(Box::new(SLLI::new(inst))),
+            (0b010, _) => Ok(Box::new(SLTI::new(inst))),
+            (0b011, _) => Ok(Box::new(SLTIU::new(inst))),
+            (0b100, _) => Ok(Box::new(XORI::new(inst))),
+            (0b101, 0b000000) => Ok(Box::new(SRLI::new(inst))),
+            (0b101, 0b010000) => Ok(Box::new(SRAI::new(inst))),
+            (0b110, _) => Ok(Box::new(ORI::new(inst))),
+            (0b111, _) => Ok(Box::new(ANDI::new(inst))),
+            _ => self.decode_inst_op_00100_b(inst),
+        }
+    }
+    fn decode_inst_op_00110(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let funct7 = x(inst, 25, 7);
+        match (funct3, funct7) {
+            (0b000, _) => Ok(Box::new(ADDIW::new(inst))),
+            (0b001, 0b0000000) => Ok(Box::new(SLLIW::new(inst))),
+            (0b101, 0b0000000) => Ok(Box::new(SRLIW::new(inst))),
+            (0b101, 0b0100000) => Ok(Box::new(SRAIW::new(inst))),
+            _ => self.decode_inst_op_00110_b(inst),
+        }
     }
-    fn decode_inst_op_00100(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
+    fn decode_inst_op_01000(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
         let funct = x(inst, 12, 3);
         match funct {
-            0b000 => Ok(Box::new(ADDI::new(inst))),
-            0b010 => Ok(Box::new(SLTI::new(inst))),
-            0b011 => Ok(Box::new(SLTIU::new(inst))),
-            0b100 => Ok(Box::new(XORI::new(inst))),
-            0b110 => Ok(Box::new(ORI::new(inst))),
-            0b111 => Ok(Box::new(ANDI::new(inst))),
-            _ => panic!(),
+            0b000 => Ok(Box::new(SB::new(inst))),
+            0b001 => Ok(Box::new(SH::new(inst))),
+            0b010 => Ok(Box::new(SW::new(inst))),
+            0b011 if self.xlen != RV32 => Ok(Box::new(SD::new(inst))),
+            _ => illegal_instruction(inst),
         }
     }
-    fn decode_inst_op_00110(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
-        panic!()
+    fn decode_inst_op_01001(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let flag = x(inst, 12, 3);
+        match flag {
+            0b010 => Ok(Box::new(FSW::new(inst))),
+            0b011 if self.has_extension("d") => Ok(Box::new(FSD::new(inst))),
+            _ => illegal_instruction(inst),
+        }
     }
-    fn decode_inst_op_01000(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
-        panic!()
+    fn decode_inst_op_01011(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let funct5 = x(inst, 27, 5);
+        let rs2 = x(inst, 20, 5);
+        match (funct3, funct5) {
+            (0b010, 0b00010) if rs2 == 0 => Ok(Box::new(LR_W::new(inst))),
+            (0b010, 0b00011) => Ok(Box::new(SC_W::new(inst))),
+            (0b010, 0b00001) => Ok(Box::new(AMOSWAP_W::new(inst))),
+            (0b010, 0b00000) => Ok(Box::new(AMOADD_W::new(inst))),
+            (0b010, 0b00100) => Ok(Box::new(AMOXOR_W::new(inst))),
+            (0b010, 0b01100) => Ok(Box::new(AMOAND_W::new(inst))),
+            (0b010, 0b01000) => Ok(Box::new(AMOOR_W::new(inst))),
+            (0b010, 0b10000) => Ok(Box::new(AMOMIN_W::new(inst))),
+            (0b010, 0b10100) => Ok(Box::new(AMOMAX_W::new(inst))),
+            (0b010, 0b11000) => Ok(Box::new(AMOMINU_W::new(inst))),
+            (0b010, 0b11100) => Ok(Box::new(AMOMAXU_W::new(inst))),
+            (0b011, _) if self.xlen == RV32 => illegal_instruction(inst),
+            (0b011, 0b00010) if rs2 == 0 => Ok(Box::new(LR_D::new(inst))),
+            (0b011, 0b00011) => Ok(Box::new(SC_D::new(inst))),
+            (0b011, 0b00001) => Ok(Box::new(AMOSWAP_D::new(inst))),
+            (0b011, 0b00000) => Ok(Box::new(AMOADD_D::new(inst))),
+            (0b011, 0b00100) => Ok(Box::new(AMOXOR_D::new(inst))),
+            (0b011, 0b01100) => Ok(Box::new(AMOAND_D::new(inst))),
+            (0b011, 0b01000) => Ok(Box::new(AMOOR_D::new(inst))),
+            (0b011, 0b10000) => Ok(Box::new(AMOMIN_D::new(inst))),
+            (0b011, 0b10100) => Ok(Box::new(AMOMAX_D::new(inst))),
+            (0b011, 0b11000) => Ok(Box::new(AMOMINU_D::new(inst))),
+            (0b011, 0b11100) => Ok(Box::new(AMOMAXU_D::new(inst))),
+            _ => illegal_instruction(inst),
+        }
     }
-    fn decode_inst_op_01100(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
-        panic!()
+    fn decode_inst_op_01100(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let funct7 = x(inst, 25, 7);
+        match (funct3, funct7) {
+            (_, 0b0000001) if !self.has_extension("m") => illegal_instruction(inst),
+            (0b000, 0b0000000) => Ok(Box::new(ADD::new(inst))),
+            (0b000, 0b0100000) => Ok(Box::new(SUB::new(inst))),
+            (0b001, 0b0000000) => Ok(Box::new(SLL::new(inst))),
+            (0b010, 0b0000000) => Ok(Box::new(SLT::new(inst))),
+            (0b011, 0b0000000) => Ok(Box::new(SLTU::new(inst))),
+            (0b100, 0b0000000) => Ok(Box::new(XOR::new(inst))),
+            (0b101, 0b0000000) => Ok(Box::new(SRL::new(inst))),
+            (0b101, 0b0100000) => Ok(Box::new(SRA::new(inst))),
+            (0b110, 0b0000000) => Ok(Box::new(OR::new(inst))),
+            (0b111, 0b0000000) => Ok(Box::new(AND::new(inst))),
+            (0b000, 0b0000001) => Ok(Box::new(MUL::new(inst))),
+            (0b001, 0b0000001) => Ok(Box::new(MULH::new(inst))),
+            (0b010, 0b0000001) => Ok(Box::new(MULHSU::new(inst))),
+            (0b011, 0b0000001) => Ok(Box::new(MULHU::new(inst))),
+            (0b100, 0b0000001) => Ok(Box::new(DIV::new(inst))),
+            (0b101, 0b0000001) => Ok(Box::new(DIVU::new(inst))),
+            (0b110, 0b0000001) => Ok(Box::new(REM::new(inst))),
+            (0b111, 0b0000001) => Ok(Box::new(REMU::new(inst))),
+            _ => self.decode_inst_op_01100_b(inst),
+        }
+    }
+    fn decode_inst_op_01110(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let funct7 = x(inst, 25, 7);
+        match (funct3, funct7) {
+            (_, 0b0000001) if !self.has_extension("m") => illegal_instruction(inst),
+            (0b000, 0b0000000) => Ok(Box::new(ADDW::new(inst))),
+            (0b000, 0b0100000) => Ok(Box::new(SUBW::new(inst))),
+            (0b001, 0b0000000) => Ok(Box::new(SLLW::new(inst))),
+            (0b101, 0b0000000) => Ok(Box::new(SRLW::new(inst))),
+            (0b101, 0b0100000) => Ok(Box::new(SRAW::new(inst))),
+            (0b000, 0b0000001) => Ok(Box::new(MULW::new(inst))),
+            (0b100, 0b0000001) => Ok(Box::new(DIVW::new(inst))),
+            (0b101, 0b0000001) => Ok(Box::new(DIVUW::new(inst))),
+            (0b110, 0b0000001) => Ok(Box::new(REMW::new(inst))),
+            (0b111, 0b0000001) => Ok(Box::new(REMUW::new(inst))),
+            _ => self.decode_inst_op_01110_b(inst),
+        }
+    }
+    /// Zba, Zbb and Zbs encodings in OP-IMM.
+    fn decode_inst_op_00100_b(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let imm = x(inst, 20, 12);
+        // shamt[5] is reserved on RV32, as for the base shifts.
+        let funct6 = match self.xlen {
+            RV32 => x(inst, 26, 6) | (x(inst, 25, 1) << 6),
+            RV64 | RV128 => x(inst, 26, 6),
+        };
+        let zbb = self.has_extension("zbb");
+        let zbkb = self.has_extension("zbkb");
+        let zbs = self.has_extension("zbs");
+        match (funct3, imm, funct6) {
+            (0b001, 0x600, _) if zbb => Ok(Box::new(CLZ::new(inst))),
+            (0b001, 0x601, _) if zbb => Ok(Box::new(CTZ::new(inst))),
+            (0b001, 0x602, _) if zbb => Ok(Box::new(CPOP::new(inst))),
+            (0b001, 0x604, _) if zbb => Ok(Box::new(SEXT_B::new(inst))),
+            (0b001, 0x605, _) if zbb => Ok(Box::new(SEXT_H::new(inst))),
+            (0b101, 0x287, _) if zbb => Ok(Box::new(ORC_B::new(inst))),
+            (0b101, 0x698, _) if (zbb || zbkb) && self.xlen == RV32 => {
+                Ok(Box::new(REV8::new(inst)))
+            }
+            (0b101, 0x6b8, _) if (zbb || zbkb) && self.xlen != RV32 => {
+                Ok(Box::new(REV8::new(inst)))
+            }
+            (0b101, _, 0b011000) if zbb || zbkb => Ok(Box::new(RORI::new(inst))),
+            (0b001, _, 0b010010) if zbs => Ok(Box::new(BCLRI::new(inst))),
+            (0b101, _, 0b010010) if zbs => Ok(Box::new(BEXTI::new(inst))),
+            (0b001, _, 0b011010) if zbs => Ok(Box::new(BINVI::new(inst))),
+            (0b001, _, 0b001010) if zbs => Ok(Box::new(BSETI::new(inst))),
+            _ => self.decode_inst_op_00100_k(inst),
+        }
+    }
+    /// Zba, Zbb and Zbkb encodings in OP-IMM-32.
+    fn decode_inst_op_00110_b(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let imm = x(inst, 20, 12);
+        let zbb = self.has_extension("zbb");
+        let zbkb = self.has_extension("zbkb");
+        match (funct3, imm, x(inst, 26, 6)) {
+            (0b001, _, 0b000010) if self.has_extension("zba") => Ok(Box::new(SLLI_UW::new(inst))),
+            (0b001, 0x600, _) if zbb => Ok(Box::new(CLZW::new(inst))),
+            (0b001, 0x601, _) if zbb => Ok(Box::new(CTZW::new(inst))),
+            (0b001, 0x602, _) if zbb => Ok(Box::new(CPOPW::new(inst))),
+            (0b101, _, _) if (zbb || zbkb) && x(inst, 25, 7) == 0b0110000 => {
+                Ok(Box::new(RORIW::new(inst)))
+            }
+            _ => illegal_instruction(inst),
+        }
+    }
+    /// Zba, Zbb, Zbc and Zbs encodings in OP.
+    fn decode_inst_op_01100_b(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let funct7 = x(inst, 25, 7);
+        let zba = self.has_extension("zba");
+        let zbb = self.has_extension("zbb");
+        let zbc = self.has_extension("zbc");
+        let zbs = self.has_extension("zbs");
+        let zbkb = self.has_extension("zbkb");
+        let zbkc = self.has_extension("zbkc");
+        match (funct7, funct3) {
+            (0b0010000, 0b010) if zba => Ok(Box::new(SH1ADD::new(inst))),
+            (0b0010000, 0b100) if zba => Ok(Box::new(SH2ADD::new(inst))),
+            (0b0010000, 0b110) if zba => Ok(Box::new(SH3ADD::new(inst))),
+            (0b0100000, 0b111) if zbb || zbkb => Ok(Box::new(ANDN::new(inst))),
+            (0b0100000, 0b110) if zbb || zbkb => Ok(Box::new(ORN::new(inst))),
+            (0b0100000, 0b100) if zbb || zbkb => Ok(Box::new(XNOR::new(inst))),
+            (0b0000101, 0b110) if zbb => Ok(Box::new(MAX::new(inst))),
+            (0b0000101, 0b111) if zbb => Ok(Box::new(MAXU::new(inst))),
+            (0b0000101, 0b100) if zbb => Ok(Box::new(MIN::new(inst))),
+            (0b0000101, 0b101) if zbb => Ok(Box::new(MINU::new(inst))),
+            (0b0110000, 0b001) if zbb || zbkb => Ok(Box::new(ROL::new(inst))),
+            (0b0110000, 0b101) if zbb || zbkb => Ok(Box::new(ROR::new(inst))),
+            (0b0000100, 0b100) if zbb && self.xlen == RV32 && x(inst, 20, 5) == 0 => {
+                Ok(Box::new(ZEXT_H::new(inst)))
+            }
+            (0b0000101, 0b001) if zbc || zbkc => Ok(Box::new(CLMUL::new(inst))),
+            (0b0000101, 0b010) if zbc => Ok(Box::new(CLMULR::new(inst))),
+            (0b0000101, 0b011) if zbc || zbkc => Ok(Box::new(CLMULH::new(inst))),
+            (0b0100100, 0b001) if zbs => Ok(Box::new(BCLR::new(inst))),
+            (0b0100100, 0b101) if zbs => Ok(Box::new(BEXT::new(inst))),
+            (0b0110100, 0b001) if zbs => Ok(Box::new(BINV::new(inst))),
+            (0b0010100, 0b001) if zbs => Ok(Box::new(BSET::new(inst))),
+            _ => self.decode_inst_op_01100_k(inst),
+        }
+    }
+    /// Zba, Zbb and Zbkb encodings in OP-32.
+    fn decode_inst_op_01110_b(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let funct7 = x(inst, 25, 7);
+        let zba = self.has_extension("zba");
+        let zbb = self.has_extension("zbb");
+        let zbkb = self.has_extension("zbkb");
+        match (funct7, funct3) {
+            (0b0000100, 0b000) if zba => Ok(Box::new(ADD_UW::new(inst))),
+            (0b0010000, 0b010) if zba => Ok(Box::new(SH1ADD_UW::new(inst))),
+            (0b0010000, 0b100) if zba => Ok(Box::new(SH2ADD_UW::new(inst))),
+            (0b0010000, 0b110) if zba => Ok(Box::new(SH3ADD_UW::new(inst))),
+            (0b0000100, 0b100) if zbb && x(inst, 20, 5) == 0 => Ok(Box::new(ZEXT_H::new(inst))),
+            (0b0000100, 0b100) if zbkb => Ok(Box::new(PACKW::new(inst))),
+            (0b0110000, 0b001) if zbb || zbkb => Ok(Box::new(ROLW::new(inst))),
+            (0b0110000, 0b101) if zbb || zbkb => Ok(Box::new(RORW::new(inst))),
+            _ => illegal_instruction(inst),
+        }
+    }
+    /// Scalar crypto encodings in OP-IMM.
+    fn decode_inst_op_00100_k(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let imm = x(inst, 20, 12);
+        let rv32 = self.xlen == RV32;
+        let zbkb = self.has_extension("zbkb");
+        let zknd = self.has_extension("zknd");
+        let zkne = self.has_extension("zkne");
+        let zknh = self.has_extension("zknh");
+        let zksh = self.has_extension("zksh");
+        match (funct3, imm) {
+            (0b101, 0x687) if zbkb => Ok(Box::new(BREV8::new(inst))),
+            (0b001, 0x08f) if zbkb && rv32 => Ok(Box::new(ZIP::new(inst))),
+            (0b101, 0x08f) if zbkb && rv32 => Ok(Box::new(UNZIP::new(inst))),
+            (0b001, 0x300) if zknd && !rv32 => Ok(Box::new(AES64IM::new(inst))),
+            // rnum values above 0xa are reserved.
+            (0b001, 0x310..=0x31a) if (zknd || zkne) && !rv32 => Ok(Box::new(AES64KS1I::new(inst))),
+            (0b001, 0x100) if zknh => Ok(Box::new(SHA256SUM0::new(inst))),
+            (0b001, 0x101) if zknh => Ok(Box::new(SHA256SUM1::new(inst))),
+            (0b001, 0x102) if zknh => Ok(Box::new(SHA256SIG0::new(inst))),
+            (0b001, 0x103) if zknh => Ok(Box::new(SHA256SIG1::new(inst))),
+            (0b001, 0x104) if zknh && !rv32 => Ok(Box::new(SHA512SUM0::new(inst))),
+            (0b001, 0x105) if zknh && !rv32 => Ok(Box::new(SHA512SUM1::new(inst))),
+            (0b001, 0x106) if zknh && !rv32 => Ok(Box::new(SHA512SIG0::new(inst))),
+            (0b001, 0x107) if zknh && !rv32 => Ok(Box::new(SHA512SIG1::new(inst))),
+            (0b001, 0x108) if zksh => Ok(Box::new(SM3P0::new(inst))),
+            (0b001, 0x109) if zksh => Ok(Box::new(SM3P1::new(inst))),
+            _ => illegal_instruction(inst),
+        }
     }
-    fn decode_inst_op_11000(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
+    /// Scalar crypto encodings in OP.
+    fn decode_inst_op_01100_k(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let funct7 = x(inst, 25, 7);
+        let rv32 = self.xlen == RV32;
+        let zbkb = self.has_extension("zbkb");
+        let zbkx = self.has_extension("zbkx");
+        let zknd = self.has_extension("zknd");
+        let zkne = self.has_extension("zkne");
+        let zknh = self.has_extension("zknh");
+        let zksed = self.has_extension("zksed");
+        match (funct7, funct3) {
+            (0b0000100, 0b100) if zbkb => Ok(Box::new(PACK::new(inst))),
+            (0b0000100, 0b111) if zbkb => Ok(Box::new(PACKH::new(inst))),
+            (0b0010100, 0b010) if zbkx => Ok(Box::new(XPERM4::new(inst))),
+            (0b0010100, 0b100) if zbkx => Ok(Box::new(XPERM8::new(inst))),
+            (0b0011001, 0b000) if zkne && !rv32 => Ok(Box::new(AES64ES::new(inst))),
+            (0b0011011, 0b000) if zkne && !rv32 => Ok(Box::new(AES64ESM::new(inst))),
+            (0b0011101, 0b000) if zknd && !rv32 => Ok(Box::new(AES64DS::new(inst))),
+            (0b0011111, 0b000) if zknd && !rv32 => Ok(Box::new(AES64DSM::new(inst))),
+            (0b0111111, 0b000) if (zknd || zkne) && !rv32 => Ok(Box::new(AES64KS2::new(inst))),
+            (0b0101110, 0b000) if zknh && rv32 => Ok(Box::new(SHA512SIG0H::new(inst))),
+            (0b0101010, 0b000) if zknh && rv32 => Ok(Box::new(SHA512SIG0L::new(inst))),
+            (0b0101111, 0b000) if zknh && rv32 => Ok(Box::new(SHA512SIG1H::new(inst))),
+            (0b0101011, 0b000) if zknh && rv32 => Ok(Box::new(SHA512SIG1L::new(inst))),
+            (0b0101000, 0b000) if zknh && rv32 => Ok(Box::new(SHA512SUM0R::new(inst))),
+            (0b0101001, 0b000) if zknh && rv32 => Ok(Box::new(SHA512SUM1R::new(inst))),
+            // The top two bits of funct7 select the byte (bs) these operate on.
+            (_, 0b000) => match funct7 & 0b11111 {
+                0b10001 if zkne && rv32 => Ok(Box::new(AES32ESI::new(inst))),
+                0b10011 if zkne && rv32 => Ok(Box::new(AES32ESMI::new(inst))),
+                0b10101 if zknd && rv32 => Ok(Box::new(AES32DSI::new(inst))),
+                0b10111 if zknd && rv32 => Ok(Box::new(AES32DSMI::new(inst))),
+                0b11000 if zksed => Ok(Box::new(SM4ED::new(inst))),
+                0b11010 if zksed => Ok(Box::new(SM4KS::new(inst))),
+                _ => illegal_instruction(inst),
+            },
+            _ => illegal_instruction(inst),
+        }
+    }
+    fn decode_inst_op_100xx(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let opcode = x(inst, 2, 5);
+        let fmt = x(inst, 25, 2);
+        if !valid_rm(inst) || (fmt == 0b01 && !self.has_extension("d")) {
+            return illegal_instruction(inst);
+        }
+        match (opcode, fmt) {
+            (0x10, 0b00) => Ok(Box::new(FMADD_S::new(inst))),
+            (0x11, 0b00) => Ok(Box::new(FMSUB_S::new(inst))),
+            (0x12, 0b00) => Ok(Box::new(FNMSUB_S::new(inst))),
+            (0x13, 0b00) => Ok(Box::new(FNMADD_S::new(inst))),
+            (0x10, 0b01) => Ok(Box::new(FMADD_D::new(inst))),
+            (0x11, 0b01) => Ok(Box::new(FMSUB_D::new(inst))),
+            (0x12, 0b01) => Ok(Box::new(FNMSUB_D::new(inst))),
+            (0x13, 0b01) => Ok(Box::new(FNMADD_D::new(inst))),
+            _ => illegal_instruction(inst),
+        }
+    }
+    fn decode_inst_op_10100(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let rs2 = x(inst, 20, 5);
+        let funct7 = x(inst, 25, 7);
+        let rv64 = self.xlen != RV32;
+        let rm = valid_rm(inst);
+        let d = self.has_extension("d");
+        // The low two bits of funct7 give the format; fcvt.s.d is the one D instruction with
+        // an S destination.
+        if (funct7 & 0b11 == 0b01 || (funct7, rs2) == (0x20, 1)) && !d {
+            return illegal_instruction(inst);
+        }
+        match (funct7, rs2, funct3) {
+            (0x00, _, _) if rm => Ok(Box::new(FADD_S::new(inst))),
+            (0x04, _, _) if rm => Ok(Box::new(FSUB_S::new(inst))),
+            (0x08, _, _) if rm => Ok(Box::new(FMUL_S::new(inst))),
+            (0x0c, _, _) if rm => Ok(Box::new(FDIV_S::new(inst))),
+            (0x2c, 0, _) if rm => Ok(Box::new(FSQRT_S::new(inst))),
+            (0x10, _, 0b000) => Ok(Box::new(FSGNJ_S::new(inst))),
+            (0x10, _, 0b001) => Ok(Box::new(FSGNJN_S::new(inst))),
+            (0x10, _, 0b010) => Ok(Box::new(FSGNJX_S::new(inst))),
+            (0x14, _, 0b000) => Ok(Box::new(FMIN_S::new(inst))),
+            (0x14, _, 0b001) => Ok(Box::new(FMAX_S::new(inst))),
+            (0x50, _, 0b010) => Ok(Box::new(FEQ_S::new(inst))),
+            (0x50, _, 0b001) => Ok(Box::new(FLT_S::new(inst))),
+            (0x50, _, 0b000) => Ok(Box::new(FLE_S::new(inst))),
+            (0x60, 0, _) if rm => Ok(Box::new(FCVT_W_S::new(inst))),
+            (0x60, 1, _) if rm => Ok(Box::new(FCVT_WU_S::new(inst))),
+            (0x60, 2, _) if rm && rv64 => Ok(Box::new(FCVT_L_S::new(inst))),
+            (0x60, 3, _) if rm && rv64 => Ok(Box::new(FCVT_LU_S::new(inst))),
+            (0x68, 0, _) if rm => Ok(Box::new(FCVT_S_W::new(inst))),
+            (0x68, 1, _) if rm => Ok(Box::new(FCVT_S_WU::new(inst))),
+            (0x68, 2, _) if rm && rv64 => Ok(Box::new(FCVT_S_L::new(inst))),
+            (0x68, 3, _) if rm && rv64 => Ok(Box::new(FCVT_S_LU::new(inst))),
+            (0x70, 0, 0b000) => Ok(Box::new(FMV_X_W::new(inst))),
+            (0x70, 0, 0b001) => Ok(Box::new(FCLASS_S::new(inst))),
+            (0x78, 0, 0b000) => Ok(Box::new(FMV_W_X::new(inst))),
+
+            (0x01, _, _) if rm => Ok(Box::new(FADD_D::new(inst))),
+            (0x05, _, _) if rm => Ok(Box::new(FSUB_D::new(inst))),
+            (0x09, _, _) if rm => Ok(Box::new(FMUL_D::new(inst))),
+            (0x0d, _, _) if rm => Ok(Box::new(FDIV_D::new(inst))),
+            (0x2d, 0, _) if rm => Ok(Box::new(FSQRT_D::new(inst))),
+            (0x11, _, 0b000) => Ok(Box::new(FSGNJ_D::new(inst))),
+            (0x11, _, 0b001) => Ok(Box::new(FSGNJN_D::new(inst))),
+            (0x11, _, 0b010) => Ok(Box::new(FSGNJX_D::new(inst))),
+            (0x15, _, 0b000) => Ok(Box::new(FMIN_D::new(inst))),
+            (0x15, _, 0b001) => Ok(Box::new(FMAX_D::new(inst))),
+            (0x20, 1, _) if rm => Ok(Box::new(FCVT_S_D::new(inst))),
+            (0x21, 0, _) if rm => Ok(Box::new(FCVT_D_S::new(inst))),
+            (0x51, _, 0b010) => Ok(Box::new(FEQ_D::new(inst))),
+            (0x51, _, 0b001) => Ok(Box::new(FLT_D::new(inst))),
+            (0x51, _, 0b000) => Ok(Box::new(FLE_D::new(inst))),
+            (0x61, 0, _) if rm => Ok(Box::new(FCVT_W_D::new(inst))),
+            (0x61, 1, _) if rm => Ok(Box::new(FCVT_WU_D::new(inst))),
+            (0x61, 2, _) if rm && rv64 => Ok(Box::new(FCVT_L_D::new(inst))),
+            (0x61, 3, _) if rm && rv64 => Ok(Box::new(FCVT_LU_D::new(inst))),
+            (0x69, 0, _) if rm => Ok(Box::new(FCVT_D_W::new(inst))),
+            (0x69, 1, _) if rm => Ok(Box::new(FCVT_D_WU::new(inst))),
+            (0x69, 2, _) if rm && rv64 => Ok(Box::new(FCVT_D_L::new(inst))),
+            (0x69, 3, _) if rm && rv64 => Ok(Box::new(FCVT_D_LU::new(inst))),
+            (0x71, 0, 0b000) if rv64 => Ok(Box::new(FMV_X_D::new(inst))),
+            (0x71, 0, 0b001) => Ok(Box::new(FCLASS_D::new(inst))),
+            (0x79, 0, 0b000) if rv64 => Ok(Box::new(FMV_D_X::new(inst))),
+            _ => illegal_instruction(inst),
+        }
+    }
+    fn decode_inst_vector_memory(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let store = x(inst, 2, 5) == 0x09;
+        let eew = match x(inst, 12, 3) {
+            0b000 => 8,
+            0b101 => 16,
+            0b110 => 32,
+            _ => 64,
+        };
+        let vm = x(inst, 25, 1) == 1;
+        let nf = x(inst, 29, 3) + 1;
+        // mew selects the reserved 128-bit and wider element widths.
+        if x(inst, 28, 1) != 0 || eew > self.elen {
+            return illegal_instruction(inst);
+        }
+        let addressing = match x(inst, 26, 2) {
+            0b00 => match x(inst, 20, 5) {
+                0b00000 => VAddressing::UnitStride,
+                0b01000 if vm && (nf as u64).is_power_of_two() && (!store || eew == 8) => {
+                    VAddressing::WholeRegister
+                }
+                0b01011 if vm && nf == 1 && eew == 8 => VAddressing::Mask,
+                0b10000 if !store => VAddressing::FaultOnlyFirst,
+                _ => return illegal_instruction(inst),
+            },
+            0b01 => VAddressing::Indexed {
+                vs2: VR::from_num(x(inst, 20, 5)),
+                ordered: false,
+            },
+            0b10 => VAddressing::Strided(XprName::from_num(x(inst, 20, 5))),
+            _ => VAddressing::Indexed {
+                vs2: VR::from_num(x(inst, 20, 5)),
+                ordered: true,
+            },
+        };
+        if store {
+            Ok(Box::new(VStore::new(inst, addressing, eew)))
+        } else {
+            Ok(Box::new(VLoad::new(inst, addressing, eew)))
+        }
+    }
+    /// OP-V: funct3 picks the vector configuration instructions or the operand types.
+    fn decode_inst_op_10101(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        match x(inst, 12, 3) {
+            OPCFG => match (x(inst, 31, 1), x(inst, 30, 1), x(inst, 25, 6)) {
+                (0, _, _) => Ok(Box::new(VSETVLI::new(inst))),
+                (1, 1, _) => Ok(Box::new(VSETIVLI::new(inst))),
+                (1, 0, 0) => Ok(Box::new(VSETVL::new(inst))),
+                _ => illegal_instruction(inst),
+            },
+            OPIVV | OPIVX | OPIVI => self.decode_inst_op_10101_i(inst),
+            OPMVV | OPMVX => self.decode_inst_op_10101_m(inst),
+            // Vector floating point also needs the scalar FP unit switched on.
+            _ if !self.fp_enabled() => illegal_instruction(inst),
+            _ => self.decode_inst_op_10101_f(inst),
+        }
+    }
+    fn decode_inst_op_10101_i(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let vm = x(inst, 25, 1) == 1;
+        let vs2 = x(inst, 20, 5);
+        let int = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VIntBinary::new(inst, op)))
+        };
+        let compare = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VIntCompare::new(inst, op)))
+        };
+        let narrow = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VIntNarrow::new(inst, op)))
+        };
+        let slide =
+            |op| -> Result<Box<dyn Instruction>, Exception> { Ok(Box::new(VSlide::new(inst, op))) };
+        match (x(inst, 26, 6), funct3) {
+            (0b000000, _) => int(VIntOp::vadd),
+            (0b000010, OPIVV | OPIVX) => int(VIntOp::vsub),
+            (0b000011, OPIVX | OPIVI) => int(VIntOp::vrsub),
+            (0b000100, OPIVV | OPIVX) => int(VIntOp::vminu),
+            (0b000101, OPIVV | OPIVX) => int(VIntOp::vmin),
+            (0b000110, OPIVV | OPIVX) => int(VIntOp::vmaxu),
+            (0b000111, OPIVV | OPIVX) => int(VIntOp::vmax),
+            (0b001001, _) => int(VIntOp::vand),
+            (0b001010, _) => int(VIntOp::vor),
+            (0b001011, _) => int(VIntOp::vxor),
+            (0b001100, _) => Ok(Box::new(VGather::new(inst, false))),
+            (0b001110, OPIVV) => Ok(Box::new(VGather::new(inst, true))),
+            (0b001110, _) => slide(VSlideOp::vslideup),
+            (0b001111, OPIVX | OPIVI) => slide(VSlideOp::vslidedown),
+            (0b010000, _) if !vm => int(VIntOp::vadc),
+            (0b010001, _) => compare(VIntCompareOp::vmadc),
+            (0b010010, OPIVV | OPIVX) if !vm => int(VIntOp::vsbc),
+            (0b010011, OPIVV | OPIVX) => compare(VIntCompareOp::vmsbc),
+            (0b010111, _) if !vm => int(VIntOp::vmerge),
+            (0b010111, _) if vs2 == 0 => int(VIntOp::vmv),
+            (0b011000, _) => compare(VIntCompareOp::vmseq),
+            (0b011001, _) => compare(VIntCompareOp::vmsne),
+            (0b011010, OPIVV | OPIVX) => compare(VIntCompareOp::vmsltu),
+            (0b011011, OPIVV | OPIVX) => compare(VIntCompareOp::vmslt),
+            (0b011100, _) => compare(VIntCompareOp::vmsleu),
+            (0b011101, _) => compare(VIntCompareOp::vmsle),
+            (0b011110, OPIVX | OPIVI) => compare(VIntCompareOp::vmsgtu),
+            (0b011111, OPIVX | OPIVI) => compare(VIntCompareOp::vmsgt),
+            (0b100000, _) => int(VIntOp::vsaddu),
+            (0b100001, _) => int(VIntOp::vsadd),
+            (0b100010, OPIVV | OPIVX) => int(VIntOp::vssubu),
+            (0b100011, OPIVV | OPIVX) => int(VIntOp::vssub),
+            (0b100101, _) => int(VIntOp::vsll),
+            (0b100111, OPIVV | OPIVX) => int(VIntOp::vsmul),
+            (0b100111, OPIVI) if vm && matches!(x(inst, 15, 5), 0 | 1 | 3 | 7) => {
+                Ok(Box::new(VMV_NR_R::new(inst)))
+            }
+            (0b101000, _) => int(VIntOp::vsrl),
+            (0b101001, _) => int(VIntOp::vsra),
+            (0b101010, _) => int(VIntOp::vssrl),
+            (0b101011, _) => int(VIntOp::vssra),
+            (0b101100, _) => narrow(VIntNarrowOp::vnsrl),
+            (0b101101, _) => narrow(VIntNarrowOp::vnsra),
+            (0b101110, _) => narrow(VIntNarrowOp::vnclipu),
+            (0b101111, _) => narrow(VIntNarrowOp::vnclip),
+            (0b110000, OPIVV) => Ok(Box::new(VIntReduce::new(inst, VIntReduceOp::vwredsumu))),
+            (0b110001, OPIVV) => Ok(Box::new(VIntReduce::new(inst, VIntReduceOp::vwredsum))),
+            _ => illegal_instruction(inst),
+        }
+    }
+    fn decode_inst_op_10101_m(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let vm = x(inst, 25, 1) == 1;
+        let vs1 = x(inst, 15, 5);
+        let vs2 = x(inst, 20, 5);
+        let int = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VIntBinary::new(inst, op)))
+        };
+        let widen = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VIntWiden::new(inst, op)))
+        };
+        let reduce = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VIntReduce::new(inst, op)))
+        };
+        let logical = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VMaskLogical::new(inst, op)))
+        };
+        let mask_set = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VMaskSet::new(inst, op)))
+        };
+        match (x(inst, 26, 6), funct3) {
+            (0b000000, OPMVV) => reduce(VIntReduceOp::vredsum),
+            (0b000001, OPMVV) => reduce(VIntReduceOp::vredand),
+            (0b000010, OPMVV) => reduce(VIntReduceOp::vredor),
+            (0b000011, OPMVV) => reduce(VIntReduceOp::vredxor),
+            (0b000100, OPMVV) => reduce(VIntReduceOp::vredminu),
+            (0b000101, OPMVV) => reduce(VIntReduceOp::vredmin),
+            (0b000110, OPMVV) => reduce(VIntReduceOp::vredmaxu),
+            (0b000111, OPMVV) => reduce(VIntReduceOp::vredmax),
+            (0b001000, _) => int(VIntOp::vaaddu),
+            (0b001001, _) => int(VIntOp::vaadd),
+            (0b001010, _) => int(VIntOp::vasubu),
+            (0b001011, _) => int(VIntOp::vasub),
+            (0b001110, OPMVX) => Ok(Box::new(VSlide::new(inst, VSlideOp::vslide1up))),
+            (0b001111, OPMVX) => Ok(Box::new(VSlide::new(inst, VSlideOp::vslide1down))),
+            (0b010000, OPMVV) => match vs1 {
+                0b00000 if vm => Ok(Box::new(VMV_X_S::new(inst))),
+                0b10000 => Ok(Box::new(VCPOP_M::new(inst))),
+                0b10001 => Ok(Box::new(VFIRST_M::new(inst))),
+                _ => illegal_instruction(inst),
+            },
+            (0b010000, OPMVX) if vm && vs2 == 0 => Ok(Box::new(VMV_S_X::new(inst))),
+            (0b010010, OPMVV) if (0b00010..=0b00111).contains(&vs1) => {
+                Ok(Box::new(VIntExtend::new(inst)))
+            }
+            (0b010100, OPMVV) => match vs1 {
+                0b00001 => mask_set(VMaskSetOp::vmsbf),
+                0b00010 => mask_set(VMaskSetOp::vmsof),
+                0b00011 => mask_set(VMaskSetOp::vmsif),
+                0b10000 => Ok(Box::new(VIOTA_M::new(inst))),
+                0b10001 if vs2 == 0 => Ok(Box::new(VID_V::new(inst))),
+                _ => illegal_instruction(inst),
+            },
+            (0b010111, OPMVV) if vm => Ok(Box::new(VCOMPRESS_VM::new(inst))),
+            (0b011000..=0b011111, OPMVV) if vm => match x(inst, 26, 3) {
+                0b000 => logical(VMaskOp::vmandn),
+                0b001 => logical(VMaskOp::vmand),
+                0b010 => logical(VMaskOp::vmor),
+                0b011 => logical(VMaskOp::vmxor),
+                0b100 => logical(VMaskOp::vmorn),
+                0b101 => logical(VMaskOp::vmnand),
+                0b110 => logical(VMaskOp::vmnor),
+                _ => logical(VMaskOp::vmxnor),
+            },
+            (0b100000, _) => int(VIntOp::vdivu),
+            (0b100001, _) => int(VIntOp::vdiv),
+            (0b100010, _) => int(VIntOp::vremu),
+            (0b100011, _) => int(VIntOp::vrem),
+            (0b100100, _) => int(VIntOp::vmulhu),
+            (0b100101, _) => int(VIntOp::vmul),
+            (0b100110, _) => int(VIntOp::vmulhsu),
+            (0b100111, _) => int(VIntOp::vmulh),
+            (0b101001, _) => int(VIntOp::vmadd),
+            (0b101011, _) => int(VIntOp::vnmsub),
+            (0b101101, _) => int(VIntOp::vmacc),
+            (0b101111, _) => int(VIntOp::vnmsac),
+            (0b110000, _) => widen(VIntWidenOp::vwaddu),
+            (0b110001, _) => widen(VIntWidenOp::vwadd),
+            (0b110010, _) => widen(VIntWidenOp::vwsubu),
+            (0b110011, _) => widen(VIntWidenOp::vwsub),
+            (0b110100, _) => widen(VIntWidenOp::vwaddu_w),
+            (0b110101, _) => widen(VIntWidenOp::vwadd_w),
+            (0b110110, _) => widen(VIntWidenOp::vwsubu_w),
+            (0b110111, _) => widen(VIntWidenOp::vwsub_w),
+            (0b111000, _) => widen(VIntWidenOp::vwmulu),
+            (0b111010, _) => widen(VIntWidenOp::vwmulsu),
+            (0b111011, _) => widen(VIntWidenOp::vwmul),
+            (0b111100, _) => widen(VIntWidenOp::vwmaccu),
+            (0b111101, _) => widen(VIntWidenOp::vwmacc),
+            (0b111110, OPMVX) => widen(VIntWidenOp::vwmaccus),
+            (0b111111, _) => widen(VIntWidenOp::vwmaccsu),
+            _ => illegal_instruction(inst),
+        }
+    }
+    fn decode_inst_op_10101_f(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        let vm = x(inst, 25, 1) == 1;
+        let vs1 = x(inst, 15, 5);
+        let vs2 = x(inst, 20, 5);
+        let float = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VFloatBinary::new(inst, op)))
+        };
+        let compare = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VFloatCompare::new(inst, op)))
+        };
+        let widen = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VFloatWiden::new(inst, op)))
+        };
+        let reduce = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VFloatReduce::new(inst, op)))
+        };
+        let unary = |op| -> Result<Box<dyn Instruction>, Exception> {
+            Ok(Box::new(VFloatUnary::new(inst, op)))
+        };
+        match (x(inst, 26, 6), funct3) {
+            (0b000000, _) => float(VFloatOp::vfadd),
+            (0b000001, OPFVV) => reduce(VFloatReduceOp::vfredusum),
+            (0b000010, _) => float(VFloatOp::vfsub),
+            (0b000011, OPFVV) => reduce(VFloatReduceOp::vfredosum),
+            (0b000100, _) => float(VFloatOp::vfmin),
+            (0b000101, OPFVV) => reduce(VFloatReduceOp::vfredmin),
+            (0b000110, _) => float(VFloatOp::vfmax),
+            (0b000111, OPFVV) => reduce(VFloatReduceOp::vfredmax),
+            (0b001000, _) => float(VFloatOp::vfsgnj),
+            (0b001001, _) => float(VFloatOp::vfsgnjn),
+            (0b001010, _) => float(VFloatOp::vfsgnjx),
+            (0b001110, OPFVF) => Ok(Box::new(VSlide::new(inst, VSlideOp::vslide1up))),
+            (0b001111, OPFVF) => Ok(Box::new(VSlide::new(inst, VSlideOp::vslide1down))),
+            (0b010000, OPFVV) if vm && vs1 == 0 => Ok(Box::new(VFMV_F_S::new(inst))),
+            (0b010000, OPFVF) if vm && vs2 == 0 => Ok(Box::new(VFMV_S_F::new(inst))),
+            (0b010010, OPFVV) => match VFloatConvertOp::n(vs1 as u8) {
+                Some(op) => Ok(Box::new(VFloatConvert::new(inst, op))),
+                None => illegal_instruction(inst),
+            },
+            (0b010011, OPFVV) => match vs1 {
+                0b00000 => unary(VFloatUnaryOp::vfsqrt),
+                0b00100 => unary(VFloatUnaryOp::vfrsqrt7),
+                0b00101 => unary(VFloatUnaryOp::vfrec7),
+                0b10000 => unary(VFloatUnaryOp::vfclass),
+                _ => illegal_instruction(inst),
+            },
+            (0b010111, OPFVF) if !vm => float(VFloatOp::vfmerge),
+            (0b010111, OPFVF) if vs2 == 0 => float(VFloatOp::vfmv),
+            (0b011000, _) => compare(VFloatCompareOp::vmfeq),
+            (0b011001, _) => compare(VFloatCompareOp::vmfle),
+            (0b011011, _) => compare(VFloatCompareOp::vmflt),
+            (0b011100, _) => compare(VFloatCompareOp::vmfne),
+            (0b011101, OPFVF) => compare(VFloatCompareOp::vmfgt),
+            (0b011111, OPFVF) => compare(VFloatCompareOp::vmfge),
+            (0b100000, _) => float(VFloatOp::vfdiv),
+            (0b100001, OPFVF) => float(VFloatOp::vfrdiv),
+            (0b100100, _) => float(VFloatOp::vfmul),
+            (0b100111, OPFVF) => float(VFloatOp::vfrsub),
+            (0b101000, _) => float(VFloatOp::vfmadd),
+            (0b101001, _) => float(VFloatOp::vfnmadd),
+            (0b101010, _) => float(VFloatOp::vfmsub),
+            (0b101011, _) => float(VFloatOp::vfnmsub),
+            (0b101100, _) => float(VFloatOp::vfmacc),
+            (0b101101, _) => float(VFloatOp::vfnmacc),
+            (0b101110, _) => float(VFloatOp::vfmsac),
+            (0b101111, _) => float(VFloatOp::vfnmsac),
+            (0b110000, _) => widen(VFloatWidenOp::vfwadd),
+            (0b110001, OPFVV) => reduce(VFloatReduceOp::vfwredusum),
+            (0b110010, _) => widen(VFloatWidenOp::vfwsub),
+            (0b110011, OPFVV) => reduce(VFloatReduceOp::vfwredosum),
+            (0b110100, _) => widen(VFloatWidenOp::vfwadd_w),
+            (0b110110, _) => widen(VFloatWidenOp::vfwsub_w),
+            (0b111000, _) => widen(VFloatWidenOp::vfwmul),
+            (0b111100, _) => widen(VFloatWidenOp::vfwmacc),
+            (0b111101, _) => widen(VFloatWidenOp::vfwnmacc),
+            (0b111110, _) => widen(VFloatWidenOp::vfwmsac),
+            (0b111111, _) => widen(VFloatWidenOp::vfwnmsac),
+            _ => illegal_instruction(inst),
+        }
+    }
+    fn decode_inst_op_11000(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
         let flag = x(inst, 12, 3);
         match flag {
             0b000 => Ok(Box::new(BEQ::new(inst))),
@@ -253,10 +1049,71 @@ impl State {
             0b101 => Ok(Box::new(BGE::new(inst))),
             0b110 => Ok(Box::new(BLTU::new(inst))),
             0b111 => Ok(Box::new(BGEU::new(inst))),
-            _ => panic!("unexpected branch"),
+            _ => illegal_instruction(inst),
+        }
+    }
+    fn decode_inst_privileged(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let mstatus = self.mstatus as u64;
+        let supervisor = self.prv == Privilege::Supervisor;
+        match inst {
+            0x00000073 => Ok(Box::new(ECALL::new(inst))),
+            0x00100073 => Ok(Box::new(EBREAK::new(inst))),
+            0x10200073 if self.prv == Privilege::Machine => Ok(Box::new(SRET::new(inst))),
+            0x10200073 if supervisor && mstatus & MSTATUS_TSR == 0 => Ok(Box::new(SRET::new(inst))),
+            0x30200073 if self.prv == Privilege::Machine => Ok(Box::new(MRET::new(inst))),
+            0x10500073 if self.prv == Privilege::Machine => Ok(Box::new(WFI::new(inst))),
+            0x10500073 if supervisor && mstatus & MSTATUS_TW == 0 => Ok(Box::new(WFI::new(inst))),
+            _ if x(inst, 25, 7) == 0b0001001 && x(inst, 7, 5) == 0 => {
+                if self.prv == Privilege::Machine || (supervisor && mstatus & MSTATUS_TVM == 0) {
+                    Ok(Box::new(SFENCE_VMA::new(inst)))
+                } else {
+                    illegal_instruction(inst)
+                }
+            }
+            _ => illegal_instruction(inst),
         }
     }
-    fn decode_inst_op_11100(&self, inst: u32) -> SimResult<Box<dyn Instruction>> {
-        panic!()
+    fn decode_inst_op_11100(&self, inst: u32) -> Result<Box<dyn Instruction>, Exception> {
+        let funct3 = x(inst, 12, 3);
+        if funct3 == 0b000 {
+            return self.decode_inst_privileged(inst);
+        }
+        let Some(csr) = CsrName::from_num(x(inst, 20, 12)) else {
+            return illegal_instruction(inst);
+        };
+        // csrrs/csrrc (and their immediate forms) with a zero source only read the CSR.
+        let write = funct3 & 0b11 == 0b01 || x(inst, 15, 5) != 0;
+        if !self.csr_accessible(csr, write) {
+            return illegal_instruction(inst);
+        }
+        match funct3 {
+            0b001 => Ok(Box::new(CSRRW::new(inst))),
+            0b010 => Ok(Box::new(CSRRS::new(inst))),
+            0b011 => Ok(Box::new(CSRRC::new(inst))),
+            0b101 => Ok(Box::new(CSRRWI::new(inst))),
+            0b110 => Ok(Box::new(CSRRSI::new(inst))),
+            0b111 => Ok(Box::new(CSRRCI::new(inst))),
+            _ => illegal_instruction(inst),
+        }
     }
 }
+
+// The OP-V funct3 values: integer (I), mask/multiply (M) and floating-point (F) operations
+// on vector-vector, vector-scalar and vector-immediate operands.
+const OPIVV: i64 = 0b000;
+const OPFVV: i64 = 0b001;
+const OPMVV: i64 = 0b010;
+const OPIVI: i64 = 0b011;
+const OPIVX: i64 = 0b100;
+const OPFVF: i64 = 0b101;
+const OPMVX: i64 = 0b110;
+const OPCFG: i64 = 0b111;
+
+/// Static rounding modes 0b101 and 0b110 are reserved.
+fn valid_rm(inst: u32) -> bool {
+    !matches!(x(inst, 12, 3), 0b101 | 0b110)
+}
+
+fn illegal_instruction<T>(inst: impl Into<u64>) -> Result<T, Exception> {
+    Err(Exception::IllegalInstruction(inst.into()))
+}
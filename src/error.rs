@@ -1,6 +1,7 @@
 use std::{error::Error, fmt, io};
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum SimError {
     ParseError(String),
     IoError(io::Error),
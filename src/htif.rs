@@ -0,0 +1,152 @@
+use std::io::{self, Read, Write};
+
+use crate::{
+    loader::SymbolTable,
+    memory::Memory,
+    processor::State,
+    syscall::{Personality, Syscalls},
+    utils::trace,
+};
+
+/// Device 0 proxies system calls, or exits when the payload's low bit is set.
+const DEVICE_SYSCALL: u64 = 0;
+/// Device 1 is the console: command 0 reads a character, command 1 writes one.
+const DEVICE_CONSOLE: u64 = 1;
+const CONSOLE_GETCHAR: u64 = 0;
+const CONSOLE_PUTCHAR: u64 = 1;
+
+/// The Host-Target Interface used by riscv-tests and bare-metal programs: the guest writes a
+/// command to `tohost` and, for commands with a reply, waits for it in `fromhost`.
+pub struct Htif {
+    tohost: u64,
+    fromhost: Option<u64>,
+    /// Proxied calls use the riscv-pk numbering that the HTIF front end serves.
+    syscalls: Syscalls,
+}
+
+impl Htif {
+    /// Enables HTIF if the program defines a `tohost` symbol.
+    pub fn new(symbols: &SymbolTable, memory: &Memory) -> Option<Self> {
+        Some(Htif {
+            tohost: symbols.lookup("tohost")?.addr,
+            fromhost: symbols.lookup("fromhost").map(|symbol| symbol.addr),
+            syscalls: Syscalls::new(Personality::Newlib, memory),
+        })
+    }
+}
+
+impl State {
+    /// Services a command left in `tohost`, if any. Commands are encoded as
+    /// `device << 56 | command << 48 | payload`.
+    pub fn poll_htif(&mut self) {
+        let Some(mut htif) = self.htif.take() else {
+            return;
+        };
+        match self.memory.read_u64(htif.tohost) {
+            Ok(0) | Err(_) => {}
+            Ok(tohost) => {
+                let device = tohost >> 56;
+                let command = tohost >> 48 & 0xff;
+                let payload = tohost & 0xffff_ffff_ffff;
+                trace!(
+                    self,
+                    "htif: device {} command {} {:#x}",
+                    device,
+                    command,
+                    payload
+                );
+                let _ = self.memory.write_u64(htif.tohost, 0);
+                let reply = match (device, command) {
+                    (DEVICE_SYSCALL, _) if payload & 1 == 1 => {
+                        // riscv-tests report a pass as 0 and a failure as the test number.
+                        self.exit_code = Some((payload >> 1) as i64);
+                        None
+                    }
+                    (DEVICE_SYSCALL, _) => {
+                        self.htif_syscall(&mut htif.syscalls, payload);
+                        Some(1)
+                    }
+                    (DEVICE_CONSOLE, CONSOLE_PUTCHAR) => {
+                        let mut stdout = io::stdout();
+                        let _ = stdout
+                            .write_all(&[payload as u8])
+                            .and_then(|_| stdout.flush());
+                        Some(0)
+                    }
+                    (DEVICE_CONSOLE, CONSOLE_GETCHAR) => {
+                        let mut ch = [0];
+                        match io::stdin().read(&mut ch) {
+                            Ok(1) => Some(ch[0] as u64),
+                            // End of input reads as -1.
+                            _ => Some(0xffff_ffff_ffff),
+                        }
+                    }
+                    _ => None,
+                };
+                if let (Some(reply), Some(fromhost)) = (reply, htif.fromhost) {
+                    let _ = self
+                        .memory
+                        .write_u64(fromhost, device << 56 | command << 48 | reply);
+                }
+            }
+        }
+        self.htif = Some(htif);
+    }
+
+    /// The payload points at eight doublewords: the call number followed by its arguments.
+    /// The result replaces the call number.
+    fn htif_syscall(&mut self, syscalls: &mut Syscalls, magic_mem: u64) {
+        let mut words = [0; 8];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = self.memory.read_u64(magic_mem + 8 * i as u64).unwrap_or(0);
+        }
+        let [number, a0, a1, a2, a3, a4, a5, _] = words;
+        let ret = syscalls.call(self, number, [a0, a1, a2, a3, a4, a5]);
+        let _ = self.memory.write_u64(magic_mem, ret as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::{
+        tests::{machine, BASE},
+        Machine, StopReason,
+    };
+
+    /// Places `tohost` at `BASE + 0x1000` and `fromhost` right after it.
+    fn with_htif(code: &[u32]) -> Machine {
+        let mut machine = machine(code);
+        machine.state.htif = Some(Htif {
+            tohost: BASE + 0x1000,
+            fromhost: Some(BASE + 0x1008),
+            syscalls: Syscalls::new(Personality::Newlib, &machine.state.memory),
+        });
+        machine
+    }
+
+    #[test]
+    fn tohost_exit_stops_with_the_test_number() {
+        // addi a0, zero, 85; auipc t0, 1; sd a0, -4(t0)
+        let mut machine = with_htif(&[0x0550_0513, 0x0000_1297, 0xfea2_be23]);
+        assert_eq!(machine.run(100), StopReason::Exited(42));
+        assert_eq!(machine.pc(), BASE + 12);
+        assert_eq!(machine.state.memory.read_u64(BASE + 0x1000), Ok(0));
+    }
+
+    #[test]
+    fn tohost_proxies_system_calls_and_replies_in_fromhost() {
+        // auipc t0, 2; auipc t1, 1; sd t0, -4(t1)
+        let mut machine = with_htif(&[0x0000_2297, 0x0000_1317, 0xfe53_3e23]);
+        // getpid, with no arguments.
+        machine
+            .write_memory(BASE + 0x2000, &172u64.to_le_bytes())
+            .unwrap();
+        assert_eq!(machine.run(3), StopReason::InstructionLimit);
+        let memory = &machine.state.memory;
+        assert_eq!(memory.read_u64(BASE + 0x2000), Ok(1));
+        assert_eq!(memory.read_u64(BASE + 0x1000), Ok(0));
+        assert_eq!(memory.read_u64(BASE + 0x1008), Ok(1));
+        assert_eq!(machine.state.exit_code, None);
+    }
+}
@@ -1,9 +1,26 @@
+pub mod privileged;
 pub mod rv32i;
+pub mod rv64a;
+pub mod rv64d;
+pub mod rv64f;
+pub mod rv64i;
+pub mod rv64m;
+pub mod rvb;
 pub mod rvc;
+pub mod rvk;
+pub mod rvv;
+pub mod rvv_float;
+pub mod rvv_int;
+pub mod rvv_mask;
+pub mod rvv_permute;
+pub mod zcb;
+pub mod zcmp;
+pub mod zcmt;
+pub mod zicsr;
 
-use crate::processor::State;
+use crate::{processor::State, trap::Exception};
 
 pub trait Instruction {
     // fn asm(&self) -> String;
-    fn execute(&self, state: &mut State);
+    fn execute(&self, state: &mut State) -> Result<(), Exception>;
 }
@@ -0,0 +1,67 @@
+use super::{rv32i::rv32i_r_type, Instruction};
+use crate::{
+    processor::{State, XprName},
+    trap::Exception,
+    utils::trace,
+};
+
+pub struct MRET;
+impl MRET {
+    pub fn new(_inst: u32) -> Self {
+        MRET
+    }
+}
+impl Instruction for MRET {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "mret");
+        state.mret();
+        Ok(())
+    }
+}
+
+pub struct SRET;
+impl SRET {
+    pub fn new(_inst: u32) -> Self {
+        SRET
+    }
+}
+impl Instruction for SRET {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sret");
+        state.sret();
+        Ok(())
+    }
+}
+
+pub struct WFI;
+impl WFI {
+    pub fn new(_inst: u32) -> Self {
+        WFI
+    }
+}
+impl Instruction for WFI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        // Pending interrupts are checked before every instruction, so waiting is a no-op.
+        trace!(state, "wfi");
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SFENCE_VMA {
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SFENCE_VMA {
+    pub fn new(inst: u32) -> Self {
+        let (_, rs1, rs2) = rv32i_r_type(inst);
+        SFENCE_VMA { rs1, rs2 }
+    }
+}
+impl Instruction for SFENCE_VMA {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sfence.vma {:?}, {:?}", self.rs1, self.rs2);
+        state.pc += 4;
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 use super::Instruction;
 use crate::{
-    processor::{State, XprName},
-    utils::x,
+    processor::{Privilege, State, XprName},
+    trap::Exception,
+    utils::{trace, x},
 };
 
 pub fn rv32i_r_type(inst: u32) -> (XprName, XprName, XprName) {
@@ -21,41 +22,52 @@ pub fn rv32i_i_type(inst: u32) -> (XprName, XprName, i64) {
     (XprName::from_num(rd), XprName::from_num(rs1), imm)
 }
 pub fn rv32i_s_type(inst: u32) -> (XprName, XprName, i64) {
-    let imm = x(inst, 7, 5) + (x(inst, 25, 7) << 5);
+    let imm = x(inst, 7, 5) + (x(inst, 25, 6) << 5) - (x(inst, 31, 1) << 11);
     let rs1 = x(inst, 15, 5);
     let rs2 = x(inst, 20, 5);
     (XprName::from_num(rs1), XprName::from_num(rs2), imm)
 }
 pub fn rv32i_b_type(inst: u32) -> (XprName, XprName, i64) {
-    let imm =
-        (x(inst, 7, 1) << 11) + x(inst, 8, 4) + (x(inst, 25, 6) << 5) - (x(inst, 31, 1) << 12);
+    let imm = (x(inst, 7, 1) << 11) + (x(inst, 8, 4) << 1) + (x(inst, 25, 6) << 5)
+        - (x(inst, 31, 1) << 12);
     let rs1 = x(inst, 15, 5);
     let rs2 = x(inst, 20, 5);
     (XprName::from_num(rs1), XprName::from_num(rs2), imm)
 }
 pub fn rv32i_u_type(inst: u32) -> (XprName, i64) {
     let rd = x(inst, 7, 5);
-    let imm = x(inst, 12, 20);
+    let imm = x(inst, 12, 19) - (x(inst, 31, 1) << 19);
     (XprName::from_num(rd), imm)
 }
 pub fn rv32i_j_type(inst: u32) -> (XprName, i64) {
     let rd = x(inst, 7, 5);
-    let imm = (x(inst, 12, 8) << 12)
-        + (x(inst, 20, 1) << 11)
-        + (x(inst, 21, 10) << 1)
-        + (x(inst, 31, 1) << 20);
+    let imm = (x(inst, 12, 8) << 12) + (x(inst, 20, 1) << 11) + (x(inst, 21, 10) << 1)
+        - (x(inst, 31, 1) << 20);
     (XprName::from_num(rd), imm)
 }
+pub fn rv32i_shamt_type(inst: u32) -> (XprName, XprName, u32) {
+    let rd = x(inst, 7, 5);
+    let rs1 = x(inst, 15, 5);
+    let shamt = x(inst, 20, 6) as u32;
+    (XprName::from_num(rd), XprName::from_num(rs1), shamt)
+}
 
-pub struct LUI(u32);
+pub struct LUI {
+    rd: XprName,
+    imm: i64,
+}
 impl LUI {
     pub fn new(inst: u32) -> Self {
-        LUI(inst)
+        let (rd, imm) = rv32i_u_type(inst);
+        LUI { rd, imm }
     }
 }
 impl Instruction for LUI {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lui {:?}, {:#x}", self.rd, self.imm);
+        state.set_reg(self.rd, self.imm << 12);
         state.pc += 4;
+        Ok(())
     }
 }
 
@@ -70,10 +82,11 @@ impl AUIPC {
     }
 }
 impl Instruction for AUIPC {
-    fn execute(&self, state: &mut State) {
-        println!("auipc {:?}, {:#x}", self.rd, self.imm);
-        state.set_reg(self.rd, state.pc + (self.imm << 12));
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "auipc {:?}, {:#x}", self.rd, self.imm);
+        state.set_reg(self.rd, state.pc.wrapping_add(self.imm << 12));
         state.pc += 4;
+        Ok(())
     }
 }
 
@@ -88,10 +101,12 @@ impl JAL {
     }
 }
 impl Instruction for JAL {
-    fn execute(&self, state: &mut State) {
-        println!("jal {:?} {}", self.rd, self.imm);
-        state.set_reg(self.rd, state.pc + 4);
-        state.pc += self.imm;
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "jal {:?}, {}", self.rd, self.imm);
+        let link = state.pc + 4;
+        state.jump(state.pc.wrapping_add(self.imm))?;
+        state.set_reg(self.rd, link);
+        Ok(())
     }
 }
 
@@ -107,8 +122,13 @@ impl JALR {
     }
 }
 impl Instruction for JALR {
-    fn execute(&self, state: &mut State) {
-        state.pc += 4;
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "jalr {:?}, {}({:?})", self.rd, self.imm, self.rs1);
+        let target = state.get_reg(self.rs1).wrapping_add(self.imm) & !1;
+        let link = state.pc + 4;
+        state.jump(target)?;
+        state.set_reg(self.rd, link);
+        Ok(())
     }
 }
 
@@ -124,13 +144,14 @@ impl BEQ {
     }
 }
 impl Instruction for BEQ {
-    fn execute(&self, state: &mut State) {
-        println!("beq {:?}, {:?}, {}", self.rs1, self.rs2, self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "beq {:?}, {:?}, {}", self.rs1, self.rs2, self.offset);
         if state.get_reg(self.rs1) == state.get_reg(self.rs2) {
-            state.pc += self.offset;
+            state.jump(state.pc.wrapping_add(self.offset))?;
         } else {
             state.pc += 4;
         }
+        Ok(())
     }
 }
 
@@ -146,13 +167,14 @@ impl BNE {
     }
 }
 impl Instruction for BNE {
-    fn execute(&self, state: &mut State) {
-        println!("bne {:?}, {:?}, {}", self.rs1, self.rs2, self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "bne {:?}, {:?}, {}", self.rs1, self.rs2, self.offset);
         if state.get_reg(self.rs1) != state.get_reg(self.rs2) {
-            state.pc += self.offset;
+            state.jump(state.pc.wrapping_add(self.offset))?;
         } else {
             state.pc += 4;
         }
+        Ok(())
     }
 }
 
@@ -168,13 +190,14 @@ impl BLT {
     }
 }
 impl Instruction for BLT {
-    fn execute(&self, state: &mut State) {
-        println!("blt {:?}, {:?}, {}", self.rs1, self.rs2, self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "blt {:?}, {:?}, {}", self.rs1, self.rs2, self.offset);
         if state.get_reg(self.rs1) < state.get_reg(self.rs2) {
-            state.pc += self.offset;
+            state.jump(state.pc.wrapping_add(self.offset))?;
         } else {
             state.pc += 4;
         }
+        Ok(())
     }
 }
 
@@ -190,13 +213,14 @@ impl BGE {
     }
 }
 impl Instruction for BGE {
-    fn execute(&self, state: &mut State) {
-        println!("bge {:?}, {:?}, {}", self.rs1, self.rs2, self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "bge {:?}, {:?}, {}", self.rs1, self.rs2, self.offset);
         if state.get_reg(self.rs1) >= state.get_reg(self.rs2) {
-            state.pc += self.offset;
+            state.jump(state.pc.wrapping_add(self.offset))?;
         } else {
             state.pc += 4;
         }
+        Ok(())
     }
 }
 
@@ -212,13 +236,20 @@ impl BLTU {
     }
 }
 impl Instruction for BLTU {
-    fn execute(&self, state: &mut State) {
-        println!("bltu {:?}, {:?}, {}", self.rs1, self.rs2, self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "bltu {:?}, {:?}, {}",
+            self.rs1,
+            self.rs2,
+            self.offset
+        );
         if (state.get_reg(self.rs1) as u64) < state.get_reg(self.rs2) as u64 {
-            state.pc += self.offset;
+            state.jump(state.pc.wrapping_add(self.offset))?;
         } else {
             state.pc += 4;
         }
+        Ok(())
     }
 }
 
@@ -234,25 +265,196 @@ impl BGEU {
     }
 }
 impl Instruction for BGEU {
-    fn execute(&self, state: &mut State) {
-        println!("bgeu {:?}, {:?}, {}", self.rs1, self.rs2, self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "bgeu {:?}, {:?}, {}",
+            self.rs1,
+            self.rs2,
+            self.offset
+        );
         if state.get_reg(self.rs1) as u64 >= state.get_reg(self.rs2) as u64 {
-            state.pc += self.offset;
+            state.jump(state.pc.wrapping_add(self.offset))?;
         } else {
             state.pc += 4;
         }
+        Ok(())
     }
 }
 
-pub struct LB(u32);
+pub struct LB {
+    rd: XprName,
+    rs1: XprName,
+    offset: i64,
+}
 impl LB {
     pub fn new(inst: u32) -> Self {
-        LB(inst)
+        let (rd, rs1, offset) = rv32i_i_type(inst);
+        LB { rd, rs1, offset }
     }
 }
 impl Instruction for LB {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lb {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access(addr)?;
+        state.set_reg(self.rd, mem as i8 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct LH {
+    rd: XprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl LH {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, offset) = rv32i_i_type(inst);
+        LH { rd, rs1, offset }
+    }
+}
+impl Instruction for LH {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lh {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access_u16(addr)?;
+        state.set_reg(self.rd, mem as i16 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct LW {
+    rd: XprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl LW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, offset) = rv32i_i_type(inst);
+        LW { rd, rs1, offset }
+    }
+}
+impl Instruction for LW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lw {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access_u32(addr)?;
+        state.set_reg(self.rd, mem as i32 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct LBU {
+    rd: XprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl LBU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, offset) = rv32i_i_type(inst);
+        LBU { rd, rs1, offset }
+    }
+}
+impl Instruction for LBU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lbu {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access(addr)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct LHU {
+    rd: XprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl LHU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, offset) = rv32i_i_type(inst);
+        LHU { rd, rs1, offset }
+    }
+}
+impl Instruction for LHU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lhu {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access_u16(addr)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SB {
+    rs1: XprName,
+    rs2: XprName,
+    offset: i64,
+}
+impl SB {
+    pub fn new(inst: u32) -> Self {
+        let (rs1, rs2, offset) = rv32i_s_type(inst);
+        SB { rs1, rs2, offset }
+    }
+}
+impl Instruction for SB {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sb {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u8(addr, rs2 as u8)?;
         state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SH {
+    rs1: XprName,
+    rs2: XprName,
+    offset: i64,
+}
+impl SH {
+    pub fn new(inst: u32) -> Self {
+        let (rs1, rs2, offset) = rv32i_s_type(inst);
+        SH { rs1, rs2, offset }
+    }
+}
+impl Instruction for SH {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sh {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u16(addr, rs2 as u16)?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SW {
+    rs1: XprName,
+    rs2: XprName,
+    offset: i64,
+}
+impl SW {
+    pub fn new(inst: u32) -> Self {
+        let (rs1, rs2, offset) = rv32i_s_type(inst);
+        SW { rs1, rs2, offset }
+    }
+}
+impl Instruction for SW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sw {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u32(addr, rs2 as u32)?;
+        state.pc += 4;
+        Ok(())
     }
 }
 
@@ -268,10 +470,11 @@ impl ADDI {
     }
 }
 impl Instruction for ADDI {
-    fn execute(&self, state: &mut State) {
-        println!("addi {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
-        state.set_reg(self.rd, state.get_reg(self.rs1) + self.imm);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "addi {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
+        state.set_reg(self.rd, state.get_reg(self.rs1).wrapping_add(self.imm));
         state.pc += 4;
+        Ok(())
     }
 }
 
@@ -287,12 +490,13 @@ impl SLTI {
     }
 }
 impl Instruction for SLTI {
-    fn execute(&self, state: &mut State) {
-        println!("slti {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "slti {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
         let rs1 = state.get_reg(self.rs1);
         let value = if rs1 < self.imm { 1 } else { 0 };
         state.set_reg(self.rd, value);
         state.pc += 4;
+        Ok(())
     }
 }
 
@@ -308,12 +512,13 @@ impl SLTIU {
     }
 }
 impl Instruction for SLTIU {
-    fn execute(&self, state: &mut State) {
-        println!("sltiu {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sltiu {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
         let rs1 = state.get_reg(self.rs1);
         let value = if (rs1 as u64) < self.imm as u64 { 1 } else { 0 };
         state.set_reg(self.rd, value);
         state.pc += 4;
+        Ok(())
     }
 }
 
@@ -329,10 +534,11 @@ impl XORI {
     }
 }
 impl Instruction for XORI {
-    fn execute(&self, state: &mut State) {
-        println!("xori {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "xori {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
         state.set_reg(self.rd, state.get_reg(self.rs1) ^ self.imm);
         state.pc += 4;
+        Ok(())
     }
 }
 
@@ -348,10 +554,11 @@ impl ORI {
     }
 }
 impl Instruction for ORI {
-    fn execute(&self, state: &mut State) {
-        println!("ori {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "ori {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
         state.set_reg(self.rd, state.get_reg(self.rs1) | self.imm);
         state.pc += 4;
+        Ok(())
     }
 }
 
@@ -367,9 +574,390 @@ impl ANDI {
     }
 }
 impl Instruction for ANDI {
-    fn execute(&self, state: &mut State) {
-        println!("andi {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "andi {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
         state.set_reg(self.rd, state.get_reg(self.rs1) & self.imm);
         state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SLLI {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl SLLI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        SLLI { rd, rs1, shamt }
+    }
+}
+impl Instruction for SLLI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "slli {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        state.set_reg(self.rd, state.get_reg(self.rs1) << self.shamt);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SRLI {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl SRLI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        SRLI { rd, rs1, shamt }
+    }
+}
+impl Instruction for SRLI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "srli {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        let rs1 = state.get_reg_u(self.rs1);
+        state.set_reg(self.rd, (rs1 >> self.shamt) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SRAI {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl SRAI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        SRAI { rd, rs1, shamt }
+    }
+}
+impl Instruction for SRAI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "srai {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        state.set_reg(self.rd, state.get_reg(self.rs1) >> self.shamt);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ADD {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl ADD {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        ADD { rd, rs1, rs2 }
+    }
+}
+impl Instruction for ADD {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "add {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1.wrapping_add(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SUB {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SUB {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SUB { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SUB {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sub {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1.wrapping_sub(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SLL {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SLL {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SLL { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SLL {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sll {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let shamt = state.get_reg(self.rs2) & state.shamt_mask();
+        state.set_reg(self.rd, rs1 << shamt);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SLT {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SLT {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SLT { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SLT {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "slt {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value = if rs1 < rs2 { 1 } else { 0 };
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SLTU {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SLTU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SLTU { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SLTU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sltu {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value = if (rs1 as u64) < rs2 as u64 { 1 } else { 0 };
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct XOR {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl XOR {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        XOR { rd, rs1, rs2 }
+    }
+}
+impl Instruction for XOR {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "xor {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        state.set_reg(self.rd, state.get_reg(self.rs1) ^ state.get_reg(self.rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SRL {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SRL {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SRL { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SRL {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "srl {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg_u(self.rs1);
+        let shamt = state.get_reg(self.rs2) & state.shamt_mask();
+        state.set_reg(self.rd, (rs1 >> shamt) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SRA {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SRA {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SRA { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SRA {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sra {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let shamt = state.get_reg(self.rs2) & state.shamt_mask();
+        state.set_reg(self.rd, rs1 >> shamt);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct OR {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl OR {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        OR { rd, rs1, rs2 }
+    }
+}
+impl Instruction for OR {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "or {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        state.set_reg(self.rd, state.get_reg(self.rs1) | state.get_reg(self.rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AND {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AND {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AND { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AND {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "and {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        state.set_reg(self.rd, state.get_reg(self.rs1) & state.get_reg(self.rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FENCE {
+    pred: i64,
+    succ: i64,
+}
+impl FENCE {
+    pub fn new(inst: u32) -> Self {
+        let pred = x(inst, 24, 4);
+        let succ = x(inst, 20, 4);
+        FENCE { pred, succ }
+    }
+}
+impl Instruction for FENCE {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        // Memory accesses are performed in program order, so there is nothing to order.
+        trace!(state, "fence {:#x}, {:#x}", self.pred, self.succ);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FENCE_I;
+impl FENCE_I {
+    pub fn new(_inst: u32) -> Self {
+        FENCE_I
+    }
+}
+impl Instruction for FENCE_I {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        // Instructions are fetched from memory on every step, so no cache needs flushing.
+        trace!(state, "fence.i");
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ECALL;
+impl ECALL {
+    pub fn new(_inst: u32) -> Self {
+        ECALL
+    }
+}
+impl Instruction for ECALL {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "ecall");
+        if state.syscalls.is_some() {
+            return state.syscall();
+        }
+        Err(match state.prv {
+            Privilege::User => Exception::EnvironmentCallFromUMode,
+            Privilege::Supervisor => Exception::EnvironmentCallFromSMode,
+            Privilege::Machine => Exception::EnvironmentCallFromMMode,
+        })
+    }
+}
+
+pub struct EBREAK;
+impl EBREAK {
+    pub fn new(_inst: u32) -> Self {
+        EBREAK
+    }
+}
+impl Instruction for EBREAK {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "ebreak");
+        Err(Exception::Breakpoint(state.zext_xlen(state.pc)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        machine::tests::{machine_with, BASE},
+        processor::XprName,
+    };
+
+    #[test]
+    fn rv32_registers_wrap_at_32_bits() {
+        let mut machine = machine_with(
+            "rv32i",
+            &[
+                0x8000_0537, // lui a0, 0x80000
+                0xfff5_0593, // addi a1, a0, -1
+                0x00a5_b633, // sltu a2, a1, a0
+                0x01f5_5693, // srli a3, a0, 31
+                0x41f5_5713, // srai a4, a0, 31
+                0x0015_1793, // slli a5, a0, 1
+                0x00a5_0833, // add a6, a0, a0
+                0x0205_1513, // slli a0, a0, 32, which needs a sixth shamt bit
+            ],
+        );
+        machine.run(8);
+        assert_eq!(machine.state.get_reg_u(XprName::a0), 0x8000_0000);
+        assert_eq!(machine.reg(XprName::a1), 0x7fff_ffff);
+        assert_eq!(machine.reg(XprName::a2), 1);
+        assert_eq!(machine.reg(XprName::a3), 1);
+        assert_eq!(machine.reg(XprName::a4), -1);
+        assert_eq!(machine.reg(XprName::a5), 0);
+        assert_eq!(machine.reg(XprName::a6), 0);
+        assert_eq!(machine.state.mcause, 2);
+        assert_eq!(machine.state.mepc, (BASE + 28) as i64);
     }
 }
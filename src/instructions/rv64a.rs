@@ -0,0 +1,762 @@
+use super::{rv32i::rv32i_r_type, Instruction};
+use crate::{
+    processor::{State, XprName},
+    trap::Exception,
+    utils::trace,
+};
+
+pub struct LR_W {
+    rd: XprName,
+    rs1: XprName,
+}
+impl LR_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        LR_W { rd, rs1 }
+    }
+}
+impl Instruction for LR_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lr.w {:?}, ({:?})", self.rd, self.rs1);
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::LoadAddressMisaligned)?;
+        let mem = state.access_u32(addr)?;
+        state.reservation = Some(state.zext_xlen(addr));
+        state.set_reg(self.rd, mem as i32 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SC_W {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SC_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SC_W { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SC_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sc.w {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::StoreAddressMisaligned)?;
+        // The reservation is consumed whether or not the store succeeds.
+        if state.reservation.take() == Some(state.zext_xlen(addr)) {
+            let rs2 = state.get_reg(self.rs2);
+            state.store_u32(addr, rs2 as u32)?;
+            state.set_reg(self.rd, 0);
+        } else {
+            state.set_reg(self.rd, 1);
+        }
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct LR_D {
+    rd: XprName,
+    rs1: XprName,
+}
+impl LR_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        LR_D { rd, rs1 }
+    }
+}
+impl Instruction for LR_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lr.d {:?}, ({:?})", self.rd, self.rs1);
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::LoadAddressMisaligned)?;
+        let mem = state.access_u64(addr)?;
+        state.reservation = Some(state.zext_xlen(addr));
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SC_D {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SC_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SC_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SC_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sc.d {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::StoreAddressMisaligned)?;
+        // The reservation is consumed whether or not the store succeeds.
+        if state.reservation.take() == Some(state.zext_xlen(addr)) {
+            let rs2 = state.get_reg(self.rs2);
+            state.store_u64(addr, rs2 as u64)?;
+            state.set_reg(self.rd, 0);
+        } else {
+            state.set_reg(self.rd, 1);
+        }
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOSWAP_W {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOSWAP_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOSWAP_W { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOSWAP_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amoswap.w {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u32(addr)
+            .map_err(Exception::into_store_fault)? as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.store_u32(addr, (rs2) as u32)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOADD_W {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOADD_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOADD_W { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOADD_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amoadd.w {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u32(addr)
+            .map_err(Exception::into_store_fault)? as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.store_u32(addr, (mem.wrapping_add(rs2)) as u32)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOXOR_W {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOXOR_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOXOR_W { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOXOR_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amoxor.w {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u32(addr)
+            .map_err(Exception::into_store_fault)? as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.store_u32(addr, (mem ^ rs2) as u32)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOAND_W {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOAND_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOAND_W { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOAND_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amoand.w {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u32(addr)
+            .map_err(Exception::into_store_fault)? as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.store_u32(addr, (mem & rs2) as u32)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOOR_W {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOOR_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOOR_W { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOOR_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amoor.w {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u32(addr)
+            .map_err(Exception::into_store_fault)? as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.store_u32(addr, (mem | rs2) as u32)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOMIN_W {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOMIN_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOMIN_W { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOMIN_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amomin.w {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u32(addr)
+            .map_err(Exception::into_store_fault)? as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.store_u32(addr, (mem.min(rs2)) as u32)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOMAX_W {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOMAX_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOMAX_W { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOMAX_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amomax.w {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u32(addr)
+            .map_err(Exception::into_store_fault)? as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.store_u32(addr, (mem.max(rs2)) as u32)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOMINU_W {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOMINU_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOMINU_W { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOMINU_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amominu.w {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u32(addr)
+            .map_err(Exception::into_store_fault)? as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.store_u32(addr, ((mem as u32).min(rs2 as u32) as i32) as u32)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOMAXU_W {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOMAXU_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOMAXU_W { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOMAXU_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amomaxu.w {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 4, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u32(addr)
+            .map_err(Exception::into_store_fault)? as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.store_u32(addr, ((mem as u32).max(rs2 as u32) as i32) as u32)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOSWAP_D {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOSWAP_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOSWAP_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOSWAP_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amoswap.d {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u64(addr)
+            .map_err(Exception::into_store_fault)? as i64;
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, (rs2) as u64)?;
+        state.set_reg(self.rd, mem);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOADD_D {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOADD_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOADD_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOADD_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amoadd.d {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u64(addr)
+            .map_err(Exception::into_store_fault)? as i64;
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, (mem.wrapping_add(rs2)) as u64)?;
+        state.set_reg(self.rd, mem);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOXOR_D {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOXOR_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOXOR_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOXOR_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amoxor.d {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u64(addr)
+            .map_err(Exception::into_store_fault)? as i64;
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, (mem ^ rs2) as u64)?;
+        state.set_reg(self.rd, mem);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOAND_D {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOAND_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOAND_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOAND_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amoand.d {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u64(addr)
+            .map_err(Exception::into_store_fault)? as i64;
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, (mem & rs2) as u64)?;
+        state.set_reg(self.rd, mem);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOOR_D {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOOR_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOOR_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOOR_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amoor.d {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u64(addr)
+            .map_err(Exception::into_store_fault)? as i64;
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, (mem | rs2) as u64)?;
+        state.set_reg(self.rd, mem);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOMIN_D {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOMIN_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOMIN_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOMIN_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amomin.d {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u64(addr)
+            .map_err(Exception::into_store_fault)? as i64;
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, (mem.min(rs2)) as u64)?;
+        state.set_reg(self.rd, mem);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOMAX_D {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOMAX_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOMAX_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOMAX_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amomax.d {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u64(addr)
+            .map_err(Exception::into_store_fault)? as i64;
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, (mem.max(rs2)) as u64)?;
+        state.set_reg(self.rd, mem);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOMINU_D {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOMINU_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOMINU_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOMINU_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amominu.d {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u64(addr)
+            .map_err(Exception::into_store_fault)? as i64;
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, ((mem as u64).min(rs2 as u64) as i64) as u64)?;
+        state.set_reg(self.rd, mem);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AMOMAXU_D {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AMOMAXU_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AMOMAXU_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AMOMAXU_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "amomaxu.d {:?}, {:?}, ({:?})",
+            self.rd,
+            self.rs2,
+            self.rs1
+        );
+        let addr = state.get_reg(self.rs1);
+        state.check_aligned(addr, 8, Exception::StoreAddressMisaligned)?;
+        let mem = state
+            .access_u64(addr)
+            .map_err(Exception::into_store_fault)? as i64;
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, ((mem as u64).max(rs2 as u64) as i64) as u64)?;
+        state.set_reg(self.rd, mem);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{machine::tests::machine, processor::XprName};
+
+    #[test]
+    fn sc_succeeds_once_per_reservation() {
+        let mut machine = machine(&[
+            0x0000_0297, // auipc t0, 0
+            0x1002_8293, // addi t0, t0, 256
+            0x0070_0593, // addi a1, zero, 7
+            0x0002_b023, // sd zero, 0(t0)
+            0x1002_b52f, // lr.d a0, (t0)
+            0x18b2_b62f, // sc.d a2, a1, (t0)
+            0x18b2_b6af, // sc.d a3, a1, (t0)
+            0x0002_b703, // ld a4, 0(t0)
+        ]);
+        machine.run(8);
+        assert_eq!(machine.reg(XprName::a0), 0);
+        assert_eq!(machine.reg(XprName::a2), 0);
+        assert_eq!(machine.reg(XprName::a3), 1);
+        assert_eq!(machine.reg(XprName::a4), 7);
+    }
+
+    #[test]
+    fn overlapping_store_breaks_the_reservation() {
+        let mut machine = machine(&[
+            0x0000_0297, // auipc t0, 0
+            0x1002_8293, // addi t0, t0, 256
+            0x0070_0593, // addi a1, zero, 7
+            0x1002_b52f, // lr.d a0, (t0)
+            0x18b2_b62f, // sc.d a2, a1, (t0)
+            0x1002_b52f, // lr.d a0, (t0)
+            0x0002_81a3, // sb zero, 3(t0)
+            0x18b2_b6af, // sc.d a3, a1, (t0)
+            0x00b2_b72f, // amoadd.d a4, a1, (t0)
+            0x0002_b783, // ld a5, 0(t0)
+        ]);
+        machine.run(10);
+        assert_eq!(machine.reg(XprName::a2), 0);
+        assert_eq!(machine.reg(XprName::a0), 7);
+        assert_eq!(machine.reg(XprName::a3), 1);
+        assert_eq!(machine.reg(XprName::a4), 7);
+        assert_eq!(machine.reg(XprName::a5), 14);
+    }
+}
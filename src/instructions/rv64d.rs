@@ -0,0 +1,903 @@
+use super::{
+    rv32i::{rv32i_i_type, rv32i_s_type},
+    rv64f::{rv_fp_r4_type, rv_fp_r_type},
+    Instruction,
+};
+use crate::{
+    processor::{FprName, State, XprName},
+    softfloat::{self, F32, F64},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+pub struct FLD {
+    rd: FprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl FLD {
+    pub fn new(inst: u32) -> Self {
+        let (_, rs1, offset) = rv32i_i_type(inst);
+        let rd = FprName::from_num(x(inst, 7, 5));
+        FLD { rd, rs1, offset }
+    }
+}
+impl Instruction for FLD {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fld {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access_u64(addr)?;
+        state.set_freg_d(self.rd, mem);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSD {
+    rs1: XprName,
+    rs2: FprName,
+    offset: i64,
+}
+impl FSD {
+    pub fn new(inst: u32) -> Self {
+        let (rs1, _, offset) = rv32i_s_type(inst);
+        let rs2 = FprName::from_num(x(inst, 20, 5));
+        FSD { rs1, rs2, offset }
+    }
+}
+impl Instruction for FSD {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fsd {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        state.store_u64(addr, state.fregs.get(self.rs2))?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMADD_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rs3: FprName,
+    rm: u8,
+}
+impl FMADD_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rs3, rm) = rv_fp_r4_type(inst);
+        FMADD_D {
+            rd,
+            rs1,
+            rs2,
+            rs3,
+            rm,
+        }
+    }
+}
+impl Instruction for FMADD_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fmadd.d {:?}, {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.rs3
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let rs3 = state.get_freg_d(self.rs3);
+        let (value, flags) = softfloat::fma(F64, rs1, rs2, rs3, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMSUB_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rs3: FprName,
+    rm: u8,
+}
+impl FMSUB_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rs3, rm) = rv_fp_r4_type(inst);
+        FMSUB_D {
+            rd,
+            rs1,
+            rs2,
+            rs3,
+            rm,
+        }
+    }
+}
+impl Instruction for FMSUB_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fmsub.d {:?}, {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.rs3
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let rs3 = state.get_freg_d(self.rs3) ^ F64.sign_bit();
+        let (value, flags) = softfloat::fma(F64, rs1, rs2, rs3, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FNMSUB_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rs3: FprName,
+    rm: u8,
+}
+impl FNMSUB_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rs3, rm) = rv_fp_r4_type(inst);
+        FNMSUB_D {
+            rd,
+            rs1,
+            rs2,
+            rs3,
+            rm,
+        }
+    }
+}
+impl Instruction for FNMSUB_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fnmsub.d {:?}, {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.rs3
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1) ^ F64.sign_bit();
+        let rs2 = state.get_freg_d(self.rs2);
+        let rs3 = state.get_freg_d(self.rs3);
+        let (value, flags) = softfloat::fma(F64, rs1, rs2, rs3, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FNMADD_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rs3: FprName,
+    rm: u8,
+}
+impl FNMADD_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rs3, rm) = rv_fp_r4_type(inst);
+        FNMADD_D {
+            rd,
+            rs1,
+            rs2,
+            rs3,
+            rm,
+        }
+    }
+}
+impl Instruction for FNMADD_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fnmadd.d {:?}, {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.rs3
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1) ^ F64.sign_bit();
+        let rs2 = state.get_freg_d(self.rs2);
+        let rs3 = state.get_freg_d(self.rs3) ^ F64.sign_bit();
+        let (value, flags) = softfloat::fma(F64, rs1, rs2, rs3, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FADD_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rm: u8,
+}
+impl FADD_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rm) = rv_fp_r_type(inst);
+        FADD_D { rd, rs1, rs2, rm }
+    }
+}
+impl Instruction for FADD_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fadd.d {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let (value, flags) = softfloat::add(F64, rs1, rs2, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSUB_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rm: u8,
+}
+impl FSUB_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rm) = rv_fp_r_type(inst);
+        FSUB_D { rd, rs1, rs2, rm }
+    }
+}
+impl Instruction for FSUB_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fsub.d {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let (value, flags) = softfloat::sub(F64, rs1, rs2, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMUL_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rm: u8,
+}
+impl FMUL_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rm) = rv_fp_r_type(inst);
+        FMUL_D { rd, rs1, rs2, rm }
+    }
+}
+impl Instruction for FMUL_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fmul.d {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let (value, flags) = softfloat::mul(F64, rs1, rs2, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FDIV_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rm: u8,
+}
+impl FDIV_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rm) = rv_fp_r_type(inst);
+        FDIV_D { rd, rs1, rs2, rm }
+    }
+}
+impl Instruction for FDIV_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fdiv.d {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let (value, flags) = softfloat::div(F64, rs1, rs2, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSQRT_D {
+    rd: FprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FSQRT_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _, rm) = rv_fp_r_type(inst);
+        FSQRT_D { rd, rs1, rm }
+    }
+}
+impl Instruction for FSQRT_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fsqrt.d {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let (value, flags) = softfloat::sqrt(F64, rs1, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSGNJ_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FSGNJ_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, _) = rv_fp_r_type(inst);
+        FSGNJ_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FSGNJ_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fsgnj.d {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let sign = F64.sign_bit();
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        state.set_freg_d(self.rd, (rs1 & !sign) | (rs2 & sign));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSGNJN_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FSGNJN_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, _) = rv_fp_r_type(inst);
+        FSGNJN_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FSGNJN_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fsgnjn.d {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let sign = F64.sign_bit();
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        state.set_freg_d(self.rd, (rs1 & !sign) | (!rs2 & sign));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSGNJX_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FSGNJX_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, _) = rv_fp_r_type(inst);
+        FSGNJX_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FSGNJX_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fsgnjx.d {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let sign = F64.sign_bit();
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        state.set_freg_d(self.rd, rs1 ^ (rs2 & sign));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMIN_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FMIN_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, _) = rv_fp_r_type(inst);
+        FMIN_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FMIN_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fmin.d {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let (value, flags) = softfloat::min(F64, rs1, rs2);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMAX_D {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FMAX_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, _) = rv_fp_r_type(inst);
+        FMAX_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FMAX_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fmax.d {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let (value, flags) = softfloat::max(F64, rs1, rs2);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_W_D {
+    rd: XprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FCVT_W_D {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, rm) = rv_fp_r_type(inst);
+        FCVT_W_D { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_W_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.w.d {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let (value, flags) = softfloat::to_int(F64, rs1, rm, true, 32);
+        state.set_reg(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_WU_D {
+    rd: XprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FCVT_WU_D {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, rm) = rv_fp_r_type(inst);
+        FCVT_WU_D { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_WU_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.wu.d {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let (value, flags) = softfloat::to_int(F64, rs1, rm, false, 32);
+        state.set_reg(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_L_D {
+    rd: XprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FCVT_L_D {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, rm) = rv_fp_r_type(inst);
+        FCVT_L_D { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_L_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.l.d {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let (value, flags) = softfloat::to_int(F64, rs1, rm, true, 64);
+        state.set_reg(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_LU_D {
+    rd: XprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FCVT_LU_D {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, rm) = rv_fp_r_type(inst);
+        FCVT_LU_D { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_LU_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.lu.d {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let (value, flags) = softfloat::to_int(F64, rs1, rm, false, 64);
+        state.set_reg(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_D_W {
+    rd: FprName,
+    rs1: XprName,
+    rm: u8,
+}
+impl FCVT_D_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, _, _, rm) = rv_fp_r_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        FCVT_D_W { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_D_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.d.w {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_reg(self.rs1) as i32 as u64;
+        let (value, flags) = softfloat::from_int(F64, rs1, true, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_D_WU {
+    rd: FprName,
+    rs1: XprName,
+    rm: u8,
+}
+impl FCVT_D_WU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, _, _, rm) = rv_fp_r_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        FCVT_D_WU { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_D_WU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.d.wu {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_reg(self.rs1) as u32 as u64;
+        let (value, flags) = softfloat::from_int(F64, rs1, false, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_D_L {
+    rd: FprName,
+    rs1: XprName,
+    rm: u8,
+}
+impl FCVT_D_L {
+    pub fn new(inst: u32) -> Self {
+        let (rd, _, _, rm) = rv_fp_r_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        FCVT_D_L { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_D_L {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.d.l {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_reg(self.rs1) as u64;
+        let (value, flags) = softfloat::from_int(F64, rs1, true, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_D_LU {
+    rd: FprName,
+    rs1: XprName,
+    rm: u8,
+}
+impl FCVT_D_LU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, _, _, rm) = rv_fp_r_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        FCVT_D_LU { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_D_LU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.d.lu {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_reg(self.rs1) as u64;
+        let (value, flags) = softfloat::from_int(F64, rs1, false, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FEQ_D {
+    rd: XprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FEQ_D {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, rs2, _) = rv_fp_r_type(inst);
+        FEQ_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FEQ_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "feq.d {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let (value, flags) = softfloat::eq(F64, rs1, rs2);
+        state.set_reg(self.rd, value as i64);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FLT_D {
+    rd: XprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FLT_D {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, rs2, _) = rv_fp_r_type(inst);
+        FLT_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FLT_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "flt.d {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let (value, flags) = softfloat::lt(F64, rs1, rs2);
+        state.set_reg(self.rd, value as i64);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FLE_D {
+    rd: XprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FLE_D {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, rs2, _) = rv_fp_r_type(inst);
+        FLE_D { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FLE_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fle.d {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_freg_d(self.rs1);
+        let rs2 = state.get_freg_d(self.rs2);
+        let (value, flags) = softfloat::le(F64, rs1, rs2);
+        state.set_reg(self.rd, value as i64);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCLASS_D {
+    rd: XprName,
+    rs1: FprName,
+}
+impl FCLASS_D {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, _) = rv_fp_r_type(inst);
+        FCLASS_D { rd, rs1 }
+    }
+}
+impl Instruction for FCLASS_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fclass.d {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_freg_d(self.rs1);
+        state.set_reg(self.rd, softfloat::classify(F64, rs1) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMV_X_D {
+    rd: XprName,
+    rs1: FprName,
+}
+impl FMV_X_D {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, _) = rv_fp_r_type(inst);
+        FMV_X_D { rd, rs1 }
+    }
+}
+impl Instruction for FMV_X_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fmv.x.d {:?}, {:?}", self.rd, self.rs1);
+        state.set_reg(self.rd, state.fregs.get(self.rs1) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMV_D_X {
+    rd: FprName,
+    rs1: XprName,
+}
+impl FMV_D_X {
+    pub fn new(inst: u32) -> Self {
+        let (rd, _, _, _) = rv_fp_r_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        FMV_D_X { rd, rs1 }
+    }
+}
+impl Instruction for FMV_D_X {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fmv.d.x {:?}, {:?}", self.rd, self.rs1);
+        state.set_freg_d(self.rd, state.get_reg(self.rs1) as u64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_S_D {
+    rd: FprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FCVT_S_D {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _, rm) = rv_fp_r_type(inst);
+        FCVT_S_D { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_S_D {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.s.d {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_d(self.rs1);
+        let (value, flags) = softfloat::convert(F64, F32, rs1, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_D_S {
+    rd: FprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FCVT_D_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _, rm) = rv_fp_r_type(inst);
+        FCVT_D_S { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_D_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.d.s {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let (value, flags) = softfloat::convert(F32, F64, rs1, rm);
+        state.set_freg_d(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
@@ -0,0 +1,909 @@
+use super::{
+    rv32i::{rv32i_i_type, rv32i_s_type},
+    Instruction,
+};
+use crate::{
+    processor::{FprName, State, XprName},
+    softfloat::{self, F32},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+pub fn rv_fp_r_type(inst: u32) -> (FprName, FprName, FprName, u8) {
+    let rd = x(inst, 7, 5);
+    let rm = x(inst, 12, 3) as u8;
+    let rs1 = x(inst, 15, 5);
+    let rs2 = x(inst, 20, 5);
+    (
+        FprName::from_num(rd),
+        FprName::from_num(rs1),
+        FprName::from_num(rs2),
+        rm,
+    )
+}
+pub fn rv_fp_r4_type(inst: u32) -> (FprName, FprName, FprName, FprName, u8) {
+    let (rd, rs1, rs2, rm) = rv_fp_r_type(inst);
+    let rs3 = x(inst, 27, 5);
+    (rd, rs1, rs2, FprName::from_num(rs3), rm)
+}
+
+pub struct FLW {
+    rd: FprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl FLW {
+    pub fn new(inst: u32) -> Self {
+        let (_, rs1, offset) = rv32i_i_type(inst);
+        let rd = FprName::from_num(x(inst, 7, 5));
+        FLW { rd, rs1, offset }
+    }
+}
+impl Instruction for FLW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "flw {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access_u32(addr)?;
+        state.set_freg_s(self.rd, mem as u64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSW {
+    rs1: XprName,
+    rs2: FprName,
+    offset: i64,
+}
+impl FSW {
+    pub fn new(inst: u32) -> Self {
+        let (rs1, _, offset) = rv32i_s_type(inst);
+        let rs2 = FprName::from_num(x(inst, 20, 5));
+        FSW { rs1, rs2, offset }
+    }
+}
+impl Instruction for FSW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fsw {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        state.store_u32(addr, state.fregs.get(self.rs2) as u32)?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMADD_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rs3: FprName,
+    rm: u8,
+}
+impl FMADD_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rs3, rm) = rv_fp_r4_type(inst);
+        FMADD_S {
+            rd,
+            rs1,
+            rs2,
+            rs3,
+            rm,
+        }
+    }
+}
+impl Instruction for FMADD_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fmadd.s {:?}, {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.rs3
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let rs3 = state.get_freg_s(self.rs3);
+        let (value, flags) = softfloat::fma(F32, rs1, rs2, rs3, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMSUB_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rs3: FprName,
+    rm: u8,
+}
+impl FMSUB_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rs3, rm) = rv_fp_r4_type(inst);
+        FMSUB_S {
+            rd,
+            rs1,
+            rs2,
+            rs3,
+            rm,
+        }
+    }
+}
+impl Instruction for FMSUB_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fmsub.s {:?}, {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.rs3
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let rs3 = state.get_freg_s(self.rs3) ^ F32.sign_bit();
+        let (value, flags) = softfloat::fma(F32, rs1, rs2, rs3, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FNMSUB_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rs3: FprName,
+    rm: u8,
+}
+impl FNMSUB_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rs3, rm) = rv_fp_r4_type(inst);
+        FNMSUB_S {
+            rd,
+            rs1,
+            rs2,
+            rs3,
+            rm,
+        }
+    }
+}
+impl Instruction for FNMSUB_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fnmsub.s {:?}, {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.rs3
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1) ^ F32.sign_bit();
+        let rs2 = state.get_freg_s(self.rs2);
+        let rs3 = state.get_freg_s(self.rs3);
+        let (value, flags) = softfloat::fma(F32, rs1, rs2, rs3, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FNMADD_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rs3: FprName,
+    rm: u8,
+}
+impl FNMADD_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rs3, rm) = rv_fp_r4_type(inst);
+        FNMADD_S {
+            rd,
+            rs1,
+            rs2,
+            rs3,
+            rm,
+        }
+    }
+}
+impl Instruction for FNMADD_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fnmadd.s {:?}, {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.rs3
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1) ^ F32.sign_bit();
+        let rs2 = state.get_freg_s(self.rs2);
+        let rs3 = state.get_freg_s(self.rs3) ^ F32.sign_bit();
+        let (value, flags) = softfloat::fma(F32, rs1, rs2, rs3, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FADD_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rm: u8,
+}
+impl FADD_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rm) = rv_fp_r_type(inst);
+        FADD_S { rd, rs1, rs2, rm }
+    }
+}
+impl Instruction for FADD_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fadd.s {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let (value, flags) = softfloat::add(F32, rs1, rs2, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSUB_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rm: u8,
+}
+impl FSUB_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rm) = rv_fp_r_type(inst);
+        FSUB_S { rd, rs1, rs2, rm }
+    }
+}
+impl Instruction for FSUB_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fsub.s {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let (value, flags) = softfloat::sub(F32, rs1, rs2, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMUL_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rm: u8,
+}
+impl FMUL_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rm) = rv_fp_r_type(inst);
+        FMUL_S { rd, rs1, rs2, rm }
+    }
+}
+impl Instruction for FMUL_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fmul.s {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let (value, flags) = softfloat::mul(F32, rs1, rs2, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FDIV_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+    rm: u8,
+}
+impl FDIV_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, rm) = rv_fp_r_type(inst);
+        FDIV_S { rd, rs1, rs2, rm }
+    }
+}
+impl Instruction for FDIV_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fdiv.s {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let (value, flags) = softfloat::div(F32, rs1, rs2, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSQRT_S {
+    rd: FprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FSQRT_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _, rm) = rv_fp_r_type(inst);
+        FSQRT_S { rd, rs1, rm }
+    }
+}
+impl Instruction for FSQRT_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fsqrt.s {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let (value, flags) = softfloat::sqrt(F32, rs1, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSGNJ_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FSGNJ_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, _) = rv_fp_r_type(inst);
+        FSGNJ_S { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FSGNJ_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fsgnj.s {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let sign = F32.sign_bit();
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        state.set_freg_s(self.rd, (rs1 & !sign) | (rs2 & sign));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSGNJN_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FSGNJN_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, _) = rv_fp_r_type(inst);
+        FSGNJN_S { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FSGNJN_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fsgnjn.s {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let sign = F32.sign_bit();
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        state.set_freg_s(self.rd, (rs1 & !sign) | (!rs2 & sign));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FSGNJX_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FSGNJX_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, _) = rv_fp_r_type(inst);
+        FSGNJX_S { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FSGNJX_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fsgnjx.s {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let sign = F32.sign_bit();
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        state.set_freg_s(self.rd, rs1 ^ (rs2 & sign));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMIN_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FMIN_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, _) = rv_fp_r_type(inst);
+        FMIN_S { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FMIN_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fmin.s {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let (value, flags) = softfloat::min(F32, rs1, rs2);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMAX_S {
+    rd: FprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FMAX_S {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2, _) = rv_fp_r_type(inst);
+        FMAX_S { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FMAX_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "fmax.s {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let (value, flags) = softfloat::max(F32, rs1, rs2);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_W_S {
+    rd: XprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FCVT_W_S {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, rm) = rv_fp_r_type(inst);
+        FCVT_W_S { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_W_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.w.s {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let (value, flags) = softfloat::to_int(F32, rs1, rm, true, 32);
+        state.set_reg(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_WU_S {
+    rd: XprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FCVT_WU_S {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, rm) = rv_fp_r_type(inst);
+        FCVT_WU_S { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_WU_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.wu.s {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let (value, flags) = softfloat::to_int(F32, rs1, rm, false, 32);
+        state.set_reg(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_L_S {
+    rd: XprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FCVT_L_S {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, rm) = rv_fp_r_type(inst);
+        FCVT_L_S { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_L_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.l.s {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let (value, flags) = softfloat::to_int(F32, rs1, rm, true, 64);
+        state.set_reg(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_LU_S {
+    rd: XprName,
+    rs1: FprName,
+    rm: u8,
+}
+impl FCVT_LU_S {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, rm) = rv_fp_r_type(inst);
+        FCVT_LU_S { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_LU_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.lu.s {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_freg_s(self.rs1);
+        let (value, flags) = softfloat::to_int(F32, rs1, rm, false, 64);
+        state.set_reg(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_S_W {
+    rd: FprName,
+    rs1: XprName,
+    rm: u8,
+}
+impl FCVT_S_W {
+    pub fn new(inst: u32) -> Self {
+        let (rd, _, _, rm) = rv_fp_r_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        FCVT_S_W { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_S_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.s.w {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_reg(self.rs1) as i32 as u64;
+        let (value, flags) = softfloat::from_int(F32, rs1, true, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_S_WU {
+    rd: FprName,
+    rs1: XprName,
+    rm: u8,
+}
+impl FCVT_S_WU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, _, _, rm) = rv_fp_r_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        FCVT_S_WU { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_S_WU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.s.wu {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_reg(self.rs1) as u32 as u64;
+        let (value, flags) = softfloat::from_int(F32, rs1, false, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_S_L {
+    rd: FprName,
+    rs1: XprName,
+    rm: u8,
+}
+impl FCVT_S_L {
+    pub fn new(inst: u32) -> Self {
+        let (rd, _, _, rm) = rv_fp_r_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        FCVT_S_L { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_S_L {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.s.l {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_reg(self.rs1) as u64;
+        let (value, flags) = softfloat::from_int(F32, rs1, true, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCVT_S_LU {
+    rd: FprName,
+    rs1: XprName,
+    rm: u8,
+}
+impl FCVT_S_LU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, _, _, rm) = rv_fp_r_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        FCVT_S_LU { rd, rs1, rm }
+    }
+}
+impl Instruction for FCVT_S_LU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fcvt.s.lu {:?}, {:?}", self.rd, self.rs1);
+        let rm = state.rounding_mode(self.rm)?;
+        let rs1 = state.get_reg(self.rs1) as u64;
+        let (value, flags) = softfloat::from_int(F32, rs1, false, rm);
+        state.set_freg_s(self.rd, value);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FEQ_S {
+    rd: XprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FEQ_S {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, rs2, _) = rv_fp_r_type(inst);
+        FEQ_S { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FEQ_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "feq.s {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let (value, flags) = softfloat::eq(F32, rs1, rs2);
+        state.set_reg(self.rd, value as i64);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FLT_S {
+    rd: XprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FLT_S {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, rs2, _) = rv_fp_r_type(inst);
+        FLT_S { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FLT_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "flt.s {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let (value, flags) = softfloat::lt(F32, rs1, rs2);
+        state.set_reg(self.rd, value as i64);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FLE_S {
+    rd: XprName,
+    rs1: FprName,
+    rs2: FprName,
+}
+impl FLE_S {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, rs2, _) = rv_fp_r_type(inst);
+        FLE_S { rd, rs1, rs2 }
+    }
+}
+impl Instruction for FLE_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fle.s {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_freg_s(self.rs1);
+        let rs2 = state.get_freg_s(self.rs2);
+        let (value, flags) = softfloat::le(F32, rs1, rs2);
+        state.set_reg(self.rd, value as i64);
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FCLASS_S {
+    rd: XprName,
+    rs1: FprName,
+}
+impl FCLASS_S {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, _) = rv_fp_r_type(inst);
+        FCLASS_S { rd, rs1 }
+    }
+}
+impl Instruction for FCLASS_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fclass.s {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_freg_s(self.rs1);
+        state.set_reg(self.rd, softfloat::classify(F32, rs1) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMV_X_W {
+    rd: XprName,
+    rs1: FprName,
+}
+impl FMV_X_W {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let (_, rs1, _, _) = rv_fp_r_type(inst);
+        FMV_X_W { rd, rs1 }
+    }
+}
+impl Instruction for FMV_X_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fmv.x.w {:?}, {:?}", self.rd, self.rs1);
+        state.set_reg(self.rd, state.fregs.get(self.rs1) as u32 as i32 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct FMV_W_X {
+    rd: FprName,
+    rs1: XprName,
+}
+impl FMV_W_X {
+    pub fn new(inst: u32) -> Self {
+        let (rd, _, _, _) = rv_fp_r_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        FMV_W_X { rd, rs1 }
+    }
+}
+impl Instruction for FMV_W_X {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fmv.w.x {:?}, {:?}", self.rd, self.rs1);
+        state.set_freg_s(self.rd, state.get_reg(self.rs1) as u32 as u64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        csr::{MSTATUS_FS, MSTATUS_FS_INITIAL},
+        machine::tests::{machine, BASE},
+        processor::XprName,
+    };
+
+    #[test]
+    fn fs_gates_the_fp_unit_and_tracks_writes() {
+        let mut machine = machine(&[
+            0xf000_0553, // fmv.w.x fa0, zero
+            0x0010_0593, // addi a1, zero, 1
+            0xd005_f5d3, // fcvt.s.w fa1, a1
+            0x18a5_f653, // fdiv.s fa2, fa1, fa0
+            0xe006_0653, // fmv.x.w a2, fa2
+            0x0000_210c, // c.fld fa1, 0(a0)
+        ]);
+        // FS is Off out of reset, so FP instructions are illegal.
+        for (offset, inst) in [(0, 0xf000_0553), (20, 0x210c)] {
+            machine.set_pc(BASE + offset);
+            machine.run(1);
+            assert_eq!(machine.state.mcause, 2);
+            assert_eq!(machine.state.mtval, inst);
+        }
+
+        // Writing an FP register or accruing a flag makes FS Dirty.
+        machine.state.mstatus = MSTATUS_FS_INITIAL as i64;
+        machine.set_pc(BASE);
+        machine.run(1);
+        assert_eq!(machine.state.mstatus as u64 & MSTATUS_FS, MSTATUS_FS);
+        machine.run(4);
+        assert_eq!(machine.reg(XprName::a2), 0x7f80_0000);
+        assert_eq!(machine.state.fflags, 0x08);
+    }
+}
@@ -0,0 +1,305 @@
+use super::{
+    rv32i::{rv32i_i_type, rv32i_r_type, rv32i_s_type, rv32i_shamt_type},
+    Instruction,
+};
+use crate::{
+    processor::{State, XprName},
+    trap::Exception,
+    utils::trace,
+};
+
+pub struct LWU {
+    rd: XprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl LWU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, offset) = rv32i_i_type(inst);
+        LWU { rd, rs1, offset }
+    }
+}
+impl Instruction for LWU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lwu {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access_u32(addr)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct LD {
+    rd: XprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl LD {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, offset) = rv32i_i_type(inst);
+        LD { rd, rs1, offset }
+    }
+}
+impl Instruction for LD {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "ld {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access_u64(addr)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SD {
+    rs1: XprName,
+    rs2: XprName,
+    offset: i64,
+}
+impl SD {
+    pub fn new(inst: u32) -> Self {
+        let (rs1, rs2, offset) = rv32i_s_type(inst);
+        SD { rs1, rs2, offset }
+    }
+}
+impl Instruction for SD {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sd {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, rs2 as u64)?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ADDIW {
+    rd: XprName,
+    rs1: XprName,
+    imm: i64,
+}
+impl ADDIW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, imm) = rv32i_i_type(inst);
+        ADDIW { rd, rs1, imm }
+    }
+}
+impl Instruction for ADDIW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "addiw {:?}, {:?}, {}", self.rd, self.rs1, self.imm);
+        let rs1 = state.get_reg(self.rs1) as i32;
+        state.set_reg(self.rd, rs1.wrapping_add(self.imm as i32) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SLLIW {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl SLLIW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        SLLIW { rd, rs1, shamt }
+    }
+}
+impl Instruction for SLLIW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "slliw {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        let rs1 = state.get_reg(self.rs1) as i32;
+        state.set_reg(self.rd, (rs1 << self.shamt) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SRLIW {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl SRLIW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        SRLIW { rd, rs1, shamt }
+    }
+}
+impl Instruction for SRLIW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "srliw {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        let rs1 = state.get_reg(self.rs1) as u32;
+        state.set_reg(self.rd, (rs1 >> self.shamt) as i32 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SRAIW {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl SRAIW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        SRAIW { rd, rs1, shamt }
+    }
+}
+impl Instruction for SRAIW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sraiw {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        let rs1 = state.get_reg(self.rs1) as i32;
+        state.set_reg(self.rd, (rs1 >> self.shamt) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ADDW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl ADDW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        ADDW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for ADDW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "addw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.set_reg(self.rd, rs1.wrapping_add(rs2) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SUBW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SUBW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SUBW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SUBW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "subw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.set_reg(self.rd, rs1.wrapping_sub(rs2) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SLLW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SLLW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SLLW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SLLW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sllw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as i32;
+        let shamt = state.get_reg(self.rs2) & 0x1f;
+        state.set_reg(self.rd, (rs1 << shamt) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SRLW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SRLW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SRLW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SRLW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "srlw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as u32;
+        let shamt = state.get_reg(self.rs2) & 0x1f;
+        state.set_reg(self.rd, (rs1 >> shamt) as i32 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SRAW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SRAW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SRAW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SRAW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sraw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as i32;
+        let shamt = state.get_reg(self.rs2) & 0x1f;
+        state.set_reg(self.rd, (rs1 >> shamt) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        machine::tests::{machine, BASE},
+        processor::XprName,
+    };
+
+    #[test]
+    fn word_operations_sign_extend_and_wrap() {
+        let mut machine = machine(&[
+            0x8000_0537, // lui a0, 0x80000
+            0xfff5_059b, // addiw a1, a0, -1
+            0x00b5_2633, // slt a2, a0, a1
+            0x00b5_36b3, // sltu a3, a0, a1
+            0x0000_0297, // auipc t0, 0
+            0x04a2_b023, // sd a0, 64(t0)
+            0x0432_8703, // lb a4, 67(t0)
+            0x0402_e783, // lwu a5, 64(t0)
+            0x0080_00ef, // jal ra, 8
+            0x0010_0813, // addi a6, zero, 1
+            0x4045_589b, // sraiw a7, a0, 4
+        ]);
+        machine.run(10);
+        assert_eq!(machine.reg(XprName::a0), -0x8000_0000);
+        assert_eq!(machine.reg(XprName::a1), 0x7fff_ffff);
+        assert_eq!(machine.reg(XprName::a2), 1);
+        assert_eq!(machine.reg(XprName::a3), 0);
+        assert_eq!(machine.reg(XprName::a4), -0x80);
+        assert_eq!(machine.reg(XprName::a5), 0x8000_0000);
+        assert_eq!(machine.reg(XprName::ra), (BASE + 36) as i64);
+        assert_eq!(machine.reg(XprName::a6), 0);
+        assert_eq!(machine.reg(XprName::a7), -0x0800_0000);
+        assert_eq!(machine.pc(), BASE + 44);
+    }
+}
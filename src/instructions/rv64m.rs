@@ -0,0 +1,348 @@
+use super::{rv32i::rv32i_r_type, Instruction};
+use crate::{
+    processor::{State, XprName},
+    trap::Exception,
+    utils::trace,
+};
+
+pub struct MUL {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl MUL {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        MUL { rd, rs1, rs2 }
+    }
+}
+impl Instruction for MUL {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "mul {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1.wrapping_mul(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct MULH {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl MULH {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        MULH { rd, rs1, rs2 }
+    }
+}
+impl Instruction for MULH {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "mulh {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as i128;
+        let rs2 = state.get_reg(self.rs2) as i128;
+        state.set_reg(self.rd, ((rs1 * rs2) >> state.xlen_bits()) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct MULHSU {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl MULHSU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        MULHSU { rd, rs1, rs2 }
+    }
+}
+impl Instruction for MULHSU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "mulhsu {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1) as i128;
+        let rs2 = state.get_reg_u(self.rs2) as i128;
+        state.set_reg(self.rd, (rs1.wrapping_mul(rs2) >> state.xlen_bits()) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct MULHU {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl MULHU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        MULHU { rd, rs1, rs2 }
+    }
+}
+impl Instruction for MULHU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "mulhu {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg_u(self.rs1) as u128;
+        let rs2 = state.get_reg_u(self.rs2) as u128;
+        state.set_reg(self.rd, ((rs1 * rs2) >> state.xlen_bits()) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct DIV {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl DIV {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        DIV { rd, rs1, rs2 }
+    }
+}
+impl Instruction for DIV {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "div {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        // Division by zero yields all ones and the overflowing case yields the dividend.
+        let value = if rs2 == 0 { -1 } else { rs1.wrapping_div(rs2) };
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct DIVU {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl DIVU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        DIVU { rd, rs1, rs2 }
+    }
+}
+impl Instruction for DIVU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "divu {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg_u(self.rs1);
+        let rs2 = state.get_reg_u(self.rs2);
+        let value = rs1.checked_div(rs2).unwrap_or(u64::MAX);
+        state.set_reg(self.rd, value as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct REM {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl REM {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        REM { rd, rs1, rs2 }
+    }
+}
+impl Instruction for REM {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "rem {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        // The remainder of a division by zero is the dividend, and zero on overflow.
+        let value = if rs2 == 0 { rs1 } else { rs1.wrapping_rem(rs2) };
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct REMU {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl REMU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        REMU { rd, rs1, rs2 }
+    }
+}
+impl Instruction for REMU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "remu {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg_u(self.rs1);
+        let rs2 = state.get_reg_u(self.rs2);
+        let value = if rs2 == 0 { rs1 } else { rs1 % rs2 };
+        state.set_reg(self.rd, value as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct MULW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl MULW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        MULW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for MULW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "mulw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.set_reg(self.rd, rs1.wrapping_mul(rs2) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct DIVW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl DIVW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        DIVW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for DIVW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "divw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        let value = if rs2 == 0 { -1 } else { rs1.wrapping_div(rs2) };
+        state.set_reg(self.rd, value as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct DIVUW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl DIVUW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        DIVUW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for DIVUW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "divuw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as u32;
+        let rs2 = state.get_reg(self.rs2) as u32;
+        let value = rs1.checked_div(rs2).unwrap_or(u32::MAX);
+        state.set_reg(self.rd, value as i32 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct REMW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl REMW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        REMW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for REMW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "remw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        let value = if rs2 == 0 { rs1 } else { rs1.wrapping_rem(rs2) };
+        state.set_reg(self.rd, value as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct REMUW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl REMUW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        REMUW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for REMUW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "remuw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1) as u32;
+        let rs2 = state.get_reg(self.rs2) as u32;
+        let value = if rs2 == 0 { rs1 } else { rs1 % rs2 };
+        state.set_reg(self.rd, value as i32 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{machine::tests::machine, processor::XprName};
+
+    #[test]
+    fn division_overflow_and_by_zero_do_not_trap() {
+        let mut machine = machine(&[
+            0xfff0_0513, // addi a0, zero, -1
+            0x03f5_1593, // slli a1, a0, 63
+            0x02a5_c633, // div a2, a1, a0
+            0x02a5_e6b3, // rem a3, a1, a0
+            0x0205_c733, // div a4, a1, zero
+            0x0205_d7b3, // divu a5, a1, zero
+            0x0205_e833, // rem a6, a1, zero
+            0x0205_78b3, // remu a7, a0, zero
+            0x8000_0337, // lui t1, 0x80000
+            0x02a3_42bb, // divw t0, t1, a0
+            0x02a3_63bb, // remw t2, t1, a0
+            0x0203_5e3b, // divuw t3, t1, zero
+            0x02b5_9433, // mulh s0, a1, a1
+            0x02a5_24b3, // mulhsu s1, a0, a0
+            0x02a5_3933, // mulhu s2, a0, a0
+        ]);
+        machine.run(15);
+        assert_eq!(machine.reg(XprName::a2), i64::MIN);
+        assert_eq!(machine.reg(XprName::a3), 0);
+        assert_eq!(machine.reg(XprName::a4), -1);
+        assert_eq!(machine.reg(XprName::a5), -1);
+        assert_eq!(machine.reg(XprName::a6), i64::MIN);
+        assert_eq!(machine.reg(XprName::a7), -1);
+        assert_eq!(machine.reg(XprName::t0), i32::MIN as i64);
+        assert_eq!(machine.reg(XprName::t2), 0);
+        assert_eq!(machine.reg(XprName::t3), -1);
+        assert_eq!(machine.reg(XprName::s0), 1 << 62);
+        assert_eq!(machine.reg(XprName::s1), -1);
+        assert_eq!(machine.reg(XprName::s2), -2);
+        assert_eq!(machine.state.mcause, 0);
+    }
+}
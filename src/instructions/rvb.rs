@@ -0,0 +1,1221 @@
+use super::{
+    rv32i::{rv32i_r_type, rv32i_shamt_type},
+    Instruction,
+};
+use crate::{
+    processor::{State, XprName, XLEN},
+    trap::Exception,
+    utils::trace,
+};
+
+/// Rotates the low XLEN bits of `value` right by `amount` modulo XLEN.
+fn rotate_right(state: &State, value: i64, amount: i64) -> i64 {
+    match state.xlen {
+        XLEN::RV32 => (value as u32).rotate_right(amount as u32) as i32 as i64,
+        XLEN::RV64 | XLEN::RV128 => value.rotate_right(amount as u32),
+    }
+}
+
+/// The full carry-less product of two XLEN-bit values.
+fn clmul(a: u64, b: u64) -> u128 {
+    (0..64)
+        .filter(|i| b >> i & 1 != 0)
+        .fold(0, |product, i| product ^ (a as u128) << i)
+}
+
+pub struct SH1ADD {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SH1ADD {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SH1ADD { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SH1ADD {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sh1add {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, (rs1 << 1).wrapping_add(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SH2ADD {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SH2ADD {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SH2ADD { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SH2ADD {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sh2add {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, (rs1 << 2).wrapping_add(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SH3ADD {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SH3ADD {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SH3ADD { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SH3ADD {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sh3add {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, (rs1 << 3).wrapping_add(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ADD_UW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl ADD_UW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        ADD_UW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for ADD_UW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "add.uw {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, (rs1 as u32 as i64).wrapping_add(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SH1ADD_UW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SH1ADD_UW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SH1ADD_UW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SH1ADD_UW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sh1add.uw {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, ((rs1 as u32 as i64) << 1).wrapping_add(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SH2ADD_UW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SH2ADD_UW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SH2ADD_UW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SH2ADD_UW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sh2add.uw {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, ((rs1 as u32 as i64) << 2).wrapping_add(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SH3ADD_UW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SH3ADD_UW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SH3ADD_UW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SH3ADD_UW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sh3add.uw {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, ((rs1 as u32 as i64) << 3).wrapping_add(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SLLI_UW {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl SLLI_UW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        SLLI_UW { rd, rs1, shamt }
+    }
+}
+impl Instruction for SLLI_UW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "slli.uw {:?}, {:?}, {}",
+            self.rd,
+            self.rs1,
+            self.shamt
+        );
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, (rs1 as u32 as i64) << self.shamt);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ANDN {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl ANDN {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        ANDN { rd, rs1, rs2 }
+    }
+}
+impl Instruction for ANDN {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "andn {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1 & !rs2);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ORN {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl ORN {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        ORN { rd, rs1, rs2 }
+    }
+}
+impl Instruction for ORN {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "orn {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1 | !rs2);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct XNOR {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl XNOR {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        XNOR { rd, rs1, rs2 }
+    }
+}
+impl Instruction for XNOR {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "xnor {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, !(rs1 ^ rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CLZ {
+    rd: XprName,
+    rs1: XprName,
+}
+impl CLZ {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        CLZ { rd, rs1 }
+    }
+}
+impl Instruction for CLZ {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "clz {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let value = match state.xlen {
+            XLEN::RV32 => (rs1 as u32).leading_zeros() as i64,
+            XLEN::RV64 | XLEN::RV128 => (rs1 as u64).leading_zeros() as i64,
+        };
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CTZ {
+    rd: XprName,
+    rs1: XprName,
+}
+impl CTZ {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        CTZ { rd, rs1 }
+    }
+}
+impl Instruction for CTZ {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "ctz {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let value = match state.xlen {
+            XLEN::RV32 => (rs1 as u32).trailing_zeros() as i64,
+            XLEN::RV64 | XLEN::RV128 => (rs1 as u64).trailing_zeros() as i64,
+        };
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CPOP {
+    rd: XprName,
+    rs1: XprName,
+}
+impl CPOP {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        CPOP { rd, rs1 }
+    }
+}
+impl Instruction for CPOP {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "cpop {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, state.zext_xlen(rs1).count_ones() as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CLZW {
+    rd: XprName,
+    rs1: XprName,
+}
+impl CLZW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        CLZW { rd, rs1 }
+    }
+}
+impl Instruction for CLZW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "clzw {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, (rs1 as u32).leading_zeros() as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CTZW {
+    rd: XprName,
+    rs1: XprName,
+}
+impl CTZW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        CTZW { rd, rs1 }
+    }
+}
+impl Instruction for CTZW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "ctzw {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, (rs1 as u32).trailing_zeros() as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CPOPW {
+    rd: XprName,
+    rs1: XprName,
+}
+impl CPOPW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        CPOPW { rd, rs1 }
+    }
+}
+impl Instruction for CPOPW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "cpopw {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, (rs1 as u32).count_ones() as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct MAX {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl MAX {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        MAX { rd, rs1, rs2 }
+    }
+}
+impl Instruction for MAX {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "max {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1.max(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct MAXU {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl MAXU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        MAXU { rd, rs1, rs2 }
+    }
+}
+impl Instruction for MAXU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "maxu {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(
+            self.rd,
+            state.zext_xlen(rs1).max(state.zext_xlen(rs2)) as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct MIN {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl MIN {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        MIN { rd, rs1, rs2 }
+    }
+}
+impl Instruction for MIN {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "min {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1.min(rs2));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct MINU {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl MINU {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        MINU { rd, rs1, rs2 }
+    }
+}
+impl Instruction for MINU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "minu {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(
+            self.rd,
+            state.zext_xlen(rs1).min(state.zext_xlen(rs2)) as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SEXT_B {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SEXT_B {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SEXT_B { rd, rs1 }
+    }
+}
+impl Instruction for SEXT_B {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sext.b {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, rs1 as i8 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SEXT_H {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SEXT_H {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SEXT_H { rd, rs1 }
+    }
+}
+impl Instruction for SEXT_H {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sext.h {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, rs1 as i16 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ZEXT_H {
+    rd: XprName,
+    rs1: XprName,
+}
+impl ZEXT_H {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        ZEXT_H { rd, rs1 }
+    }
+}
+impl Instruction for ZEXT_H {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "zext.h {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, rs1 as u16 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ROL {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl ROL {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        ROL { rd, rs1, rs2 }
+    }
+}
+impl Instruction for ROL {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "rol {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value = rotate_right(
+            state,
+            rs1,
+            state.xlen_bits() as i64 - (rs2 & state.shamt_mask()),
+        );
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ROR {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl ROR {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        ROR { rd, rs1, rs2 }
+    }
+}
+impl Instruction for ROR {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "ror {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rotate_right(state, rs1, rs2 & state.shamt_mask()));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct RORI {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl RORI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        RORI { rd, rs1, shamt }
+    }
+}
+impl Instruction for RORI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "rori {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, rotate_right(state, rs1, self.shamt as i64));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ROLW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl ROLW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        ROLW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for ROLW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "rolw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(
+            self.rd,
+            (rs1 as u32).rotate_left(rs2 as u32 & 0x1f) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct RORW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl RORW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        RORW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for RORW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "rorw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(
+            self.rd,
+            (rs1 as u32).rotate_right(rs2 as u32 & 0x1f) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct RORIW {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl RORIW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        RORIW { rd, rs1, shamt }
+    }
+}
+impl Instruction for RORIW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "roriw {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, (rs1 as u32).rotate_right(self.shamt) as i32 as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ORC_B {
+    rd: XprName,
+    rs1: XprName,
+}
+impl ORC_B {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        ORC_B { rd, rs1 }
+    }
+}
+impl Instruction for ORC_B {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "orc.b {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let value = (0..8).fold(0, |value, byte| {
+            if rs1 >> (byte * 8) & 0xff != 0 {
+                value | 0xff << (byte * 8)
+            } else {
+                value
+            }
+        });
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct REV8 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl REV8 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        REV8 { rd, rs1 }
+    }
+}
+impl Instruction for REV8 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "rev8 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let value = match state.xlen {
+            XLEN::RV32 => (rs1 as u32).swap_bytes() as i64,
+            XLEN::RV64 | XLEN::RV128 => rs1.swap_bytes(),
+        };
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CLMUL {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl CLMUL {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        CLMUL { rd, rs1, rs2 }
+    }
+}
+impl Instruction for CLMUL {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "clmul {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(
+            self.rd,
+            clmul(state.zext_xlen(rs1), state.zext_xlen(rs2)) as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CLMULH {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl CLMULH {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        CLMULH { rd, rs1, rs2 }
+    }
+}
+impl Instruction for CLMULH {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "clmulh {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value = (clmul(state.zext_xlen(rs1), state.zext_xlen(rs2)) >> state.xlen_bits()) as i64;
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CLMULR {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl CLMULR {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        CLMULR { rd, rs1, rs2 }
+    }
+}
+impl Instruction for CLMULR {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "clmulr {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value =
+            (clmul(state.zext_xlen(rs1), state.zext_xlen(rs2)) >> (state.xlen_bits() - 1)) as i64;
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct BCLR {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl BCLR {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        BCLR { rd, rs1, rs2 }
+    }
+}
+impl Instruction for BCLR {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "bclr {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1 & !(1 << (rs2 & state.shamt_mask())));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct BCLRI {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl BCLRI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        BCLRI { rd, rs1, shamt }
+    }
+}
+impl Instruction for BCLRI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "bclri {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, rs1 & !(1 << self.shamt));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct BEXT {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl BEXT {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        BEXT { rd, rs1, rs2 }
+    }
+}
+impl Instruction for BEXT {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "bext {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1 >> (rs2 & state.shamt_mask()) & 1);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct BEXTI {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl BEXTI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        BEXTI { rd, rs1, shamt }
+    }
+}
+impl Instruction for BEXTI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "bexti {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, rs1 >> self.shamt & 1);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct BINV {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl BINV {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        BINV { rd, rs1, rs2 }
+    }
+}
+impl Instruction for BINV {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "binv {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1 ^ 1 << (rs2 & state.shamt_mask()));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct BINVI {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl BINVI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        BINVI { rd, rs1, shamt }
+    }
+}
+impl Instruction for BINVI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "binvi {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, rs1 ^ 1 << self.shamt);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct BSET {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl BSET {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        BSET { rd, rs1, rs2 }
+    }
+}
+impl Instruction for BSET {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "bset {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rs1 | 1 << (rs2 & state.shamt_mask()));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct BSETI {
+    rd: XprName,
+    rs1: XprName,
+    shamt: u32,
+}
+impl BSETI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, shamt) = rv32i_shamt_type(inst);
+        BSETI { rd, rs1, shamt }
+    }
+}
+impl Instruction for BSETI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "bseti {:?}, {:?}, {}", self.rd, self.rs1, self.shamt);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, rs1 | 1 << self.shamt);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        machine::{tests::machine_with, Machine},
+        processor::XprName::{self, *},
+    };
+
+    const RV32: &str = "rv32imac_zba_zbb_zbc_zbs";
+    const RV64: &str = "rv64imac_zba_zbb_zbc_zbs";
+
+    /// Runs `code` with `a0` and `a1` as its operands.
+    fn run(isa: &str, code: &[u32], rs1: u64, rs2: u64) -> Machine {
+        let mut machine = machine_with(isa, code);
+        machine.set_reg(a0, rs1 as i64);
+        machine.set_reg(a1, rs2 as i64);
+        machine.run(code.len() as u64);
+        assert_eq!(machine.state.mcause, 0, "unexpected trap");
+        machine
+    }
+
+    fn assert_regs(machine: &Machine, expected: &[(XprName, u64)]) {
+        for &(reg, value) in expected {
+            assert_eq!(machine.state.get_reg_u(reg), value, "{:?}", reg);
+        }
+    }
+
+    #[test]
+    fn carry_less_multiply() {
+        let code = [
+            0x0ab5_1633, // clmul a2, a0, a1
+            0x0ab5_36b3, // clmulh a3, a0, a1
+            0x0ab5_2733, // clmulr a4, a0, a1
+        ];
+        let machine = run(RV64, &code, 0x8000_0000_0000_0003, 7);
+        assert_regs(&machine, &[(a2, 0x8000_0000_0000_0009), (a3, 3), (a4, 7)]);
+        let machine = run(RV32, &code, 0x8000_0003, 7);
+        assert_regs(&machine, &[(a2, 0x8000_0009), (a3, 3), (a4, 7)]);
+    }
+
+    #[test]
+    fn byte_reversal_and_or_combine() {
+        let machine = run(
+            RV64,
+            &[
+                0x6b85_5613, // rev8 a2, a0
+                0x2875_5693, // orc.b a3, a0
+            ],
+            0x0102_0304_0050_0007,
+            0,
+        );
+        assert_regs(
+            &machine,
+            &[(a2, 0x0700_5000_0403_0201), (a3, 0xffff_ffff_00ff_00ff)],
+        );
+        let machine = run(
+            RV32,
+            &[
+                0x6985_5613, // rev8 a2, a0
+                0x2875_5693, // orc.b a3, a0
+            ],
+            0x0100_5007,
+            0,
+        );
+        assert_regs(&machine, &[(a2, 0x0750_0001), (a3, 0xff00_ffff)]);
+    }
+
+    #[test]
+    fn rotates_wrap_at_xlen() {
+        let code = [
+            0x60b5_1633, // rol a2, a0, a1
+            0x60b5_56b3, // ror a3, a0, a1
+            0x6085_5713, // rori a4, a0, 8
+        ];
+        let word = [
+            0x60b5_17bb, // rolw a5, a0, a1
+            0x60b5_583b, // rorw a6, a0, a1
+            0x6085_589b, // roriw a7, a0, 8
+        ];
+        let machine = run(RV64, &[code, word].concat(), 0x8000_0000_0000_0008, 4);
+        assert_regs(
+            &machine,
+            &[
+                (a2, 0x88),
+                (a3, 0x8800_0000_0000_0000),
+                (a4, 0x0880_0000_0000_0000),
+                (a5, 0x80),
+                (a6, 0xffff_ffff_8000_0000),
+                (a7, 0x0800_0000),
+            ],
+        );
+        let machine = run(RV32, &code, 0x8000_0008, 4);
+        assert_regs(
+            &machine,
+            &[(a2, 0x88), (a3, 0x8800_0000), (a4, 0x0880_0000)],
+        );
+    }
+
+    #[test]
+    fn shift_and_add() {
+        let code = [
+            0x20b5_2633, // sh1add a2, a0, a1
+            0x20b5_46b3, // sh2add a3, a0, a1
+            0x20b5_6733, // sh3add a4, a0, a1
+        ];
+        let unsigned_word = [
+            0x08b5_07bb, // add.uw a5, a0, a1
+            0x20b5_283b, // sh1add.uw a6, a0, a1
+            0x20b5_48bb, // sh2add.uw a7, a0, a1
+            0x20b5_62bb, // sh3add.uw t0, a0, a1
+            0x0845_131b, // slli.uw t1, a0, 4
+        ];
+        let code64 = [code.as_slice(), &unsigned_word].concat();
+        let machine = run(RV64, &code64, 0xffff_ffff_8000_0010, 0x1000);
+        assert_regs(
+            &machine,
+            &[
+                (a2, 0xffff_ffff_0000_1020),
+                (a3, 0xffff_fffe_0000_1040),
+                (a4, 0xffff_fffc_0000_1080),
+                (a5, 0x8000_1010),
+                (a6, 0x1_0000_1020),
+                (a7, 0x2_0000_1040),
+                (t0, 0x4_0000_1080),
+                (t1, 0x8_0000_0100),
+            ],
+        );
+        let machine = run(RV32, &code, 0x8000_0010, 0x1000);
+        assert_regs(&machine, &[(a2, 0x1020), (a3, 0x1040), (a4, 0x1080)]);
+
+        // The .uw forms live in OP-32, which RV32 does not have.
+        let mut machine = machine_with(RV32, &unsigned_word);
+        machine.step();
+        assert_eq!(machine.state.mcause, 2);
+    }
+
+    #[test]
+    fn single_bit_immediates() {
+        let code = [
+            0x2845_1613, // bseti a2, a0, 4
+            0x4815_1693, // bclri a3, a0, 1
+            0x69f5_1713, // binvi a4, a0, 31
+            0x49f5_5793, // bexti a5, a0, 31
+        ];
+        let wide = [
+            0x2a85_1813, // bseti a6, a0, 40
+            0x4bf5_5893, // bexti a7, a0, 63
+            0x4bf5_1293, // bclri t0, a0, 63
+            0x6a05_1313, // binvi t1, a0, 32
+        ];
+        let machine = run(RV64, &[code, wide].concat(), 0x8000_0000_0000_0002, 0);
+        assert_regs(
+            &machine,
+            &[
+                (a2, 0x8000_0000_0000_0012),
+                (a3, 0x8000_0000_0000_0000),
+                (a4, 0x8000_0000_8000_0002),
+                (a5, 0),
+                (a6, 0x8000_0100_0000_0002),
+                (a7, 1),
+                (t0, 2),
+                (t1, 0x8000_0001_0000_0002),
+            ],
+        );
+        let machine = run(RV32, &code, 0x8000_0002, 0);
+        assert_regs(
+            &machine,
+            &[(a2, 0x8000_0012), (a3, 0x8000_0000), (a4, 2), (a5, 1)],
+        );
+
+        // A shift amount of 32 or more is reserved on RV32.
+        for inst in wide {
+            let mut machine = machine_with(RV32, &[inst]);
+            machine.step();
+            assert_eq!(machine.state.mcause, 2, "{:#010x}", inst);
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use super::Instruction;
 use crate::{
-    processor::{State, XprName, XprName::sp},
-    utils::x,
+    processor::{FprName, State, XprName, XprName::ra, XprName::sp},
+    trap::Exception,
+    utils::{trace, x},
 };
 
 #[inline]
@@ -11,9 +12,10 @@ fn rvc_cr_type(inst: u16) -> (XprName, XprName) {
     (XprName::from_num(rs1), XprName::from_num(rs2))
 }
 #[inline]
-fn rvc_ci_type(inst: u16) -> XprName {
-    let rs1 = x(inst, 7, 5);
-    XprName::from_num(rs1)
+fn rvc_ci_type(inst: u16) -> (XprName, i64) {
+    let rd = x(inst, 7, 5);
+    let imm = x(inst, 2, 5) - (x(inst, 12, 1) << 5);
+    (XprName::from_num(rd), imm)
 }
 #[inline]
 fn rvc_css_type(inst: u16) -> (XprName, i64) {
@@ -27,63 +29,89 @@ fn rvc_ciw_type(inst: u16) -> XprName {
     XprName::from_num(rd + 0x8)
 }
 #[inline]
-fn rvc_cl_type(inst: u16) -> (XprName, XprName) {
+pub(super) fn rvc_cl_type(inst: u16) -> (XprName, XprName) {
     let rd = x(inst, 2, 3);
     let rs1 = x(inst, 7, 3);
     (XprName::from_num(rd + 0x8), XprName::from_num(rs1 + 0x8))
 }
 #[inline]
-fn rvc_cs_type(inst: u16) -> (XprName, XprName) {
+pub(super) fn rvc_cs_type(inst: u16) -> (XprName, XprName) {
     let rs2 = x(inst, 2, 3);
     let rs1 = x(inst, 7, 3);
     (XprName::from_num(rs2 + 0x8), XprName::from_num(rs1 + 0x8))
 }
 #[inline]
-fn rvc_cb_type(inst: u16) -> XprName {
+pub(super) fn rvc_cb_type(inst: u16) -> XprName {
     let rs1 = x(inst, 7, 3);
     XprName::from_num(rs1 + 0x8)
 }
+/// Offset of c.beqz and c.bnez: `offset[8|4:3]` in bits 12:10, `offset[7:6|2:1|5]` in 6:2.
+#[inline]
+fn rvc_cb_offset(inst: u16) -> i64 {
+    (x(inst, 2, 1) << 5) + (x(inst, 3, 2) << 1) + (x(inst, 5, 2) << 6) + (x(inst, 10, 2) << 3)
+        - (x(inst, 12, 1) << 8)
+}
 #[inline]
 fn rvc_cj_type(inst: u16) -> i64 {
-    let imm = (x(inst, 2, 1) << 5)
+    (x(inst, 2, 1) << 5)
         + (x(inst, 3, 3) << 1)
         + (x(inst, 6, 1) << 7)
         + (x(inst, 7, 1) << 6)
         + (x(inst, 8, 1) << 10)
         + (x(inst, 9, 2) << 8)
         + (x(inst, 11, 1) << 4)
-        + (x(inst, 12, 1) << 11);
-    imm
+        - (x(inst, 12, 1) << 11)
+}
+/// The 6-bit shift amount of c.slli, c.srli and c.srai.
+#[inline]
+fn rvc_shamt(inst: u16) -> u32 {
+    (x(inst, 2, 5) + (x(inst, 12, 1) << 5)) as u32
 }
 
-pub struct C_ADDI4SPN(u16);
+pub struct C_ADDI4SPN {
+    rd: XprName,
+    imm: i64,
+}
 impl C_ADDI4SPN {
     pub fn new(inst: u16) -> Self {
-        C_ADDI4SPN(inst)
+        let rd = rvc_ciw_type(inst);
+        let imm = (x(inst, 5, 1) << 3)
+            + (x(inst, 6, 1) << 2)
+            + (x(inst, 7, 4) << 6)
+            + (x(inst, 11, 2) << 4);
+        C_ADDI4SPN { rd, imm }
     }
 }
 impl Instruction for C_ADDI4SPN {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "addi {:?}, sp, {}", self.rd, self.imm);
+        state.set_reg(self.rd, state.get_reg(sp).wrapping_add(self.imm));
         state.pc += 2;
+        Ok(())
     }
 }
 
 pub struct C_FLD {
-    rd: XprName,
+    rd: FprName,
     rs1: XprName,
-    uimm: u64,
+    offset: u64,
 }
 impl C_FLD {
     pub fn new(inst: u16) -> Self {
-        let (rd, rs1) = rvc_cl_type(inst);
-        let uimm: u64 =
-            ((x(inst, 5, 1) << 6) + (x(inst, 6, 1) << 2) + (x(inst, 10, 3) << 3)) as u64;
-        C_FLD { rd, rs1, uimm }
+        let (_, rs1) = rvc_cl_type(inst);
+        let rd = FprName::from_num(x(inst, 2, 3) + 0x8);
+        let offset: u64 = ((x(inst, 5, 2) << 6) + (x(inst, 10, 3) << 3)) as u64;
+        C_FLD { rd, rs1, offset }
     }
 }
 impl Instruction for C_FLD {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fld {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset as i64);
+        let mem = state.access_u64(addr)?;
+        state.set_freg_d(self.rd, mem);
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -101,13 +129,14 @@ impl C_LQ {
     }
 }
 impl Instruction for C_LQ {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
         // TODO: 128 bit
-        println!("lq {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        trace!(state, "lq {:?}, {}({:?})", self.rd, self.offset, self.rs1);
         let addr = state.get_reg(self.rs1) + self.offset as i64;
-        let mem = state.access_u64(addr);
+        let mem = state.access_u64(addr)?;
         state.set_reg(self.rd, mem as i64);
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -125,12 +154,38 @@ impl C_LW {
     }
 }
 impl Instruction for C_LW {
-    fn execute(&self, state: &mut State) {
-        println!("lw {:?}, {}({:?})", self.rd, self.offset, self.rs1);
-        let addr = state.get_reg(self.rs1) + self.offset as i64;
-        let mem = state.access_u32(addr);
-        state.set_reg(self.rd, mem as i64);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lw {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset as i64);
+        let mem = state.access_u32(addr)?;
+        state.set_reg(self.rd, mem as i32 as i64);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_FLW {
+    rd: FprName,
+    rs1: XprName,
+    offset: u64,
+}
+impl C_FLW {
+    pub fn new(inst: u16) -> Self {
+        let (_, rs1) = rvc_cl_type(inst);
+        let rd = FprName::from_num(x(inst, 2, 3) + 0x8);
+        let offset: u64 =
+            ((x(inst, 5, 1) << 6) + (x(inst, 6, 1) << 2) + (x(inst, 10, 3) << 3)) as u64;
+        C_FLW { rd, rs1, offset }
+    }
+}
+impl Instruction for C_FLW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "flw {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset as i64);
+        let mem = state.access_u32(addr)?;
+        state.set_freg_s(self.rd, mem as u64);
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -141,40 +196,47 @@ pub struct C_LD {
 }
 impl C_LD {
     pub fn new(inst: u16) -> Self {
-        let (rd, rs1) = rvc_cs_type(inst);
+        let (rd, rs1) = rvc_cl_type(inst);
         let offset = ((x(inst, 5, 2) << 6) + (x(inst, 10, 3) << 3)) as u64;
         C_LD { rd, rs1, offset }
     }
 }
 impl Instruction for C_LD {
-    fn execute(&self, state: &mut State) {
-        println!("ld {:?}, {}({:?})", self.rd, self.offset, self.rs1);
-        let addr = state.get_reg(self.rs1) + self.offset as i64;
-        let mem = state.access_u32(addr);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "ld {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset as i64);
+        let mem = state.access_u64(addr)?;
         state.set_reg(self.rd, mem as i64);
         state.pc += 2;
+        Ok(())
     }
 }
 
 pub struct C_FSD {
-    rd: XprName,
     rs1: XprName,
+    rs2: FprName,
     offset: u64,
 }
 impl C_FSD {
     pub fn new(inst: u16) -> Self {
-        let (rd, rs1) = rvc_cl_type(inst);
-        let offset: u64 =
-            ((x(inst, 5, 1) << 6) + (x(inst, 6, 1) << 2) + (x(inst, 10, 3) << 3)) as u64;
-        C_FSD { rd, rs1, offset }
+        let (_, rs1) = rvc_cs_type(inst);
+        let rs2 = FprName::from_num(x(inst, 2, 3) + 0x8);
+        let offset: u64 = ((x(inst, 5, 2) << 6) + (x(inst, 10, 3) << 3)) as u64;
+        C_FSD { rs1, rs2, offset }
     }
 }
 impl Instruction for C_FSD {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fsd {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset as i64);
+        state.store_u64(addr, state.fregs.get(self.rs2))?;
         state.pc += 2;
+        Ok(())
     }
 }
 
+// Decoded for RV128, whose 128-bit stores are not executed yet.
+#[allow(dead_code)]
 pub struct C_SQ {
     rs1: XprName,
     rs2: XprName,
@@ -189,9 +251,10 @@ impl C_SQ {
     }
 }
 impl Instruction for C_SQ {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
         // TODO: 128 bit
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -204,13 +267,42 @@ impl C_SW {
     pub fn new(inst: u16) -> Self {
         let (rs2, rs1) = rvc_cs_type(inst);
         let offset: u64 =
-            ((x(inst, 5, 2) << 6) + (x(inst, 10, 1) << 8) + (x(inst, 11, 2) << 4)) as u64;
+            ((x(inst, 5, 1) << 6) + (x(inst, 6, 1) << 2) + (x(inst, 10, 3) << 3)) as u64;
         C_SW { rs2, rs1, offset }
     }
 }
 impl Instruction for C_SW {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sw {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset as i64);
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u32(addr, rs2 as u32)?;
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_FSW {
+    rs1: XprName,
+    rs2: FprName,
+    offset: u64,
+}
+impl C_FSW {
+    pub fn new(inst: u16) -> Self {
+        let (_, rs1) = rvc_cs_type(inst);
+        let rs2 = FprName::from_num(x(inst, 2, 3) + 0x8);
+        let offset: u64 =
+            ((x(inst, 5, 1) << 6) + (x(inst, 6, 1) << 2) + (x(inst, 10, 3) << 3)) as u64;
+        C_FSW { rs1, rs2, offset }
+    }
+}
+impl Instruction for C_FSW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fsw {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset as i64);
+        state.store_u32(addr, state.fregs.get(self.rs2) as u32)?;
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -227,24 +319,27 @@ impl C_SD {
     }
 }
 impl Instruction for C_SD {
-    fn execute(&self, state: &mut State) {
-        println!("sd {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
-        let addr = state.get_reg(self.rs1) + self.offset as i64;
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sd {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset as i64);
         let rs2 = state.get_reg(self.rs2);
-        state.store_u64(addr, rs2 as u64);
+        state.store_u64(addr, rs2 as u64)?;
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_NOP(u16);
+pub struct C_NOP;
 impl C_NOP {
-    pub fn new(inst: u16) -> Self {
-        C_NOP(inst)
+    pub fn new(_inst: u16) -> Self {
+        C_NOP
     }
 }
 impl Instruction for C_NOP {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "nop");
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -254,20 +349,17 @@ pub struct C_ADDI {
 }
 impl C_ADDI {
     pub fn new(inst: u16) -> Self {
-        let imm = x(inst, 2, 5) - (x(inst, 12, 1) << 5);
-        let rd = x(inst, 7, 5) as usize;
-        C_ADDI {
-            imm,
-            rd: XprName::n(rd as i64).unwrap(),
-        }
+        let (rd, imm) = rvc_ci_type(inst);
+        C_ADDI { imm, rd }
     }
 }
 impl Instruction for C_ADDI {
-    fn execute(&self, state: &mut State) {
-        println!("addi {:?}, {:?}, {}", self.rd, self.rd, self.imm);
-        let rd_value = state.regs.get(self.rd);
-        state.regs.set(self.rd, rd_value + self.imm);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "addi {:?}, {:?}, {}", self.rd, self.rd, self.imm);
+        let rd_value = state.get_reg(self.rd);
+        state.set_reg(self.rd, rd_value.wrapping_add(self.imm));
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -281,9 +373,32 @@ impl C_JAL {
     }
 }
 impl Instruction for C_JAL {
-    fn execute(&self, state: &mut State) {
-        println!("jal {}", self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "jal {}", self.offset);
+        let link = state.pc + 2;
+        state.jump(state.pc.wrapping_add(self.offset))?;
+        state.set_reg(ra, link);
+        Ok(())
+    }
+}
+
+pub struct C_ADDIW {
+    rd: XprName,
+    imm: i64,
+}
+impl C_ADDIW {
+    pub fn new(inst: u16) -> Self {
+        let (rd, imm) = rvc_ci_type(inst);
+        C_ADDIW { rd, imm }
+    }
+}
+impl Instruction for C_ADDIW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "addiw {:?}, {:?}, {}", self.rd, self.rd, self.imm);
+        let rd = state.get_reg(self.rd) as i32;
+        state.set_reg(self.rd, rd.wrapping_add(self.imm as i32) as i64);
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -293,96 +408,104 @@ pub struct C_LI {
 }
 impl C_LI {
     pub fn new(inst: u16) -> Self {
-        let rd = rvc_ci_type(inst);
-        let imm = x(inst, 2, 5) + (x(inst, 12, 1) << 5);
+        let (rd, imm) = rvc_ci_type(inst);
         C_LI { rd, imm }
     }
 }
 impl Instruction for C_LI {
-    fn execute(&self, state: &mut State) {
-        println!("li {:?}, {}", self.rd, self.imm);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "li {:?}, {}", self.rd, self.imm);
         state.set_reg(self.rd, self.imm);
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_SRLI {
-    rd: XprName,
-    shamt: i64,
+pub struct C_ADDI16SP {
+    imm: i64,
 }
-impl C_SRLI {
+impl C_ADDI16SP {
     pub fn new(inst: u16) -> Self {
-        let rd = rvc_cb_type(inst);
-        let shamt = x(inst, 2, 5) + (x(inst, 12, 1) << 5);
-        C_SRLI { rd, shamt }
+        let imm = (x(inst, 2, 1) << 5)
+            + (x(inst, 3, 2) << 7)
+            + (x(inst, 5, 1) << 6)
+            + (x(inst, 6, 1) << 4)
+            - (x(inst, 12, 1) << 9);
+        C_ADDI16SP { imm }
     }
 }
-impl Instruction for C_SRLI {
-    fn execute(&self, state: &mut State) {
-        println!("srli {:?}, {}", self.rd, self.shamt);
-        // TODO: Logical ?
-        let rd = state.get_reg(self.rd);
-        state.set_reg(self.rd, rd >> self.shamt);
+impl Instruction for C_ADDI16SP {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "addi sp, sp, {}", self.imm);
+        state.set_reg(sp, state.get_reg(sp).wrapping_add(self.imm));
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_SRLI64 {
+pub struct C_LUI {
     rd: XprName,
-    shamt: i64,
+    imm: i64,
 }
-impl C_SRLI64 {
+impl C_LUI {
     pub fn new(inst: u16) -> Self {
-        let rd = rvc_cb_type(inst);
-        C_SRLI64 { rd, shamt: 64 }
+        let (rd, imm) = rvc_ci_type(inst);
+        C_LUI { rd, imm: imm << 12 }
     }
 }
-impl Instruction for C_SRLI64 {
-    fn execute(&self, state: &mut State) {
-        println!("srli64 {:?}, {}", self.rd, self.shamt);
-        let rd = state.get_reg(self.rd);
-        state.set_reg(self.rd, rd >> self.shamt);
+impl Instruction for C_LUI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "lui {:?}, {:#x}",
+            self.rd,
+            (self.imm >> 12) & 0xfffff
+        );
+        state.set_reg(self.rd, self.imm);
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_SRAI {
+pub struct C_SRLI {
     rd: XprName,
-    shamt: i64,
+    shamt: u32,
 }
-impl C_SRAI {
+impl C_SRLI {
     pub fn new(inst: u16) -> Self {
         let rd = rvc_cb_type(inst);
-        let shamt = x(inst, 2, 5) + (x(inst, 12, 1) << 5);
-        C_SRAI { rd, shamt }
+        let shamt = rvc_shamt(inst);
+        C_SRLI { rd, shamt }
     }
 }
-impl Instruction for C_SRAI {
-    fn execute(&self, state: &mut State) {
-        println!("srai {:?}, {}", self.rd, self.shamt);
-        // TODO: Arithmetic ?
-        let rd = state.get_reg(self.rd);
-        state.set_reg(self.rd, rd >> self.shamt);
+impl Instruction for C_SRLI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "srli {:?}, {:?}, {}", self.rd, self.rd, self.shamt);
+        let rd = state.get_reg_u(self.rd);
+        state.set_reg(self.rd, (rd >> self.shamt) as i64);
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_SRAI64 {
+pub struct C_SRAI {
     rd: XprName,
-    shamt: i64,
+    shamt: u32,
 }
-impl C_SRAI64 {
+impl C_SRAI {
     pub fn new(inst: u16) -> Self {
         let rd = rvc_cb_type(inst);
-        C_SRAI64 { rd, shamt: 64 }
+        let shamt = rvc_shamt(inst);
+        C_SRAI { rd, shamt }
     }
 }
-impl Instruction for C_SRAI64 {
-    fn execute(&self, state: &mut State) {
-        println!("srai64 {:?}, {}", self.rd, self.shamt);
+impl Instruction for C_SRAI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "srai {:?}, {:?}, {}", self.rd, self.rd, self.shamt);
         let rd = state.get_reg(self.rd);
         state.set_reg(self.rd, rd >> self.shamt);
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -393,16 +516,17 @@ pub struct C_ANDI {
 impl C_ANDI {
     pub fn new(inst: u16) -> Self {
         let rd = rvc_cb_type(inst);
-        let imm = x(inst, 2, 5) + (x(inst, 12, 1) << 5);
+        let (_, imm) = rvc_ci_type(inst);
         C_ANDI { rd, imm }
     }
 }
 impl Instruction for C_ANDI {
-    fn execute(&self, state: &mut State) {
-        println!("andi {:?}, {}", self.rd, self.imm);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "andi {:?}, {:?}, {}", self.rd, self.rd, self.imm);
         let rd = state.get_reg(self.rd);
         state.set_reg(self.rd, rd & self.imm);
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -417,10 +541,12 @@ impl C_SUB {
     }
 }
 impl Instruction for C_SUB {
-    fn execute(&self, state: &mut State) {
-        println!("sub {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
-        state.set_reg(self.rd, state.get_reg(self.rd) - state.get_reg(self.rs2));
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sub {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
+        let rd = state.get_reg(self.rd);
+        state.set_reg(self.rd, rd.wrapping_sub(state.get_reg(self.rs2)));
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -435,10 +561,11 @@ impl C_XOR {
     }
 }
 impl Instruction for C_XOR {
-    fn execute(&self, state: &mut State) {
-        println!("xor {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "xor {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
         state.set_reg(self.rd, state.get_reg(self.rd) ^ state.get_reg(self.rs2));
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -453,10 +580,11 @@ impl C_OR {
     }
 }
 impl Instruction for C_OR {
-    fn execute(&self, state: &mut State) {
-        println!("or {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "or {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
         state.set_reg(self.rd, state.get_reg(self.rd) | state.get_reg(self.rs2));
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -471,10 +599,11 @@ impl C_AND {
     }
 }
 impl Instruction for C_AND {
-    fn execute(&self, state: &mut State) {
-        println!("and {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "and {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
         state.set_reg(self.rd, state.get_reg(self.rd) & state.get_reg(self.rs2));
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -489,10 +618,13 @@ impl C_SUBW {
     }
 }
 impl Instruction for C_SUBW {
-    fn execute(&self, state: &mut State) {
-        println!("subw {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
-        state.set_reg(self.rd, state.get_reg(self.rd) - state.get_reg(self.rs2));
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "subw {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
+        let rd = state.get_reg(self.rd) as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.set_reg(self.rd, rd.wrapping_sub(rs2) as i64);
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -507,10 +639,13 @@ impl C_ADDW {
     }
 }
 impl Instruction for C_ADDW {
-    fn execute(&self, state: &mut State) {
-        println!("addw {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
-        state.set_reg(self.rd, state.get_reg(self.rd) + state.get_reg(self.rs2));
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "addw {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
+        let rd = state.get_reg(self.rd) as i32;
+        let rs2 = state.get_reg(self.rs2) as i32;
+        state.set_reg(self.rd, rd.wrapping_add(rs2) as i64);
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -524,9 +659,9 @@ impl C_J {
     }
 }
 impl Instruction for C_J {
-    fn execute(&self, state: &mut State) {
-        println!("j {}", self.offset);
-        state.pc += 2;
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "j {}", self.offset);
+        state.jump(state.pc.wrapping_add(self.offset))
     }
 }
 
@@ -537,23 +672,20 @@ pub struct C_BEQZ {
 impl C_BEQZ {
     pub fn new(inst: u16) -> Self {
         let rs1 = rvc_cb_type(inst);
-        let offset = (x(inst, 3, 1) << 5)
-            + (x(inst, 4, 2) << 1)
-            + (x(inst, 6, 2) << 6)
-            + (x(inst, 10, 2) << 3)
-            - (x(inst, 12, 1) << 8);
+        let offset = rvc_cb_offset(inst);
         C_BEQZ { rs1, offset }
     }
 }
 impl Instruction for C_BEQZ {
-    fn execute(&self, state: &mut State) {
-        println!("beqz {:?}, {}", self.rs1, self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "beqz {:?}, {}", self.rs1, self.offset);
         let rs1 = state.get_reg(self.rs1);
         if rs1 == 0 {
-            state.pc += self.offset;
+            state.jump(state.pc.wrapping_add(self.offset))?;
         } else {
             state.pc += 2;
         }
+        Ok(())
     }
 }
 
@@ -564,95 +696,119 @@ pub struct C_BNEZ {
 impl C_BNEZ {
     pub fn new(inst: u16) -> Self {
         let rs1 = rvc_cb_type(inst);
-        let offset = (x(inst, 3, 1) << 5)
-            + (x(inst, 4, 2) << 1)
-            + (x(inst, 6, 2) << 6)
-            + (x(inst, 10, 2) << 3)
-            - (x(inst, 12, 1) << 8);
+        let offset = rvc_cb_offset(inst);
         C_BNEZ { rs1, offset }
     }
 }
 impl Instruction for C_BNEZ {
-    fn execute(&self, state: &mut State) {
-        println!("bnez {:?}, {}", self.rs1, self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "bnez {:?}, {}", self.rs1, self.offset);
         let rs1 = state.get_reg(self.rs1);
         if rs1 != 0 {
-            state.pc += self.offset;
+            state.jump(state.pc.wrapping_add(self.offset))?;
         } else {
             state.pc += 2;
         }
+        Ok(())
     }
 }
 
-pub struct C_SLLI(u16);
+pub struct C_SLLI {
+    rd: XprName,
+    shamt: u32,
+}
 impl C_SLLI {
     pub fn new(inst: u16) -> Self {
-        C_SLLI(inst)
+        let (rd, _) = rvc_ci_type(inst);
+        let shamt = rvc_shamt(inst);
+        C_SLLI { rd, shamt }
     }
 }
 impl Instruction for C_SLLI {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "slli {:?}, {:?}, {}", self.rd, self.rd, self.shamt);
+        state.set_reg(self.rd, state.get_reg(self.rd) << self.shamt);
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_SLLI64(u16);
-impl C_SLLI64 {
-    pub fn new(inst: u16) -> Self {
-        C_SLLI64(inst)
-    }
-}
-impl Instruction for C_SLLI64 {
-    fn execute(&self, state: &mut State) {
-        state.pc += 2;
-    }
+pub struct C_FLDSP {
+    rd: FprName,
+    offset: i64,
 }
-
-pub struct C_FLDSP(u16);
 impl C_FLDSP {
     pub fn new(inst: u16) -> Self {
-        C_FLDSP(inst)
+        let rd = FprName::from_num(x(inst, 7, 5));
+        let offset = (x(inst, 2, 3) << 6) + (x(inst, 5, 2) << 3) + (x(inst, 12, 1) << 5);
+        C_FLDSP { rd, offset }
     }
 }
 impl Instruction for C_FLDSP {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fld {:?}, {}(sp)", self.rd, self.offset);
+        let addr = state.get_reg(sp).wrapping_add(self.offset);
+        let mem = state.access_u64(addr)?;
+        state.set_freg_d(self.rd, mem);
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_LQSP(u16);
+pub struct C_LQSP;
 impl C_LQSP {
-    pub fn new(inst: u16) -> Self {
-        C_LQSP(inst)
+    pub fn new(_inst: u16) -> Self {
+        C_LQSP
     }
 }
 impl Instruction for C_LQSP {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_LWSP(u16);
+pub struct C_LWSP {
+    rd: XprName,
+    offset: i64,
+}
 impl C_LWSP {
     pub fn new(inst: u16) -> Self {
-        C_LWSP(inst)
+        let (rd, _) = rvc_ci_type(inst);
+        let offset = (x(inst, 2, 2) << 6) + (x(inst, 4, 3) << 2) + (x(inst, 12, 1) << 5);
+        C_LWSP { rd, offset }
     }
 }
 impl Instruction for C_LWSP {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lw {:?}, {}(sp)", self.rd, self.offset);
+        let addr = state.get_reg(sp).wrapping_add(self.offset);
+        let mem = state.access_u32(addr)?;
+        state.set_reg(self.rd, mem as i32 as i64);
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_FLWSP(u16);
+pub struct C_FLWSP {
+    rd: FprName,
+    offset: i64,
+}
 impl C_FLWSP {
     pub fn new(inst: u16) -> Self {
-        C_FLWSP(inst)
+        let rd = FprName::from_num(x(inst, 7, 5));
+        let offset = (x(inst, 2, 2) << 6) + (x(inst, 4, 3) << 2) + (x(inst, 12, 1) << 5);
+        C_FLWSP { rd, offset }
     }
 }
 impl Instruction for C_FLWSP {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "flw {:?}, {}(sp)", self.rd, self.offset);
+        let addr = state.get_reg(sp).wrapping_add(self.offset);
+        let mem = state.access_u32(addr)?;
+        state.set_freg_s(self.rd, mem as u64);
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -662,29 +818,35 @@ pub struct C_LDSP {
 }
 impl C_LDSP {
     pub fn new(inst: u16) -> Self {
-        let rd = rvc_ci_type(inst);
+        let (rd, _) = rvc_ci_type(inst);
         let offset = (x(inst, 2, 3) << 6) + (x(inst, 5, 2) << 3) + (x(inst, 12, 1) << 5);
         C_LDSP { rd, offset }
     }
 }
 impl Instruction for C_LDSP {
-    fn execute(&self, state: &mut State) {
-        println!("ld {:?}, {}(sp)", self.rd, self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "ld {:?}, {}(sp)", self.rd, self.offset);
+        let addr = state.get_reg(sp).wrapping_add(self.offset);
+        let mem = state.access_u64(addr)?;
+        state.set_reg(self.rd, mem as i64);
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_JR(u16);
+pub struct C_JR {
+    rs1: XprName,
+}
 impl C_JR {
     pub fn new(inst: u16) -> Self {
-        C_JR(inst)
+        let (rs1, _) = rvc_cr_type(inst);
+        C_JR { rs1 }
     }
 }
 impl Instruction for C_JR {
-    fn execute(&self, state: &mut State) {
-        println!("ret");
-        state.get_reg(XprName::ra);
-        state.pc += 2;
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "jr {:?}", self.rs1);
+        state.jump(state.get_reg(self.rs1) & !1)
     }
 }
 
@@ -699,34 +861,44 @@ impl C_MV {
     }
 }
 impl Instruction for C_MV {
-    fn execute(&self, state: &mut State) {
-        println!("mv {:?}, {:?}", self.rd, self.rs2);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "mv {:?}, {:?}", self.rd, self.rs2);
         state.set_reg(self.rd, state.get_reg(self.rs2));
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_EBREAK(u16);
+pub struct C_EBREAK;
 impl C_EBREAK {
-    pub fn new(inst: u16) -> Self {
-        C_EBREAK(inst)
+    pub fn new(_inst: u16) -> Self {
+        C_EBREAK
     }
 }
 impl Instruction for C_EBREAK {
-    fn execute(&self, state: &mut State) {
-        state.pc += 2;
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "c.ebreak");
+        Err(Exception::Breakpoint(state.zext_xlen(state.pc)))
     }
 }
 
-pub struct C_JALR(u16);
+pub struct C_JALR {
+    rs1: XprName,
+}
 impl C_JALR {
     pub fn new(inst: u16) -> Self {
-        C_JALR(inst)
+        let (rs1, _) = rvc_cr_type(inst);
+        C_JALR { rs1 }
     }
 }
 impl Instruction for C_JALR {
-    fn execute(&self, state: &mut State) {
-        state.pc += 2;
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "jalr {:?}", self.rs1);
+        let target = state.get_reg(self.rs1) & !1;
+        let link = state.pc + 2;
+        state.jump(target)?;
+        state.set_reg(ra, link);
+        Ok(())
     }
 }
 
@@ -741,60 +913,90 @@ impl C_ADD {
     }
 }
 impl Instruction for C_ADD {
-    fn execute(&self, state: &mut State) {
-        println!("add {:?}, {:?}, {:?}", self.rs1, self.rs1, self.rs2);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "add {:?}, {:?}, {:?}", self.rs1, self.rs1, self.rs2);
         let rs1 = state.get_reg(self.rs1);
         let rs2 = state.get_reg(self.rs2);
-        state.set_reg(self.rs1, rs1 + rs2);
+        state.set_reg(self.rs1, rs1.wrapping_add(rs2));
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_FSDSP(u16);
+pub struct C_FSDSP {
+    rs2: FprName,
+    offset: i64,
+}
 impl C_FSDSP {
     pub fn new(inst: u16) -> Self {
-        C_FSDSP(inst)
+        let rs2 = FprName::from_num(x(inst, 2, 5));
+        let offset = (x(inst, 7, 3) << 6) + (x(inst, 10, 3) << 3);
+        C_FSDSP { rs2, offset }
     }
 }
 impl Instruction for C_FSDSP {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fsd {:?}, {}(sp)", self.rs2, self.offset);
+        let addr = state.get_reg(sp).wrapping_add(self.offset);
+        state.store_u64(addr, state.fregs.get(self.rs2))?;
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_SQSP(u16);
+pub struct C_SQSP;
 impl C_SQSP {
-    pub fn new(inst: u16) -> Self {
-        C_SQSP(inst)
+    pub fn new(_inst: u16) -> Self {
+        C_SQSP
     }
 }
 impl Instruction for C_SQSP {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_SWSP(u16);
+pub struct C_SWSP {
+    rs2: XprName,
+    offset: i64,
+}
 impl C_SWSP {
     pub fn new(inst: u16) -> Self {
-        C_SWSP(inst)
+        let (rs2, imm) = rvc_css_type(inst);
+        let offset = (x(imm as u64, 0, 2) << 6) + (x(imm as u64, 2, 4) << 2);
+        C_SWSP { rs2, offset }
     }
 }
 impl Instruction for C_SWSP {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sw {:?}, {}(sp)", self.rs2, self.offset);
+        let addr = state.get_reg(sp).wrapping_add(self.offset);
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u32(addr, rs2 as u32)?;
         state.pc += 2;
+        Ok(())
     }
 }
 
-pub struct C_FSWSP(u16);
+pub struct C_FSWSP {
+    rs2: FprName,
+    offset: i64,
+}
 impl C_FSWSP {
     pub fn new(inst: u16) -> Self {
-        C_FSWSP(inst)
+        let rs2 = FprName::from_num(x(inst, 2, 5));
+        let offset = (x(inst, 7, 2) << 6) + (x(inst, 9, 4) << 2);
+        C_FSWSP { rs2, offset }
     }
 }
 impl Instruction for C_FSWSP {
-    fn execute(&self, state: &mut State) {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "fsw {:?}, {}(sp)", self.rs2, self.offset);
+        let addr = state.get_reg(sp).wrapping_add(self.offset);
+        state.store_u32(addr, state.fregs.get(self.rs2) as u32)?;
         state.pc += 2;
+        Ok(())
     }
 }
 
@@ -810,10 +1012,12 @@ impl C_SDSP {
     }
 }
 impl Instruction for C_SDSP {
-    fn execute(&self, state: &mut State) {
-        println!("sd {:?}, {}(sp)", self.rs2, self.offset);
-        let sp_val = state.regs.get(sp);
-        state.regs.set(self.rs2, sp_val + self.offset);
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sd {:?}, {}(sp)", self.rs2, self.offset);
+        let addr = state.get_reg(sp).wrapping_add(self.offset);
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u64(addr, rs2 as u64)?;
         state.pc += 2;
+        Ok(())
     }
 }
@@ -0,0 +1,1489 @@
+use super::{rv32i::rv32i_r_type, Instruction};
+use crate::{
+    processor::{State, XprName},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+#[rustfmt::skip]
+const AES_SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+const AES_INV_SBOX: [u8; 256] = invert(&AES_SBOX);
+
+#[rustfmt::skip]
+const SM4_SBOX: [u8; 256] = [
+    0xd6, 0x90, 0xe9, 0xfe, 0xcc, 0xe1, 0x3d, 0xb7, 0x16, 0xb6, 0x14, 0xc2, 0x28, 0xfb, 0x2c, 0x05,
+    0x2b, 0x67, 0x9a, 0x76, 0x2a, 0xbe, 0x04, 0xc3, 0xaa, 0x44, 0x13, 0x26, 0x49, 0x86, 0x06, 0x99,
+    0x9c, 0x42, 0x50, 0xf4, 0x91, 0xef, 0x98, 0x7a, 0x33, 0x54, 0x0b, 0x43, 0xed, 0xcf, 0xac, 0x62,
+    0xe4, 0xb3, 0x1c, 0xa9, 0xc9, 0x08, 0xe8, 0x95, 0x80, 0xdf, 0x94, 0xfa, 0x75, 0x8f, 0x3f, 0xa6,
+    0x47, 0x07, 0xa7, 0xfc, 0xf3, 0x73, 0x17, 0xba, 0x83, 0x59, 0x3c, 0x19, 0xe6, 0x85, 0x4f, 0xa8,
+    0x68, 0x6b, 0x81, 0xb2, 0x71, 0x64, 0xda, 0x8b, 0xf8, 0xeb, 0x0f, 0x4b, 0x70, 0x56, 0x9d, 0x35,
+    0x1e, 0x24, 0x0e, 0x5e, 0x63, 0x58, 0xd1, 0xa2, 0x25, 0x22, 0x7c, 0x3b, 0x01, 0x21, 0x78, 0x87,
+    0xd4, 0x00, 0x46, 0x57, 0x9f, 0xd3, 0x27, 0x52, 0x4c, 0x36, 0x02, 0xe7, 0xa0, 0xc4, 0xc8, 0x9e,
+    0xea, 0xbf, 0x8a, 0xd2, 0x40, 0xc7, 0x38, 0xb5, 0xa3, 0xf7, 0xf2, 0xce, 0xf9, 0x61, 0x15, 0xa1,
+    0xe0, 0xae, 0x5d, 0xa4, 0x9b, 0x34, 0x1a, 0x55, 0xad, 0x93, 0x32, 0x30, 0xf5, 0x8c, 0xb1, 0xe3,
+    0x1d, 0xf6, 0xe2, 0x2e, 0x82, 0x66, 0xca, 0x60, 0xc0, 0x29, 0x23, 0xab, 0x0d, 0x53, 0x4e, 0x6f,
+    0xd5, 0xdb, 0x37, 0x45, 0xde, 0xfd, 0x8e, 0x2f, 0x03, 0xff, 0x6a, 0x72, 0x6d, 0x6c, 0x5b, 0x51,
+    0x8d, 0x1b, 0xaf, 0x92, 0xbb, 0xdd, 0xbc, 0x7f, 0x11, 0xd9, 0x5c, 0x41, 0x1f, 0x10, 0x5a, 0xd8,
+    0x0a, 0xc1, 0x31, 0x88, 0xa5, 0xcd, 0x7b, 0xbd, 0x2d, 0x74, 0xd0, 0x12, 0xb8, 0xe5, 0xb4, 0xb0,
+    0x89, 0x69, 0x97, 0x4a, 0x0c, 0x96, 0x77, 0x7e, 0x65, 0xb9, 0xf1, 0x09, 0xc5, 0x6e, 0xc6, 0x84,
+    0x18, 0xf0, 0x7d, 0xec, 0x3a, 0xdc, 0x4d, 0x20, 0x79, 0xee, 0x5f, 0x3e, 0xd7, 0xcb, 0x39, 0x48,
+];
+
+const fn invert(sbox: &[u8; 256]) -> [u8; 256] {
+    let mut inverse = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        inverse[sbox[i] as usize] = i as u8;
+        i += 1;
+    }
+    inverse
+}
+
+/// Multiplication in GF(2^8) modulo the AES polynomial x^8 + x^4 + x^3 + x + 1.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 };
+        b >>= 1;
+    }
+    product
+}
+
+/// MixColumns coefficients for encryption and decryption; each output byte takes the row
+/// rotated right by its index.
+const MIX_FORWARD: [u8; 4] = [0x02, 0x03, 0x01, 0x01];
+const MIX_INVERSE: [u8; 4] = [0x0e, 0x0b, 0x0d, 0x09];
+
+/// Multiplies a column, one byte per row with row 0 lowest, by the MixColumns matrix.
+fn mix_column(column: u32, coefficients: [u8; 4]) -> u32 {
+    let bytes = column.to_le_bytes();
+    let mixed: [u8; 4] = std::array::from_fn(|row| {
+        (0..4).fold(0, |acc, i| {
+            acc ^ gf_mul(coefficients[(i + 4 - row) % 4], bytes[i])
+        })
+    });
+    u32::from_le_bytes(mixed)
+}
+
+fn sub_bytes(value: u64, sbox: &[u8; 256]) -> u64 {
+    u64::from_le_bytes(value.to_le_bytes().map(|byte| sbox[byte as usize]))
+}
+
+/// The low half of ShiftRows (or its inverse) on the state held as rs2:rs1, with the
+/// bytes in column-major order.
+fn shift_rows(rs1: u64, rs2: u64, inverse: bool) -> u64 {
+    let state = ((rs2 as u128) << 64 | rs1 as u128).to_le_bytes();
+    let bytes: [u8; 8] = std::array::from_fn(|i| {
+        let (column, row) = (i / 4, i % 4);
+        let from = if inverse {
+            (column + 4 - row) % 4
+        } else {
+            (column + row) % 4
+        };
+        state[from * 4 + row]
+    });
+    u64::from_le_bytes(bytes)
+}
+
+/// One round of AES on the 64-bit half of the state that RV64 works on.
+fn aes64_round(rs1: u64, rs2: u64, inverse: bool, mix: bool) -> u64 {
+    let (sbox, coefficients) = if inverse {
+        (&AES_INV_SBOX, MIX_INVERSE)
+    } else {
+        (&AES_SBOX, MIX_FORWARD)
+    };
+    let value = sub_bytes(shift_rows(rs1, rs2, inverse), sbox);
+    if mix {
+        mix_columns(value, coefficients)
+    } else {
+        value
+    }
+}
+
+fn mix_columns(value: u64, coefficients: [u8; 4]) -> u64 {
+    let low = mix_column(value as u32, coefficients) as u64;
+    let high = mix_column((value >> 32) as u32, coefficients) as u64;
+    high << 32 | low
+}
+
+/// The RV32 AES step: one byte of rs2 through the S-box, optionally mixed as a column,
+/// rotated back into place and xored into rs1.
+fn aes32(rs1: i64, rs2: i64, bs: u32, sbox: &[u8; 256], mix: Option<[u8; 4]>) -> i64 {
+    let byte = sbox[(rs2 as u32 >> (8 * bs)) as u8 as usize];
+    let column = match mix {
+        Some(coefficients) => mix_column(byte as u32, coefficients),
+        None => byte as u32,
+    };
+    (rs1 as u32 ^ column.rotate_left(8 * bs)) as i32 as i64
+}
+
+/// The SM4 S-box applied to byte `bs` of rs2.
+fn sm4_sbox(rs2: i64, bs: u32) -> u32 {
+    SM4_SBOX[(rs2 as u32 >> (8 * bs)) as u8 as usize] as u32
+}
+
+/// Permutes the `bits`-wide elements of rs1 by the indices in rs2; out-of-range indices
+/// select zero.
+fn xperm(state: &State, rs1: u64, rs2: u64, bits: u32) -> i64 {
+    let mask = (1 << bits) - 1;
+    let count = state.xlen_bits() / bits;
+    (0..count).fold(0, |result, i| {
+        let index = (rs2 >> (i * bits)) & mask;
+        let element = if index < count as u64 {
+            (rs1 >> (index as u32 * bits)) & mask
+        } else {
+            0
+        };
+        result | (element << (i * bits)) as i64
+    })
+}
+
+pub struct PACK {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl PACK {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        PACK { rd, rs1, rs2 }
+    }
+}
+impl Instruction for PACK {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "pack {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let half = state.xlen_bits() / 2;
+        let low = rs1 as u64 & ((1 << half) - 1);
+        state.set_reg(self.rd, rs2 << half | low as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct PACKH {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl PACKH {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        PACKH { rd, rs1, rs2 }
+    }
+}
+impl Instruction for PACKH {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "packh {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, (rs2 & 0xff) << 8 | rs1 & 0xff);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct PACKW {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl PACKW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        PACKW { rd, rs1, rs2 }
+    }
+}
+impl Instruction for PACKW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "packw {:?}, {:?}, {:?}", self.rd, self.rs1, self.rs2);
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, ((rs2 << 16) as i32 as i64) | rs1 & 0xffff);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct BREV8 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl BREV8 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        BREV8 { rd, rs1 }
+    }
+}
+impl Instruction for BREV8 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "brev8 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let value = u64::from_le_bytes((rs1 as u64).to_le_bytes().map(u8::reverse_bits));
+        state.set_reg(self.rd, value as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct ZIP {
+    rd: XprName,
+    rs1: XprName,
+}
+impl ZIP {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        ZIP { rd, rs1 }
+    }
+}
+impl Instruction for ZIP {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "zip {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        // Interleaves the low and high halves: bit i goes to 2i and bit 16 + i to 2i + 1.
+        let value = (0..16).fold(0, |value, i| {
+            value | (rs1 >> i & 1) << (2 * i) | (rs1 >> (16 + i) & 1) << (2 * i + 1)
+        });
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct UNZIP {
+    rd: XprName,
+    rs1: XprName,
+}
+impl UNZIP {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        UNZIP { rd, rs1 }
+    }
+}
+impl Instruction for UNZIP {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "unzip {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let value = (0..16).fold(0, |value, i| {
+            value | (rs1 >> (2 * i) & 1) << i | (rs1 >> (2 * i + 1) & 1) << (16 + i)
+        });
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct XPERM4 {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl XPERM4 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        XPERM4 { rd, rs1, rs2 }
+    }
+}
+impl Instruction for XPERM4 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "xperm4 {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value = xperm(state, rs1 as u64, rs2 as u64, 4);
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct XPERM8 {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl XPERM8 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        XPERM8 { rd, rs1, rs2 }
+    }
+}
+impl Instruction for XPERM8 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "xperm8 {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value = xperm(state, rs1 as u64, rs2 as u64, 8);
+        state.set_reg(self.rd, value);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES32ESI {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+    bs: u32,
+}
+impl AES32ESI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        let bs = x(inst, 30, 2) as u32;
+        AES32ESI { rd, rs1, rs2, bs }
+    }
+}
+impl Instruction for AES32ESI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "aes32esi {:?}, {:?}, {:?}, {}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.bs
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, aes32(rs1, rs2, self.bs, &AES_SBOX, None));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES32ESMI {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+    bs: u32,
+}
+impl AES32ESMI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        let bs = x(inst, 30, 2) as u32;
+        AES32ESMI { rd, rs1, rs2, bs }
+    }
+}
+impl Instruction for AES32ESMI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "aes32esmi {:?}, {:?}, {:?}, {}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.bs
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(
+            self.rd,
+            aes32(rs1, rs2, self.bs, &AES_SBOX, Some(MIX_FORWARD)),
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES32DSI {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+    bs: u32,
+}
+impl AES32DSI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        let bs = x(inst, 30, 2) as u32;
+        AES32DSI { rd, rs1, rs2, bs }
+    }
+}
+impl Instruction for AES32DSI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "aes32dsi {:?}, {:?}, {:?}, {}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.bs
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, aes32(rs1, rs2, self.bs, &AES_INV_SBOX, None));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES32DSMI {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+    bs: u32,
+}
+impl AES32DSMI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        let bs = x(inst, 30, 2) as u32;
+        AES32DSMI { rd, rs1, rs2, bs }
+    }
+}
+impl Instruction for AES32DSMI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "aes32dsmi {:?}, {:?}, {:?}, {}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.bs
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(
+            self.rd,
+            aes32(rs1, rs2, self.bs, &AES_INV_SBOX, Some(MIX_INVERSE)),
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES64ES {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AES64ES {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AES64ES { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AES64ES {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "aes64es {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value = aes64_round(rs1 as u64, rs2 as u64, false, false);
+        state.set_reg(self.rd, value as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES64ESM {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AES64ESM {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AES64ESM { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AES64ESM {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "aes64esm {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value = aes64_round(rs1 as u64, rs2 as u64, false, true);
+        state.set_reg(self.rd, value as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES64DS {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AES64DS {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AES64DS { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AES64DS {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "aes64ds {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value = aes64_round(rs1 as u64, rs2 as u64, true, false);
+        state.set_reg(self.rd, value as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES64DSM {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AES64DSM {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AES64DSM { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AES64DSM {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "aes64dsm {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let value = aes64_round(rs1 as u64, rs2 as u64, true, true);
+        state.set_reg(self.rd, value as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES64IM {
+    rd: XprName,
+    rs1: XprName,
+}
+impl AES64IM {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        AES64IM { rd, rs1 }
+    }
+}
+impl Instruction for AES64IM {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "aes64im {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        state.set_reg(self.rd, mix_columns(rs1 as u64, MIX_INVERSE) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES64KS1I {
+    rd: XprName,
+    rs1: XprName,
+    rnum: u32,
+}
+impl AES64KS1I {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        let rnum = x(inst, 20, 4) as u32;
+        AES64KS1I { rd, rs1, rnum }
+    }
+}
+impl Instruction for AES64KS1I {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "aes64ks1i {:?}, {:?}, {}",
+            self.rd,
+            self.rs1,
+            self.rnum
+        );
+        const RCON: [u32; 11] = [
+            0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36, 0x00,
+        ];
+        let word = (state.get_reg(self.rs1) as u64 >> 32) as u32;
+        // Round 10 is the AES-256 step that substitutes without rotating.
+        let word = if self.rnum == 0xa {
+            word
+        } else {
+            word.rotate_right(8)
+        };
+        let word = sub_bytes(word as u64, &AES_SBOX) as u32 ^ RCON[self.rnum as usize];
+        state.set_reg(self.rd, ((word as u64) << 32 | word as u64) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct AES64KS2 {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl AES64KS2 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        AES64KS2 { rd, rs1, rs2 }
+    }
+}
+impl Instruction for AES64KS2 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "aes64ks2 {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let low = (rs1 as u64 >> 32) as u32 ^ rs2 as u32;
+        let high = low ^ (rs2 as u64 >> 32) as u32;
+        state.set_reg(self.rd, ((high as u64) << 32 | low as u64) as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA256SIG0 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SHA256SIG0 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SHA256SIG0 { rd, rs1 }
+    }
+}
+impl Instruction for SHA256SIG0 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sha256sig0 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let x = rs1 as u32;
+        state.set_reg(
+            self.rd,
+            (x.rotate_right(7) ^ x.rotate_right(18) ^ x >> 3) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA256SIG1 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SHA256SIG1 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SHA256SIG1 { rd, rs1 }
+    }
+}
+impl Instruction for SHA256SIG1 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sha256sig1 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let x = rs1 as u32;
+        state.set_reg(
+            self.rd,
+            (x.rotate_right(17) ^ x.rotate_right(19) ^ x >> 10) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA256SUM0 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SHA256SUM0 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SHA256SUM0 { rd, rs1 }
+    }
+}
+impl Instruction for SHA256SUM0 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sha256sum0 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let x = rs1 as u32;
+        state.set_reg(
+            self.rd,
+            (x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA256SUM1 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SHA256SUM1 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SHA256SUM1 { rd, rs1 }
+    }
+}
+impl Instruction for SHA256SUM1 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sha256sum1 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let x = rs1 as u32;
+        state.set_reg(
+            self.rd,
+            (x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA512SIG0 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SHA512SIG0 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SHA512SIG0 { rd, rs1 }
+    }
+}
+impl Instruction for SHA512SIG0 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sha512sig0 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let x = rs1 as u64;
+        state.set_reg(
+            self.rd,
+            (x.rotate_right(1) ^ x.rotate_right(8) ^ x >> 7) as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA512SIG1 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SHA512SIG1 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SHA512SIG1 { rd, rs1 }
+    }
+}
+impl Instruction for SHA512SIG1 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sha512sig1 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let x = rs1 as u64;
+        state.set_reg(
+            self.rd,
+            (x.rotate_right(19) ^ x.rotate_right(61) ^ x >> 6) as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA512SUM0 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SHA512SUM0 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SHA512SUM0 { rd, rs1 }
+    }
+}
+impl Instruction for SHA512SUM0 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sha512sum0 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let x = rs1 as u64;
+        state.set_reg(
+            self.rd,
+            (x.rotate_right(28) ^ x.rotate_right(34) ^ x.rotate_right(39)) as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA512SUM1 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SHA512SUM1 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SHA512SUM1 { rd, rs1 }
+    }
+}
+impl Instruction for SHA512SUM1 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sha512sum1 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let x = rs1 as u64;
+        state.set_reg(
+            self.rd,
+            (x.rotate_right(14) ^ x.rotate_right(18) ^ x.rotate_right(41)) as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA512SIG0H {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SHA512SIG0H {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SHA512SIG0H { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SHA512SIG0H {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sha512sig0h {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let (a, b) = (rs1 as u32, rs2 as u32);
+        state.set_reg(
+            self.rd,
+            (a >> 1 ^ a >> 7 ^ a >> 8 ^ b << 31 ^ b << 24) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA512SIG0L {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SHA512SIG0L {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SHA512SIG0L { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SHA512SIG0L {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sha512sig0l {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let (a, b) = (rs1 as u32, rs2 as u32);
+        state.set_reg(
+            self.rd,
+            (a >> 1 ^ a >> 7 ^ a >> 8 ^ b << 31 ^ b << 25 ^ b << 24) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA512SIG1H {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SHA512SIG1H {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SHA512SIG1H { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SHA512SIG1H {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sha512sig1h {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let (a, b) = (rs1 as u32, rs2 as u32);
+        state.set_reg(
+            self.rd,
+            (a << 3 ^ a >> 6 ^ a >> 19 ^ b >> 29 ^ b << 13) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA512SIG1L {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SHA512SIG1L {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SHA512SIG1L { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SHA512SIG1L {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sha512sig1l {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let (a, b) = (rs1 as u32, rs2 as u32);
+        state.set_reg(
+            self.rd,
+            (a << 3 ^ a >> 6 ^ a >> 19 ^ b >> 29 ^ b << 26 ^ b << 13) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA512SUM0R {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SHA512SUM0R {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SHA512SUM0R { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SHA512SUM0R {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sha512sum0r {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let (a, b) = (rs1 as u32, rs2 as u32);
+        state.set_reg(
+            self.rd,
+            (a << 25 ^ a << 30 ^ a >> 28 ^ b >> 7 ^ b >> 2 ^ b << 4) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SHA512SUM1R {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl SHA512SUM1R {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        SHA512SUM1R { rd, rs1, rs2 }
+    }
+}
+impl Instruction for SHA512SUM1R {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sha512sum1r {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let (a, b) = (rs1 as u32, rs2 as u32);
+        state.set_reg(
+            self.rd,
+            (a << 23 ^ a >> 14 ^ a >> 18 ^ b >> 9 ^ b << 18 ^ b << 14) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SM4ED {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+    bs: u32,
+}
+impl SM4ED {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        let bs = x(inst, 30, 2) as u32;
+        SM4ED { rd, rs1, rs2, bs }
+    }
+}
+impl Instruction for SM4ED {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sm4ed {:?}, {:?}, {:?}, {}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.bs
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let x = sm4_sbox(rs2, self.bs);
+        // SM4's linear transform L on a lone byte, so none of the rotations wrap yet.
+        let y = x ^ x << 2 ^ x << 10 ^ x << 18 ^ x << 24;
+        state.set_reg(
+            self.rd,
+            (y.rotate_left(8 * self.bs) ^ rs1 as u32) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SM4KS {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+    bs: u32,
+}
+impl SM4KS {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, rs2) = rv32i_r_type(inst);
+        let bs = x(inst, 30, 2) as u32;
+        SM4KS { rd, rs1, rs2, bs }
+    }
+}
+impl Instruction for SM4KS {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "sm4ks {:?}, {:?}, {:?}, {}",
+            self.rd,
+            self.rs1,
+            self.rs2,
+            self.bs
+        );
+        let rs1 = state.get_reg(self.rs1);
+        let rs2 = state.get_reg(self.rs2);
+        let x = sm4_sbox(rs2, self.bs);
+        // The key schedule's L', likewise on a lone byte.
+        let y = x ^ x << 13 ^ x << 23;
+        state.set_reg(
+            self.rd,
+            (y.rotate_left(8 * self.bs) ^ rs1 as u32) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SM3P0 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SM3P0 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SM3P0 { rd, rs1 }
+    }
+}
+impl Instruction for SM3P0 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sm3p0 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let x = rs1 as u32;
+        state.set_reg(
+            self.rd,
+            (x ^ x.rotate_left(9) ^ x.rotate_left(17)) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct SM3P1 {
+    rd: XprName,
+    rs1: XprName,
+}
+impl SM3P1 {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, _) = rv32i_r_type(inst);
+        SM3P1 { rd, rs1 }
+    }
+}
+impl Instruction for SM3P1 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sm3p1 {:?}, {:?}", self.rd, self.rs1);
+        let rs1 = state.get_reg(self.rs1);
+        let x = rs1 as u32;
+        state.set_reg(
+            self.rd,
+            (x ^ x.rotate_left(15) ^ x.rotate_left(23)) as i32 as i64,
+        );
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        machine::{
+            tests::{machine_with, BASE},
+            Machine,
+        },
+        processor::XprName::*,
+    };
+
+    const RV32: &str = "rv32imac_zbkb_zbkc_zbkx_zkne_zknd_zknh_zksed_zksh";
+    const RV64: &str = "rv64imac_zbkb_zbkc_zbkx_zkne_zknd_zknh_zksed_zksh";
+
+    // All with rd = a2, rs1 = a0, rs2 = a1; the AES32 and SM4 ones with bs = 0.
+    const AES32ESI: u32 = 0x22b5_0633;
+    const AES32ESMI: u32 = 0x26b5_0633;
+    const AES32DSI: u32 = 0x2ab5_0633;
+    const AES32DSMI: u32 = 0x2eb5_0633;
+    const AES64ES: u32 = 0x32b5_0633;
+    const AES64ESM: u32 = 0x36b5_0633;
+    const AES64DS: u32 = 0x3ab5_0633;
+    const AES64DSM: u32 = 0x3eb5_0633;
+    const AES64IM: u32 = 0x3005_1613;
+    const AES64KS1I: u32 = 0x3105_1613;
+    const AES64KS2: u32 = 0x7eb5_0633;
+    const SM4ED: u32 = 0x30b5_0633;
+    const SM4KS: u32 = 0x34b5_0633;
+
+    /// Runs single instructions on `a0` and `a1`, reading the result back from `a2`.
+    struct Alu(Machine);
+    impl Alu {
+        fn new(isa: &str) -> Self {
+            Alu(machine_with(isa, &[]))
+        }
+
+        fn exec(&mut self, inst: u32, rs1: u64, rs2: u64) -> u64 {
+            let machine = &mut self.0;
+            machine.load(BASE, &inst.to_le_bytes()).unwrap();
+            machine.set_pc(BASE);
+            machine.set_reg(a0, rs1 as i64);
+            machine.set_reg(a1, rs2 as i64);
+            machine.step();
+            assert_eq!(machine.state.mcause, 0, "{:#010x} trapped", inst);
+            machine.state.get_reg_u(a2)
+        }
+
+        /// Runs an AES32 or SM4 instruction over all four byte selects of `rs2`.
+        fn exec_bytes(&mut self, inst: u32, acc: u32, rs2: u32) -> u32 {
+            (0..4).fold(acc, |acc, bs| {
+                self.exec(inst | bs << 30, acc as u64, rs2 as u64) as u32
+            })
+        }
+    }
+
+    /// Splits a block, written as in FIPS-197, into the little-endian doublewords the AES64
+    /// instructions hold it in.
+    fn doublewords(block: u128) -> [u64; 2] {
+        let bytes = block.to_be_bytes();
+        [0, 1].map(|i| u64::from_le_bytes(bytes[8 * i..8 * i + 8].try_into().unwrap()))
+    }
+
+    /// The same for the little-endian columns the AES32 instructions work on.
+    fn columns(block: u128) -> [u32; 4] {
+        let bytes = block.to_be_bytes();
+        [0, 1, 2, 3].map(|i| u32::from_le_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap()))
+    }
+
+    fn aes64_round(alu: &mut Alu, inst: u32, state: [u64; 2], key: [u64; 2]) -> [u64; 2] {
+        [
+            alu.exec(inst, state[0], state[1]) ^ key[0],
+            alu.exec(inst, state[1], state[0]) ^ key[1],
+        ]
+    }
+
+    /// An AES32 round: output row r of column j comes from column j + r, or j - r when
+    /// decrypting.
+    fn aes32_round(alu: &mut Alu, inst: u32, state: [u32; 4], key: [u32; 4]) -> [u32; 4] {
+        let decrypt = inst == AES32DSI || inst == AES32DSMI;
+        let mut next = key;
+        for (j, column) in next.iter_mut().enumerate() {
+            for r in 0..4 {
+                let from = if decrypt { j + 4 - r } else { j + r };
+                *column = alu.exec(
+                    inst | (r as u32) << 30,
+                    *column as u64,
+                    state[from % 4] as u64,
+                ) as u32;
+            }
+        }
+        next
+    }
+
+    /// InvMixColumns of a round key column, as a SubBytes that AES32DSMI's InvSubBytes undoes.
+    fn inv_mix_column(alu: &mut Alu, column: u32) -> u32 {
+        (0..4).fold(0, |acc, bs| {
+            let sub = alu.exec(AES32ESI | bs << 30, 0, column as u64);
+            alu.exec(AES32DSMI | bs << 30, acc as u64, sub) as u32
+        })
+    }
+
+    fn aes64_key_schedule(alu: &mut Alu, key: u128) -> Vec<[u64; 2]> {
+        let mut keys = vec![doublewords(key)];
+        for rnum in 0..10 {
+            let [lo, hi] = keys[rnum as usize];
+            let temp = alu.exec(AES64KS1I | rnum << 20, hi, 0);
+            let lo = alu.exec(AES64KS2, temp, lo);
+            let hi = alu.exec(AES64KS2, lo, hi);
+            keys.push([lo, hi]);
+        }
+        keys
+    }
+
+    fn aes32_key_schedule(alu: &mut Alu, key: u128) -> Vec<[u32; 4]> {
+        let mut keys = vec![columns(key)];
+        let mut rcon = 1u8;
+        for round in 0..10 {
+            let mut next = keys[round];
+            // SubWord(RotWord(w[3])) with the round constant folded into w[0].
+            next[0] = alu.exec_bytes(AES32ESI, next[0] ^ rcon as u32, next[3].rotate_right(8));
+            for i in 1..4 {
+                next[i] ^= next[i - 1];
+            }
+            keys.push(next);
+            rcon = rcon << 1 ^ if rcon & 0x80 != 0 { 0x1b } else { 0 };
+        }
+        keys
+    }
+
+    #[test]
+    fn fips_197_round() {
+        // Appendix B: the state at the start of round 1, after its SubBytes, ShiftRows and
+        // MixColumns, and after its AddRoundKey.
+        let start = 0x193de3be_a0f4e22b_9ac68d2a_e9f84808;
+        let mixed = 0x046681e5_e0cb199a_48f8d37a_2806264c;
+        let key = 0xa0fafe17_88542cb1_23a33939_2a6c7605;
+        let next = 0xa49c7ff2_689f352b_6b5bea43_026a5049;
+
+        let mut alu = Alu::new(RV64);
+        let state = aes64_round(&mut alu, AES64ESM, doublewords(start), [0, 0]);
+        assert_eq!(state, doublewords(mixed));
+        let state = aes64_round(&mut alu, AES64ESM, doublewords(start), doublewords(key));
+        assert_eq!(state, doublewords(next));
+
+        let mut alu = Alu::new(RV32);
+        let state = aes32_round(&mut alu, AES32ESMI, columns(start), columns(key));
+        assert_eq!(state, columns(next));
+    }
+
+    #[test]
+    fn aes128_round_trips_on_rv64() {
+        // Appendix C.1.
+        let key = 0x00010203_04050607_08090a0b_0c0d0e0f;
+        let plaintext = doublewords(0x00112233_44556677_8899aabb_ccddeeff);
+        let ciphertext = doublewords(0x69c4e0d8_6a7b0430_d8cdb780_70b4c55a);
+
+        let mut alu = Alu::new(RV64);
+        let keys = aes64_key_schedule(&mut alu, key);
+        assert_eq!(keys[10], doublewords(0x13111d7f_e3944a17_f307a78b_4d2b30c5));
+
+        let mut state = [plaintext[0] ^ keys[0][0], plaintext[1] ^ keys[0][1]];
+        for (round, key) in keys.iter().enumerate().skip(1) {
+            let inst = if round == 10 { AES64ES } else { AES64ESM };
+            state = aes64_round(&mut alu, inst, state, *key);
+        }
+        assert_eq!(state, ciphertext);
+
+        // The equivalent inverse cipher, with InvMixColumns applied to the middle round keys.
+        let mut state = [ciphertext[0] ^ keys[10][0], ciphertext[1] ^ keys[10][1]];
+        for round in (1..10).rev() {
+            let key = keys[round].map(|half| alu.exec(AES64IM, half, 0));
+            state = aes64_round(&mut alu, AES64DSM, state, key);
+        }
+        state = aes64_round(&mut alu, AES64DS, state, keys[0]);
+        assert_eq!(state, plaintext);
+    }
+
+    #[test]
+    fn aes128_round_trips_on_rv32() {
+        let key = 0x00010203_04050607_08090a0b_0c0d0e0f;
+        let plaintext = columns(0x00112233_44556677_8899aabb_ccddeeff);
+        let ciphertext = columns(0x69c4e0d8_6a7b0430_d8cdb780_70b4c55a);
+
+        let mut alu = Alu::new(RV32);
+        let keys = aes32_key_schedule(&mut alu, key);
+        assert_eq!(keys[10], columns(0x13111d7f_e3944a17_f307a78b_4d2b30c5));
+
+        let mut state = [0, 1, 2, 3].map(|i| plaintext[i] ^ keys[0][i]);
+        for (round, key) in keys.iter().enumerate().skip(1) {
+            let inst = if round == 10 { AES32ESI } else { AES32ESMI };
+            state = aes32_round(&mut alu, inst, state, *key);
+        }
+        assert_eq!(state, ciphertext);
+
+        let mut state = [0, 1, 2, 3].map(|i| ciphertext[i] ^ keys[10][i]);
+        for round in (1..10).rev() {
+            let key = keys[round].map(|column| inv_mix_column(&mut alu, column));
+            state = aes32_round(&mut alu, AES32DSMI, state, key);
+        }
+        state = aes32_round(&mut alu, AES32DSI, state, keys[0]);
+        assert_eq!(state, plaintext);
+    }
+
+    #[test]
+    fn sha2_sigma_functions() {
+        let sha256 = [
+            (0x1025_1613, 0xba0c_f582), // sha256sig0 a2, a0
+            (0x1035_1613, 0xcfe5_da3c), // sha256sig1 a2, a0
+            (0x1005_1613, 0xce20_b47e), // sha256sum0 a2, a0
+            (0x1015_1613, 0x55b6_5510), // sha256sum1 a2, a0
+        ];
+        let mut rv32 = Alu::new(RV32);
+        let mut rv64 = Alu::new(RV64);
+        for (inst, expected) in sha256 {
+            assert_eq!(rv32.exec(inst, 0x6a09_e667, 0), expected);
+            // On RV64 the result is sign-extended, whatever the upper half of rs1 holds.
+            let extended = expected as i32 as u64;
+            assert_eq!(rv64.exec(inst, 0x1234_5678_6a09_e667, 0), extended);
+        }
+
+        // The RV64 instructions, and the RV32 pairs producing each half of the same result:
+        // the low half from (low, high) and the high half from (high, low).
+        let x: u64 = 0x6a09_e667_f3bc_c908;
+        let (lo, hi) = (x & 0xffff_ffff, x >> 32);
+        let sha512 = [
+            (0x1065_1613, 0x54b5_0633, 0x5cb5_0633, 0x3dba_e919_51ca_a1df), // sig0
+            (0x1075_1613, 0x56b5_0633, 0x5eb5_0633, 0xc8c6_19e7_3ee4_4510), // sig1
+            (0x1045_1613, 0x50b5_0633, 0x50b5_0633, 0x08c4_db56_aac8_0c2a), // sum0
+            (0x1055_1613, 0x52b5_0633, 0x52b5_0633, 0x259a_6cc1_6433_36ef), // sum1
+        ];
+        for (inst, low, high, expected) in sha512 {
+            assert_eq!(rv64.exec(inst, x, 0), expected);
+            assert_eq!(rv32.exec(low, lo, hi), expected & 0xffff_ffff);
+            assert_eq!(rv32.exec(high, hi, lo), expected >> 32);
+        }
+    }
+
+    #[test]
+    fn sm3_permutations() {
+        for isa in [RV32, RV64] {
+            let mut alu = Alu::new(isa);
+            // sm3p0 a2, a0; sm3p1 a2, a0
+            assert_eq!(alu.exec(0x1085_1613, 0x6a09_e667, 0) as u32, 0xb50b_fca0);
+            assert_eq!(alu.exec(0x1095_1613, 0x6a09_e667, 0) as u32, 0xaa8f_5790);
+        }
+    }
+
+    #[test]
+    fn sm4_encrypts_the_standard_example() {
+        // GB/T 32907-2016 Appendix A.1: the key and the plaintext are the same block.
+        let block = [0x0123_4567, 0x89ab_cdef, 0xfedc_ba98, 0x7654_3210];
+        let fk = [0xa3b1_bac6, 0x56aa_3350, 0x677d_9197, 0xb270_22dc];
+        for isa in [RV32, RV64] {
+            let mut alu = Alu::new(isa);
+            let mut k: Vec<u32> = (0..4).map(|i| block[i] ^ fk[i]).collect();
+            let mut x = block.to_vec();
+            for i in 0..32 {
+                let ck = u32::from_be_bytes([0, 1, 2, 3].map(|j| ((4 * i + j) * 7) as u8));
+                let input = k[i + 1] ^ k[i + 2] ^ k[i + 3] ^ ck;
+                let rk = alu.exec_bytes(SM4KS, k[i], input);
+                k.push(rk);
+                let input = x[i + 1] ^ x[i + 2] ^ x[i + 3] ^ rk;
+                x.push(alu.exec_bytes(SM4ED, x[i], input));
+            }
+            assert_eq!(
+                [x[35], x[34], x[33], x[32]],
+                [0x681e_df34, 0xd206_965e, 0x86b3_e94f, 0x536e_4246]
+            );
+        }
+    }
+
+    #[test]
+    fn crossbar_permutations() {
+        let mut alu = Alu::new(RV64);
+        // xperm4 a2, a0, a1; xperm8 a2, a0, a1
+        let xperm4 = 0x28b5_2633;
+        let xperm8 = 0x28b5_4633;
+        assert_eq!(
+            alu.exec(xperm4, 0xfedc_ba98_7654_3210, 0x0123_4567_89ab_cdef),
+            0x0123_4567_89ab_cdef
+        );
+        assert_eq!(
+            alu.exec(xperm4, 0x0123_4567_89ab_cdef, 0x1032_5476_98ba_dcfe),
+            0xefcd_ab89_6745_2301
+        );
+        // Indices past the end of rs1 select zero.
+        assert_eq!(
+            alu.exec(xperm8, 0x8877_6655_4433_2211, 0xff08_0701_0000_0302),
+            0x0000_8822_1111_4433
+        );
+
+        let mut alu = Alu::new(RV32);
+        assert_eq!(alu.exec(xperm4, 0x7654_3210, 0x0123_cdef), 0x0123_0000);
+        assert_eq!(alu.exec(xperm8, 0x4433_2211, 0x00ff_0302), 0x1100_4433);
+    }
+}
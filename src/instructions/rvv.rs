@@ -0,0 +1,699 @@
+use std::fmt;
+
+use super::Instruction;
+use crate::{
+    processor::{FprName, Reg, State, XprName, VR},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+/// vtype as an instruction sees it. LMUL is counted in eighths so that the fractional
+/// settings stay integral.
+#[derive(Debug, Clone, Copy)]
+pub struct VType {
+    pub sew: u32,
+    pub lmul8: u32,
+}
+impl VType {
+    /// Decodes a vtype value, or returns `None` for the settings that make it set vill.
+    pub fn decode(state: &State, value: u64) -> Option<VType> {
+        let lmul8 = match value & 0b111 {
+            0b000 => 8,
+            0b001 => 16,
+            0b010 => 32,
+            0b011 => 64,
+            0b101 => 1,
+            0b110 => 2,
+            0b111 => 4,
+            _ => return None,
+        };
+        let sew = match value >> 3 & 0b111 {
+            vsew @ 0..=3 => 8 << vsew,
+            _ => return None,
+        };
+        // Everything above vma is reserved, vill included. A fractional LMUL must still
+        // hold an ELEN-wide element's worth of SEW-wide elements.
+        if value >> 8 != 0 || sew > state.elen || sew * 8 > lmul8 * state.elen {
+            return None;
+        }
+        Some(VType { sew, lmul8 })
+    }
+    pub fn vlmax(&self, state: &State) -> usize {
+        state.vlen() as usize * self.lmul8 as usize / 8 / self.sew as usize
+    }
+}
+
+/// The current vtype, or an illegal-instruction exception while vill is set.
+pub fn vtype(state: &State) -> Result<VType, Exception> {
+    VType::decode(state, state.zext_xlen(state.vtype)).ok_or(Exception::IllegalInstruction(0))
+}
+
+/// Registers in a group whose EMUL is `emul8` eighths; fractional groups take one register.
+pub fn group_size(emul8: u32) -> usize {
+    (emul8 as usize).div_ceil(8)
+}
+
+/// Rejects an EMUL outside 1/8..8 and register groups not aligned to their size.
+pub fn check_group(vr: VR, emul8: u32) -> Result<(), Exception> {
+    if !(1..=64).contains(&emul8) || !(vr as usize).is_multiple_of(group_size(emul8)) {
+        return Err(Exception::IllegalInstruction(0));
+    }
+    Ok(())
+}
+
+/// A masked instruction may not write v0 unless the result is itself a mask.
+pub fn check_mask_overlap(vd: VR, vm: bool) -> Result<(), Exception> {
+    if !vm && vd == VR::v0 {
+        return Err(Exception::IllegalInstruction(0));
+    }
+    Ok(())
+}
+
+/// Whether element `i` is active; an unmasked instruction (vm=1) processes them all.
+pub fn active(state: &State, vm: bool, i: usize) -> bool {
+    vm || state.vregs.mask(VR::v0, i)
+}
+
+/// Runs `body` on each active element from vstart up to `vl`, leaving inactive and tail
+/// elements undisturbed. A fault stops the loop with vstart at the element that raised it.
+pub fn for_each_element(
+    state: &mut State,
+    vm: bool,
+    vl: usize,
+    mut body: impl FnMut(&mut State, usize) -> Result<(), Exception>,
+) -> Result<(), Exception> {
+    for i in state.vstart as usize..vl {
+        if active(state, vm, i) {
+            if let Err(exception) = body(state, i) {
+                state.vstart = i as Reg;
+                state.mark_vs_dirty();
+                return Err(exception);
+            }
+        }
+    }
+    state.vstart = 0;
+    Ok(())
+}
+
+/// Reductions, vcpop.m and friends cannot resume part-way through.
+pub fn check_vstart_zero(state: &State) -> Result<(), Exception> {
+    if state.vstart != 0 {
+        return Err(Exception::IllegalInstruction(0));
+    }
+    Ok(())
+}
+
+pub fn mask_bits(bits: u32) -> u64 {
+    if bits == 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+pub fn sext(value: u64, bits: u32) -> i64 {
+    ((value << (64 - bits)) as i64) >> (64 - bits)
+}
+
+/// Splits an OP-V instruction into vd (or rd), vs2 and vm.
+pub fn rvv_arith_type(inst: u32) -> (VR, VR, bool) {
+    let vd = VR::from_num(x(inst, 7, 5));
+    let vs2 = VR::from_num(x(inst, 20, 5));
+    let vm = x(inst, 25, 1) == 1;
+    (vd, vs2, vm)
+}
+
+/// The second operand of an OP-V instruction, chosen by funct3.
+#[derive(Debug, Clone, Copy)]
+pub enum VSource {
+    Vector(VR),
+    Scalar(XprName),
+    Float(FprName),
+    Imm(i64),
+}
+impl VSource {
+    /// Decodes the vs1/rs1/imm field; immediates are sign-extended.
+    pub fn new(inst: u32) -> Self {
+        let field = x(inst, 15, 5);
+        match x(inst, 12, 3) {
+            0b000..=0b010 => VSource::Vector(VR::from_num(field)),
+            0b011 => VSource::Imm(field << 59 >> 59),
+            0b101 => VSource::Float(FprName::from_num(field)),
+            _ => VSource::Scalar(XprName::from_num(field)),
+        }
+    }
+    /// As `new`, but for the shifts, slides and gathers that take an unsigned immediate.
+    pub fn new_unsigned(inst: u32) -> Self {
+        match VSource::new(inst) {
+            VSource::Imm(_) => VSource::Imm(x(inst, 15, 5)),
+            source => source,
+        }
+    }
+    /// The operand-type suffix of the mnemonic, such as `vx` in `vadd.vx`.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            VSource::Vector(_) => "vv",
+            VSource::Scalar(_) => "vx",
+            VSource::Float(_) => "vf",
+            VSource::Imm(_) => "vi",
+        }
+    }
+    pub fn check_group(&self, emul8: u32) -> Result<(), Exception> {
+        match *self {
+            VSource::Vector(vs1) => check_group(vs1, emul8),
+            _ => Ok(()),
+        }
+    }
+    /// Element `i` of a vector operand, or the scalar operand truncated to `eew` bits.
+    /// Single-precision scalars are unboxed.
+    pub fn element(&self, state: &State, i: usize, eew: u32) -> u64 {
+        match *self {
+            VSource::Vector(vs1) => state.vregs.get(vs1, i, eew),
+            VSource::Scalar(rs1) => state.get_reg(rs1) as u64 & mask_bits(eew),
+            VSource::Float(rs1) if eew == 32 => state.get_freg_s(rs1),
+            VSource::Float(rs1) => state.get_freg_d(rs1),
+            VSource::Imm(imm) => imm as u64 & mask_bits(eew),
+        }
+    }
+    /// The scalar operand as an unsigned XLEN value, for slide offsets and gather indices.
+    pub fn offset(&self, state: &State) -> u64 {
+        match *self {
+            VSource::Scalar(rs1) => state.get_reg_u(rs1),
+            VSource::Imm(imm) => imm as u64,
+            VSource::Vector(_) | VSource::Float(_) => 0,
+        }
+    }
+}
+impl fmt::Display for VSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VSource::Vector(vs1) => write!(f, "{:?}", vs1),
+            VSource::Scalar(rs1) => write!(f, "{:?}", rs1),
+            VSource::Float(rs1) => write!(f, "{:?}", rs1),
+            VSource::Imm(imm) => write!(f, "{}", imm),
+        }
+    }
+}
+
+/// The mnemonic for an operation and its operand types, where a trailing `_w` on the
+/// operation marks a wide first source (`vwadd_w` with `vv` is `vwadd.wv`).
+pub fn mnemonic(op: impl fmt::Debug, suffix: &str) -> String {
+    let op = format!("{:?}", op);
+    match op.strip_suffix("_w") {
+        Some(base) => format!("{}.w{}", base, &suffix[1..]),
+        None => format!("{}.{}", op, suffix),
+    }
+}
+
+/// Applies a new vtype and AVL, setting vill instead when the vtype is unsupported.
+/// `avl` is `None` to request VLMAX.
+fn set_vl(state: &mut State, rd: XprName, avl: Option<u64>, vtype: u64) {
+    match VType::decode(state, vtype) {
+        Some(config) => {
+            let vlmax = config.vlmax(state) as u64;
+            state.vtype = vtype as Reg;
+            state.vl = avl.map_or(vlmax, |avl| avl.min(vlmax)) as Reg;
+        }
+        None => {
+            state.vtype = state.vill();
+            state.vl = 0;
+        }
+    }
+    state.vstart = 0;
+    state.mark_vs_dirty();
+    state.set_reg(rd, state.vl);
+}
+
+/// The AVL of vsetvli and vsetvl: x0 as rs1 asks for VLMAX, or keeps vl when rd is x0 too.
+fn register_avl(state: &State, rd: XprName, rs1: XprName) -> Option<u64> {
+    match (rs1, rd) {
+        (XprName::zero, XprName::zero) => Some(state.vl as u64),
+        (XprName::zero, _) => None,
+        _ => Some(state.get_reg_u(rs1)),
+    }
+}
+
+pub struct VSETVLI {
+    rd: XprName,
+    rs1: XprName,
+    vtype: u64,
+}
+impl VSETVLI {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        let vtype = x(inst, 20, 11) as u64;
+        VSETVLI { rd, rs1, vtype }
+    }
+}
+impl Instruction for VSETVLI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "vsetvli {:?}, {:?}, {:#x}",
+            self.rd,
+            self.rs1,
+            self.vtype
+        );
+        let avl = register_avl(state, self.rd, self.rs1);
+        set_vl(state, self.rd, avl, self.vtype);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct VSETIVLI {
+    rd: XprName,
+    avl: u64,
+    vtype: u64,
+}
+impl VSETIVLI {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let avl = x(inst, 15, 5) as u64;
+        let vtype = x(inst, 20, 10) as u64;
+        VSETIVLI { rd, avl, vtype }
+    }
+}
+impl Instruction for VSETIVLI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "vsetivli {:?}, {}, {:#x}",
+            self.rd,
+            self.avl,
+            self.vtype
+        );
+        set_vl(state, self.rd, Some(self.avl), self.vtype);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct VSETVL {
+    rd: XprName,
+    rs1: XprName,
+    rs2: XprName,
+}
+impl VSETVL {
+    pub fn new(inst: u32) -> Self {
+        let rd = XprName::from_num(x(inst, 7, 5));
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        let rs2 = XprName::from_num(x(inst, 20, 5));
+        VSETVL { rd, rs1, rs2 }
+    }
+}
+impl Instruction for VSETVL {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "vsetvl {:?}, {:?}, {:?}",
+            self.rd,
+            self.rs1,
+            self.rs2
+        );
+        let avl = register_avl(state, self.rd, self.rs1);
+        let vtype = state.get_reg_u(self.rs2);
+        set_vl(state, self.rd, avl, vtype);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+/// How a vector load or store walks memory.
+#[derive(Debug, Clone, Copy)]
+pub enum VAddressing {
+    UnitStride,
+    /// A fault past the first element shortens vl instead of trapping.
+    FaultOnlyFirst,
+    /// vlm.v and vsm.v: ceil(vl / 8) bytes, ignoring vtype's SEW.
+    Mask,
+    /// Whole registers, regardless of vtype and vl.
+    WholeRegister,
+    Strided(XprName),
+    Indexed {
+        vs2: VR,
+        ordered: bool,
+    },
+}
+
+/// A vector load or store. Memory is accessed one element at a time, fields of a segment in
+/// order, so a fault leaves the earlier elements done and vstart pointing at the faulting one.
+struct VMemory {
+    vd: VR,
+    rs1: XprName,
+    addressing: VAddressing,
+    eew: u32,
+    nf: usize,
+    vm: bool,
+}
+impl VMemory {
+    fn new(inst: u32, addressing: VAddressing, eew: u32) -> Self {
+        let (vd, _, vm) = rvv_arith_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        let nf = x(inst, 29, 3) as usize + 1;
+        VMemory {
+            vd,
+            rs1,
+            addressing,
+            eew,
+            nf,
+            vm,
+        }
+    }
+
+    fn mnemonic(&self, store: bool) -> String {
+        let op = if store { "vs" } else { "vl" };
+        let seg = if self.nf > 1 {
+            format!("seg{}", self.nf)
+        } else {
+            String::new()
+        };
+        match self.addressing {
+            VAddressing::UnitStride => format!("{}{}e{}.v", op, seg, self.eew),
+            VAddressing::FaultOnlyFirst => format!("{}{}e{}ff.v", op, seg, self.eew),
+            VAddressing::Mask => format!("{}m.v", op),
+            VAddressing::WholeRegister if store => format!("vs{}r.v", self.nf),
+            VAddressing::WholeRegister => format!("vl{}re{}.v", self.nf, self.eew),
+            VAddressing::Strided(_) => format!("{}s{}e{}.v", op, seg, self.eew),
+            VAddressing::Indexed { ordered, .. } => {
+                let order = if ordered { "o" } else { "u" };
+                format!("{}{}x{}ei{}.v", op, order, seg, self.eew)
+            }
+        }
+    }
+
+    fn trace(&self, state: &State, store: bool) {
+        let mnemonic = self.mnemonic(store);
+        match self.addressing {
+            VAddressing::Strided(rs2) => trace!(
+                state,
+                "{} {:?}, ({:?}), {:?}",
+                mnemonic,
+                self.vd,
+                self.rs1,
+                rs2
+            ),
+            VAddressing::Indexed { vs2, .. } => trace!(
+                state,
+                "{} {:?}, ({:?}), {:?}",
+                mnemonic,
+                self.vd,
+                self.rs1,
+                vs2
+            ),
+            _ => trace!(state, "{} {:?}, ({:?})", mnemonic, self.vd, self.rs1),
+        }
+    }
+
+    fn execute(&self, state: &mut State, store: bool) -> Result<(), Exception> {
+        self.trace(state, store);
+        match self.addressing {
+            VAddressing::WholeRegister => self.whole_register(state, store)?,
+            VAddressing::Mask => self.mask(state, store)?,
+            _ => self.elements(state, store)?,
+        }
+        state.pc += 4;
+        Ok(())
+    }
+
+    /// Moves `nf` registers as one group of EEW-wide elements.
+    fn whole_register(&self, state: &mut State, store: bool) -> Result<(), Exception> {
+        check_group(self.vd, self.nf as u32 * 8)?;
+        let base = state.get_reg(self.rs1);
+        let size = self.eew as i64 / 8;
+        let evl = self.nf * state.vregs.vlenb() / size as usize;
+        for_each_element(state, true, evl, |state, i| {
+            let addr = base.wrapping_add(i as i64 * size);
+            self.transfer(state, store, addr, self.vd, i, self.eew)
+        })
+    }
+
+    fn mask(&self, state: &mut State, store: bool) -> Result<(), Exception> {
+        vtype(state)?;
+        let base = state.get_reg(self.rs1);
+        let evl = (state.vl as usize).div_ceil(8);
+        for_each_element(state, true, evl, |state, i| {
+            let addr = base.wrapping_add(i as i64);
+            self.transfer(state, store, addr, self.vd, i, 8)
+        })
+    }
+
+    /// Unit-stride, strided and indexed accesses, with segments.
+    fn elements(&self, state: &mut State, store: bool) -> Result<(), Exception> {
+        let config = vtype(state)?;
+        // Indexed accesses take their data width from SEW and their index width from EEW.
+        let (data_eew, index_emul8) = (
+            match self.addressing {
+                VAddressing::Indexed { .. } => config.sew,
+                _ => self.eew,
+            },
+            self.eew * config.lmul8 / config.sew,
+        );
+        let data_emul8 = data_eew * config.lmul8 / config.sew;
+        check_group(self.vd, data_emul8)?;
+        let regs = group_size(data_emul8);
+        if self.nf * regs > 8 || self.vd as usize + self.nf * regs > 32 {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        if let VAddressing::Indexed { vs2, .. } = self.addressing {
+            check_group(vs2, index_emul8)?;
+        }
+        if !store {
+            check_mask_overlap(self.vd, self.vm)?;
+        }
+
+        let base = state.get_reg(self.rs1);
+        let size = data_eew as i64 / 8;
+        let nf = self.nf as i64;
+        let address = |state: &State, i: usize, field: i64| -> i64 {
+            let i = i as i64;
+            let offset = match self.addressing {
+                VAddressing::Strided(rs2) => i
+                    .wrapping_mul(state.get_reg(rs2))
+                    .wrapping_add(field * size),
+                VAddressing::Indexed { vs2, .. } => {
+                    let index = state.vregs.get(vs2, i as usize, self.eew) as i64;
+                    index.wrapping_add(field * size)
+                }
+                _ => (i * nf + field) * size,
+            };
+            base.wrapping_add(offset)
+        };
+        let segment = |state: &mut State, i: usize| -> Result<(), Exception> {
+            for field in 0..self.nf {
+                let addr = address(state, i, field as i64);
+                let vd = VR::from_num((self.vd as usize + field * regs) as i64);
+                self.transfer(state, store, addr, vd, i, data_eew)?;
+            }
+            Ok(())
+        };
+
+        let vl = state.vl as usize;
+        if let VAddressing::FaultOnlyFirst = self.addressing {
+            for i in state.vstart as usize..vl {
+                if !active(state, self.vm, i) {
+                    continue;
+                }
+                match segment(state, i) {
+                    Ok(()) => {}
+                    Err(_) if i > 0 => {
+                        state.vl = i as Reg;
+                        break;
+                    }
+                    Err(exception) => return Err(exception),
+                }
+            }
+            state.vstart = 0;
+            return Ok(());
+        }
+        for_each_element(state, self.vm, vl, segment)
+    }
+
+    fn transfer(
+        &self,
+        state: &mut State,
+        store: bool,
+        addr: i64,
+        vd: VR,
+        i: usize,
+        eew: u32,
+    ) -> Result<(), Exception> {
+        if store {
+            let value = state.vregs.get(vd, i, eew);
+            match eew {
+                8 => state.store_u8(addr, value as u8),
+                16 => state.store_u16(addr, value as u16),
+                32 => state.store_u32(addr, value as u32),
+                _ => state.store_u64(addr, value),
+            }
+        } else {
+            let value = match eew {
+                8 => state.access(addr)? as u64,
+                16 => state.access_u16(addr)? as u64,
+                32 => state.access_u32(addr)? as u64,
+                _ => state.access_u64(addr)?,
+            };
+            state.set_vreg(vd, i, eew, value);
+            Ok(())
+        }
+    }
+}
+
+pub struct VLoad {
+    access: VMemory,
+}
+impl VLoad {
+    pub fn new(inst: u32, addressing: VAddressing, eew: u32) -> Self {
+        VLoad {
+            access: VMemory::new(inst, addressing, eew),
+        }
+    }
+}
+impl Instruction for VLoad {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        self.access.execute(state, false)
+    }
+}
+
+pub struct VStore {
+    access: VMemory,
+}
+impl VStore {
+    pub fn new(inst: u32, addressing: VAddressing, eew: u32) -> Self {
+        VStore {
+            access: VMemory::new(inst, addressing, eew),
+        }
+    }
+}
+impl Instruction for VStore {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        self.access.execute(state, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VR;
+    use crate::{
+        csr::MSTATUS_VS_INITIAL,
+        machine::{
+            tests::{machine_with, BASE},
+            Machine,
+        },
+        processor::{Reg, XprName::*},
+    };
+
+    /// Builds a machine with the vector unit on and `a1` holding the AVL.
+    fn vector_machine(isa: &str, code: &[u32], avl: i64) -> Machine {
+        let mut machine = machine_with(isa, code);
+        machine.state.mstatus = MSTATUS_VS_INITIAL as Reg;
+        machine.set_reg(a1, avl);
+        machine
+    }
+
+    /// Asserts that `inst`, at `offset` from the start of the code, raised an illegal-instruction
+    /// exception.
+    fn assert_illegal(machine: &Machine, offset: u64, inst: u32) {
+        assert_eq!(machine.state.mcause, 2);
+        assert_eq!(machine.state.mepc, (BASE + offset) as i64);
+        assert_eq!(machine.state.mtval, inst as i64);
+    }
+
+    #[test]
+    fn unsupported_vtype_sets_vill() {
+        let code = [
+            0x0d05_f557, // vsetvli a0, a1, e32, m1, ta, ma
+            0x0045_f557, // vsetvli a0, a1, 0x4 (reserved LMUL)
+            0xc210_26f3, // csrr a3, vtype
+            0x0221_80d7, // vadd.vv v1, v2, v3
+        ];
+        let mut machine = vector_machine("rv64gcv", &code, 3);
+        machine.step();
+        assert_eq!(machine.reg(a0), 3);
+        assert_eq!(machine.state.vtype, 0xd0);
+        machine.run(3);
+        assert_eq!(machine.reg(a0), 0);
+        assert_eq!(machine.state.vl, 0);
+        assert_eq!(machine.state.get_reg_u(a3), 1 << 63);
+        assert_illegal(&machine, 12, 0x0221_80d7);
+
+        // SEW=64 is beyond Zve32x's ELEN.
+        let code = [
+            0x0d85_f557, // vsetvli a0, a1, e64, m1, ta, ma
+            0xc210_26f3, // csrr a3, vtype
+        ];
+        let mut machine = vector_machine("rv32imac_zve32x", &code, 3);
+        machine.run(2);
+        assert_eq!(machine.state.mcause, 0);
+        assert_eq!(machine.reg(a0), 0);
+        assert_eq!(machine.state.get_reg_u(a3), 1 << 31);
+    }
+
+    #[test]
+    fn tail_and_inactive_elements_are_left_undisturbed() {
+        // Agnostic elements may be left alone too, so ta, ma and tu, mu behave the same.
+        for (vsetvli, vtype) in [
+            (0x0d05_f557, 0xd0), // vsetvli a0, a1, e32, m1, ta, ma
+            (0x0105_f557, 0x10), // vsetvli a0, a1, e32, m1, tu, mu
+        ] {
+            // vadd.vi v2, v1, 1, v0.t
+            let mut machine = vector_machine("rv64gcv", &[vsetvli, 0x0010_b157], 2);
+            for i in 0..4 {
+                machine.state.vregs.set(VR::v1, i, 32, 10 + i as u64);
+                machine
+                    .state
+                    .vregs
+                    .set(VR::v2, i, 32, 0xdead_0000 + i as u64);
+            }
+            machine.state.vregs.set(VR::v0, 0, 8, 0b01);
+            machine.run(2);
+            assert_eq!(machine.state.mcause, 0);
+            assert_eq!(machine.state.vtype, vtype);
+            let v2: Vec<u64> = (0..4)
+                .map(|i| machine.state.vregs.get(VR::v2, i, 32))
+                .collect();
+            assert_eq!(v2, [11, 0xdead_0001, 0xdead_0002, 0xdead_0003]);
+        }
+    }
+
+    #[test]
+    fn register_groups_must_be_aligned_to_lmul() {
+        for (inst, aligned) in [
+            (0x0243_0157, true),  // vadd.vv v2, v4, v6
+            (0x0222_00d7, false), // vadd.vv v1, v2, v4
+            (0x0232_0157, false), // vadd.vv v2, v3, v4
+            (0x0242_8157, false), // vadd.vv v2, v4, v5
+            (0x0206_6107, true),  // vle32.v v2, (a2)
+            (0x0206_6087, false), // vle32.v v1, (a2)
+        ] {
+            // vsetvli a0, a1, e32, m2, ta, ma
+            let mut machine = vector_machine("rv64gcv", &[0x0d15_f557, inst], 8);
+            machine.set_reg(a2, (BASE + 0x100) as i64);
+            machine.run(2);
+            if aligned {
+                assert_eq!(machine.state.mcause, 0, "{:#x}", inst);
+                assert_eq!(machine.pc(), BASE + 8);
+            } else {
+                assert_illegal(&machine, 4, inst);
+            }
+        }
+    }
+
+    #[test]
+    fn vector_unit_off_makes_vector_instructions_illegal() {
+        for inst in [
+            0x0d05_f557, // vsetvli a0, a1, e32, m1, ta, ma
+            0x0221_80d7, // vadd.vv v1, v2, v3
+            0x5e02_b0d7, // vmv.v.i v1, 5
+            0x0206_6087, // vle32.v v1, (a2)
+            0xc210_26f3, // csrr a3, vtype
+        ] {
+            for isa in ["rv64gcv", "rv32imac_zve32x"] {
+                let mut machine = machine_with(isa, &[inst]);
+                machine.set_reg(a2, (BASE + 0x100) as i64);
+                machine.step();
+                assert_illegal(&machine, 0, inst);
+            }
+        }
+    }
+}
@@ -0,0 +1,637 @@
+use enumn::N;
+
+use super::{
+    rvv::{
+        active, check_group, check_mask_overlap, check_vstart_zero, for_each_element, mnemonic,
+        rvv_arith_type, sext, vtype, VSource,
+    },
+    Instruction,
+};
+use crate::{
+    processor::{FprName, State, VR},
+    softfloat::{self, Format, RoundingMode, F32, F64},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+/// The floating-point format of SEW-wide elements, or an illegal-instruction exception when
+/// the vector unit has no floating point of that width.
+pub fn float_format(state: &State, sew: u32) -> Result<Format, Exception> {
+    let any = |extensions: &[&str]| extensions.iter().any(|ext| state.has_extension(ext));
+    match sew {
+        32 if any(&["v", "zve32f", "zve64f", "zve64d"]) => Ok(F32),
+        64 if any(&["v", "zve64d"]) => Ok(F64),
+        _ => Err(Exception::IllegalInstruction(0)),
+    }
+}
+
+fn neg(fmt: Format, value: u64) -> u64 {
+    value ^ fmt.sign_bit()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VFloatOp {
+    vfadd,
+    vfsub,
+    vfrsub,
+    vfmul,
+    vfdiv,
+    vfrdiv,
+    vfmin,
+    vfmax,
+    vfsgnj,
+    vfsgnjn,
+    vfsgnjx,
+    vfmerge,
+    vfmv,
+    vfmacc,
+    vfnmacc,
+    vfmsac,
+    vfnmsac,
+    vfmadd,
+    vfnmadd,
+    vfmsub,
+    vfnmsub,
+}
+impl VFloatOp {
+    /// One element from vs2 (`a`), the second operand (`b`) and the old vd (`d`).
+    fn eval(self, fmt: Format, a: u64, b: u64, d: u64, carry: bool, rm: RoundingMode) -> (u64, u8) {
+        use VFloatOp::*;
+        let sign = fmt.sign_bit();
+        match self {
+            vfadd => softfloat::add(fmt, a, b, rm),
+            vfsub => softfloat::sub(fmt, a, b, rm),
+            vfrsub => softfloat::sub(fmt, b, a, rm),
+            vfmul => softfloat::mul(fmt, a, b, rm),
+            vfdiv => softfloat::div(fmt, a, b, rm),
+            vfrdiv => softfloat::div(fmt, b, a, rm),
+            vfmin => softfloat::min(fmt, a, b),
+            vfmax => softfloat::max(fmt, a, b),
+            vfsgnj => (a & !sign | b & sign, 0),
+            vfsgnjn => (a & !sign | !b & sign, 0),
+            vfsgnjx => (a ^ b & sign, 0),
+            vfmerge => (if carry { b } else { a }, 0),
+            vfmv => (b, 0),
+            // The multiply-adds overwrite either the addend (the *acc and *sac forms) or a
+            // multiplicand (the *add and *sub forms).
+            vfmacc => softfloat::fma(fmt, b, a, d, rm),
+            vfnmacc => softfloat::fma(fmt, neg(fmt, b), a, neg(fmt, d), rm),
+            vfmsac => softfloat::fma(fmt, b, a, neg(fmt, d), rm),
+            vfnmsac => softfloat::fma(fmt, neg(fmt, b), a, d, rm),
+            vfmadd => softfloat::fma(fmt, b, d, a, rm),
+            vfnmadd => softfloat::fma(fmt, neg(fmt, b), d, neg(fmt, a), rm),
+            vfmsub => softfloat::fma(fmt, b, d, neg(fmt, a), rm),
+            vfnmsub => softfloat::fma(fmt, neg(fmt, b), d, a, rm),
+        }
+    }
+}
+
+/// Single-width floating-point arithmetic: `vd[i] = op(vs2[i], vs1[i] | rs1)`.
+pub struct VFloatBinary {
+    op: VFloatOp,
+    vd: VR,
+    vs2: VR,
+    src: VSource,
+    vm: bool,
+}
+impl VFloatBinary {
+    pub fn new(inst: u32, op: VFloatOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let src = VSource::new(inst);
+        VFloatBinary {
+            op,
+            vd,
+            vs2,
+            src,
+            vm,
+        }
+    }
+}
+impl Instruction for VFloatBinary {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        let name = match self.op {
+            VFloatOp::vfmv => "vfmv.v.f".to_string(),
+            VFloatOp::vfmerge => "vfmerge.vfm".to_string(),
+            op => mnemonic(op, self.src.suffix()),
+        };
+        trace!(
+            state,
+            "{} {:?}, {:?}, {}",
+            name,
+            self.vd,
+            self.vs2,
+            self.src
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        let fmt = float_format(state, sew)?;
+        let rm = state.rounding_mode(0b111)?;
+        check_group(self.vd, config.lmul8)?;
+        check_group(self.vs2, config.lmul8)?;
+        self.src.check_group(config.lmul8)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        let mut flags = 0;
+        let vl = state.vl as usize;
+        let vm = self.vm || self.op == VFloatOp::vfmerge;
+        let result = for_each_element(state, vm, vl, |state, i| {
+            let a = state.vregs.get(self.vs2, i, sew);
+            let b = self.src.element(state, i, sew);
+            let d = state.vregs.get(self.vd, i, sew);
+            let carry = !self.vm && state.vregs.mask(VR::v0, i);
+            let (value, element_flags) = self.op.eval(fmt, a, b, d, carry, rm);
+            flags |= element_flags;
+            state.set_vreg(self.vd, i, sew, value);
+            Ok(())
+        });
+        state.accrue_fflags(flags);
+        result?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VFloatCompareOp {
+    vmfeq,
+    vmfle,
+    vmflt,
+    vmfne,
+    vmfgt,
+    vmfge,
+}
+impl VFloatCompareOp {
+    fn eval(self, fmt: Format, a: u64, b: u64) -> (bool, u8) {
+        use VFloatCompareOp::*;
+        match self {
+            vmfeq => softfloat::eq(fmt, a, b),
+            vmfle => softfloat::le(fmt, a, b),
+            vmflt => softfloat::lt(fmt, a, b),
+            vmfne => {
+                let (equal, flags) = softfloat::eq(fmt, a, b);
+                (!equal, flags)
+            }
+            vmfgt => softfloat::lt(fmt, b, a),
+            vmfge => softfloat::le(fmt, b, a),
+        }
+    }
+}
+
+/// Floating-point compares, written as a mask.
+pub struct VFloatCompare {
+    op: VFloatCompareOp,
+    vd: VR,
+    vs2: VR,
+    src: VSource,
+    vm: bool,
+}
+impl VFloatCompare {
+    pub fn new(inst: u32, op: VFloatCompareOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let src = VSource::new(inst);
+        VFloatCompare {
+            op,
+            vd,
+            vs2,
+            src,
+            vm,
+        }
+    }
+}
+impl Instruction for VFloatCompare {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "{} {:?}, {:?}, {}",
+            mnemonic(self.op, self.src.suffix()),
+            self.vd,
+            self.vs2,
+            self.src
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        let fmt = float_format(state, sew)?;
+        check_group(self.vs2, config.lmul8)?;
+        self.src.check_group(config.lmul8)?;
+        let mut flags = 0;
+        let mut results = vec![];
+        let vl = state.vl as usize;
+        for_each_element(state, self.vm, vl, |state, i| {
+            let a = state.vregs.get(self.vs2, i, sew);
+            let b = self.src.element(state, i, sew);
+            let (value, element_flags) = self.op.eval(fmt, a, b);
+            flags |= element_flags;
+            results.push((i, value));
+            Ok(())
+        })?;
+        for (i, value) in results {
+            state.set_vmask(self.vd, i, value);
+        }
+        state.accrue_fflags(flags);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VFloatWidenOp {
+    vfwadd,
+    vfwsub,
+    vfwadd_w,
+    vfwsub_w,
+    vfwmul,
+    vfwmacc,
+    vfwnmacc,
+    vfwmsac,
+    vfwnmsac,
+}
+impl VFloatWidenOp {
+    fn wide_vs2(self) -> bool {
+        matches!(self, VFloatWidenOp::vfwadd_w | VFloatWidenOp::vfwsub_w)
+    }
+    /// The narrow operands are widened exactly, then the operation rounds once at 2*SEW.
+    fn eval(
+        self,
+        narrow: Format,
+        wide: Format,
+        a: u64,
+        b: u64,
+        d: u64,
+        rm: RoundingMode,
+    ) -> (u64, u8) {
+        use VFloatWidenOp::*;
+        let (a, a_flags) = if self.wide_vs2() {
+            (a, 0)
+        } else {
+            softfloat::convert(narrow, wide, a, rm)
+        };
+        let (b, b_flags) = softfloat::convert(narrow, wide, b, rm);
+        let (value, flags) = match self {
+            vfwadd | vfwadd_w => softfloat::add(wide, a, b, rm),
+            vfwsub | vfwsub_w => softfloat::sub(wide, a, b, rm),
+            vfwmul => softfloat::mul(wide, a, b, rm),
+            vfwmacc => softfloat::fma(wide, b, a, d, rm),
+            vfwnmacc => softfloat::fma(wide, neg(wide, b), a, neg(wide, d), rm),
+            vfwmsac => softfloat::fma(wide, b, a, neg(wide, d), rm),
+            vfwnmsac => softfloat::fma(wide, neg(wide, b), a, d, rm),
+        };
+        (value, flags | a_flags | b_flags)
+    }
+}
+
+/// Widening floating-point arithmetic, writing 2*SEW-wide elements.
+pub struct VFloatWiden {
+    op: VFloatWidenOp,
+    vd: VR,
+    vs2: VR,
+    src: VSource,
+    vm: bool,
+}
+impl VFloatWiden {
+    pub fn new(inst: u32, op: VFloatWidenOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let src = VSource::new(inst);
+        VFloatWiden {
+            op,
+            vd,
+            vs2,
+            src,
+            vm,
+        }
+    }
+}
+impl Instruction for VFloatWiden {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "{} {:?}, {:?}, {}",
+            mnemonic(self.op, self.src.suffix()),
+            self.vd,
+            self.vs2,
+            self.src
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        let narrow = float_format(state, sew)?;
+        let wide = float_format(state, sew * 2)?;
+        let rm = state.rounding_mode(0b111)?;
+        let vs2_eew = if self.op.wide_vs2() { sew * 2 } else { sew };
+        check_group(self.vd, config.lmul8 * 2)?;
+        check_group(self.vs2, config.lmul8 * vs2_eew / sew)?;
+        self.src.check_group(config.lmul8)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        let mut flags = 0;
+        let vl = state.vl as usize;
+        let result = for_each_element(state, self.vm, vl, |state, i| {
+            let a = state.vregs.get(self.vs2, i, vs2_eew);
+            let b = self.src.element(state, i, sew);
+            let d = state.vregs.get(self.vd, i, sew * 2);
+            let (value, element_flags) = self.op.eval(narrow, wide, a, b, d, rm);
+            flags |= element_flags;
+            state.set_vreg(self.vd, i, sew * 2, value);
+            Ok(())
+        });
+        state.accrue_fflags(flags);
+        result?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VFloatUnaryOp {
+    vfsqrt,
+    vfrsqrt7,
+    vfrec7,
+    vfclass,
+}
+
+/// The VFUNARY1 group: square root, the two estimates and classification.
+pub struct VFloatUnary {
+    op: VFloatUnaryOp,
+    vd: VR,
+    vs2: VR,
+    vm: bool,
+}
+impl VFloatUnary {
+    pub fn new(inst: u32, op: VFloatUnaryOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        VFloatUnary { op, vd, vs2, vm }
+    }
+}
+impl Instruction for VFloatUnary {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "{:?}.v {:?}, {:?}", self.op, self.vd, self.vs2);
+        let config = vtype(state)?;
+        let sew = config.sew;
+        let fmt = float_format(state, sew)?;
+        let rm = state.rounding_mode(0b111)?;
+        check_group(self.vd, config.lmul8)?;
+        check_group(self.vs2, config.lmul8)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        let mut flags = 0;
+        let vl = state.vl as usize;
+        let result = for_each_element(state, self.vm, vl, |state, i| {
+            let a = state.vregs.get(self.vs2, i, sew);
+            let (value, element_flags) = match self.op {
+                VFloatUnaryOp::vfsqrt => softfloat::sqrt(fmt, a, rm),
+                VFloatUnaryOp::vfrsqrt7 => softfloat::rsqrt7(fmt, a),
+                VFloatUnaryOp::vfrec7 => softfloat::recip7(fmt, a, rm),
+                VFloatUnaryOp::vfclass => (softfloat::classify(fmt, a), 0),
+            };
+            flags |= element_flags;
+            state.set_vreg(self.vd, i, sew, value);
+            Ok(())
+        });
+        state.accrue_fflags(flags);
+        result?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+/// The VFUNARY0 conversions, numbered by their vs1 encoding.
+#[derive(Debug, PartialEq, N, Clone, Copy)]
+#[repr(u8)]
+pub enum VFloatConvertOp {
+    vfcvt_xu_f = 0b00000,
+    vfcvt_x_f = 0b00001,
+    vfcvt_f_xu = 0b00010,
+    vfcvt_f_x = 0b00011,
+    vfcvt_rtz_xu_f = 0b00110,
+    vfcvt_rtz_x_f = 0b00111,
+    vfwcvt_xu_f = 0b01000,
+    vfwcvt_x_f = 0b01001,
+    vfwcvt_f_xu = 0b01010,
+    vfwcvt_f_x = 0b01011,
+    vfwcvt_f_f = 0b01100,
+    vfwcvt_rtz_xu_f = 0b01110,
+    vfwcvt_rtz_x_f = 0b01111,
+    vfncvt_xu_f = 0b10000,
+    vfncvt_x_f = 0b10001,
+    vfncvt_f_xu = 0b10010,
+    vfncvt_f_x = 0b10011,
+    vfncvt_f_f = 0b10100,
+    vfncvt_rod_f_f = 0b10101,
+    vfncvt_rtz_xu_f = 0b10110,
+    vfncvt_rtz_x_f = 0b10111,
+}
+
+/// Conversions between integers and floats, and between float widths. Bits 4:3 of the
+/// encoding say whether the destination is single-width, widened or narrowed, and bits 2:0
+/// which conversion it is.
+pub struct VFloatConvert {
+    op: VFloatConvertOp,
+    vd: VR,
+    vs2: VR,
+    vm: bool,
+}
+impl VFloatConvert {
+    pub fn new(inst: u32, op: VFloatConvertOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        VFloatConvert { op, vd, vs2, vm }
+    }
+}
+impl Instruction for VFloatConvert {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        let code = self.op as u8;
+        let width = if code >> 3 == 0b10 { "w" } else { "v" };
+        trace!(
+            state,
+            "{}.{} {:?}, {:?}",
+            format!("{:?}", self.op).replace('_', "."),
+            width,
+            self.vd,
+            self.vs2
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        let (src_eew, dst_eew) = match code >> 3 {
+            0b00 => (sew, sew),
+            0b01 => (sew, sew * 2),
+            _ => (sew * 2, sew),
+        };
+        if src_eew > state.elen || dst_eew > state.elen {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        check_group(self.vd, config.lmul8 * dst_eew / sew)?;
+        check_group(self.vs2, config.lmul8 * src_eew / sew)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        let kind = code & 0b111;
+        let signed = kind & 1 == 1;
+        // Only the float side of an integer conversion needs a floating-point format.
+        let (src_fmt, dst_fmt) = match kind {
+            0b010 | 0b011 => (None, Some(float_format(state, dst_eew)?)),
+            0b100 | 0b101 => (
+                Some(float_format(state, src_eew)?),
+                Some(float_format(state, dst_eew)?),
+            ),
+            _ => (Some(float_format(state, src_eew)?), None),
+        };
+        let rm = match kind {
+            0b110 | 0b111 => RoundingMode::RTZ,
+            _ => state.rounding_mode(0b111)?,
+        };
+        let mut flags = 0;
+        let vl = state.vl as usize;
+        let result = for_each_element(state, self.vm, vl, |state, i| {
+            let a = state.vregs.get(self.vs2, i, src_eew);
+            let (value, element_flags) = match (src_fmt, dst_fmt) {
+                (Some(from), Some(to)) if kind == 0b101 => {
+                    softfloat::convert_round_to_odd(from, to, a)
+                }
+                (Some(from), Some(to)) => softfloat::convert(from, to, a, rm),
+                (Some(from), None) => {
+                    let (value, flags) = softfloat::to_int(from, a, rm, signed, dst_eew);
+                    (value as u64, flags)
+                }
+                (None, Some(to)) => {
+                    let value = if signed { sext(a, src_eew) as u64 } else { a };
+                    softfloat::from_int(to, value, signed, rm)
+                }
+                (None, None) => unreachable!(),
+            };
+            flags |= element_flags;
+            state.set_vreg(self.vd, i, dst_eew, value);
+            Ok(())
+        });
+        state.accrue_fflags(flags);
+        result?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VFloatReduceOp {
+    vfredusum,
+    vfredosum,
+    vfredmin,
+    vfredmax,
+    vfwredusum,
+    vfwredosum,
+}
+
+/// Floating-point reductions. The unordered sums are computed in element order too.
+pub struct VFloatReduce {
+    op: VFloatReduceOp,
+    vd: VR,
+    vs2: VR,
+    vs1: VR,
+    vm: bool,
+}
+impl VFloatReduce {
+    pub fn new(inst: u32, op: VFloatReduceOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let vs1 = VR::from_num(x(inst, 15, 5));
+        VFloatReduce {
+            op,
+            vd,
+            vs2,
+            vs1,
+            vm,
+        }
+    }
+}
+impl Instruction for VFloatReduce {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        use VFloatReduceOp::*;
+        trace!(
+            state,
+            "{:?}.vs {:?}, {:?}, {:?}",
+            self.op,
+            self.vd,
+            self.vs2,
+            self.vs1
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        let fmt = float_format(state, sew)?;
+        let widening = matches!(self.op, vfwredusum | vfwredosum);
+        let acc_eew = if widening { sew * 2 } else { sew };
+        let acc_fmt = float_format(state, acc_eew)?;
+        let rm = state.rounding_mode(0b111)?;
+        check_group(self.vs2, config.lmul8)?;
+        check_vstart_zero(state)?;
+        let vl = state.vl as usize;
+        if vl > 0 {
+            let mut flags = 0;
+            let mut acc = state.vregs.get(self.vs1, 0, acc_eew);
+            for i in 0..vl {
+                if !active(state, self.vm, i) {
+                    continue;
+                }
+                let mut element = state.vregs.get(self.vs2, i, sew);
+                if widening {
+                    let (wide, element_flags) = softfloat::convert(fmt, acc_fmt, element, rm);
+                    element = wide;
+                    flags |= element_flags;
+                }
+                let (value, element_flags) = match self.op {
+                    vfredmin => softfloat::min(acc_fmt, acc, element),
+                    vfredmax => softfloat::max(acc_fmt, acc, element),
+                    _ => softfloat::add(acc_fmt, acc, element, rm),
+                };
+                acc = value;
+                flags |= element_flags;
+            }
+            state.set_vreg(self.vd, 0, acc_eew, acc);
+            state.accrue_fflags(flags);
+        }
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct VFMV_F_S {
+    rd: FprName,
+    vs2: VR,
+}
+impl VFMV_F_S {
+    pub fn new(inst: u32) -> Self {
+        let (_, vs2, _) = rvv_arith_type(inst);
+        let rd = FprName::from_num(x(inst, 7, 5));
+        VFMV_F_S { rd, vs2 }
+    }
+}
+impl Instruction for VFMV_F_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "vfmv.f.s {:?}, {:?}", self.rd, self.vs2);
+        let sew = vtype(state)?.sew;
+        float_format(state, sew)?;
+        let value = state.vregs.get(self.vs2, 0, sew);
+        if sew == 32 {
+            state.set_freg_s(self.rd, value);
+        } else {
+            state.set_freg_d(self.rd, value);
+        }
+        state.vstart = 0;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct VFMV_S_F {
+    vd: VR,
+    rs1: FprName,
+}
+impl VFMV_S_F {
+    pub fn new(inst: u32) -> Self {
+        let (vd, _, _) = rvv_arith_type(inst);
+        let rs1 = FprName::from_num(x(inst, 15, 5));
+        VFMV_S_F { vd, rs1 }
+    }
+}
+impl Instruction for VFMV_S_F {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "vfmv.s.f {:?}, {:?}", self.vd, self.rs1);
+        let sew = vtype(state)?.sew;
+        float_format(state, sew)?;
+        if state.vstart < state.vl {
+            let value = VSource::Float(self.rs1).element(state, 0, sew);
+            state.set_vreg(self.vd, 0, sew, value);
+        }
+        state.vstart = 0;
+        state.pc += 4;
+        Ok(())
+    }
+}
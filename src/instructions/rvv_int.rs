@@ -0,0 +1,679 @@
+use super::{
+    rvv::{
+        active, check_group, check_mask_overlap, check_vstart_zero, for_each_element, mask_bits,
+        mnemonic, rvv_arith_type, sext, vtype, VSource,
+    },
+    Instruction,
+};
+use crate::{
+    processor::{Reg, State, VR},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+/// The fixed-point rounding mode in effect and whether any element saturated.
+struct FixedPoint {
+    vxrm: Reg,
+    saturated: bool,
+}
+impl FixedPoint {
+    fn new(state: &State) -> Self {
+        FixedPoint {
+            vxrm: state.vxrm,
+            saturated: false,
+        }
+    }
+    /// Shifts right by `shift` bits, rounding off the discarded bits as vxrm says.
+    fn roundoff(&self, value: i128, shift: u32) -> i128 {
+        if shift == 0 {
+            return value;
+        }
+        let lsb = value >> shift & 1;
+        let half = value >> (shift - 1) & 1;
+        let below_half = (value & ((1 << (shift - 1)) - 1) != 0) as i128;
+        let round = match self.vxrm {
+            // rnu, rne, rdn and rod.
+            0 => half,
+            1 => half & (below_half | lsb),
+            2 => 0,
+            _ => (lsb == 0 && half | below_half != 0) as i128,
+        };
+        (value >> shift) + round
+    }
+    fn clamp_signed(&mut self, value: i128, bits: u32) -> u64 {
+        let max = (1 << (bits - 1)) - 1;
+        let min = -(1 << (bits - 1));
+        self.saturated |= value > max || value < min;
+        value.clamp(min, max) as u64
+    }
+    fn clamp_unsigned(&mut self, value: i128, bits: u32) -> u64 {
+        let max = mask_bits(bits) as i128;
+        self.saturated |= value > max || value < 0;
+        value.clamp(0, max) as u64
+    }
+    /// vxsat is sticky, so it is only ever set here.
+    fn finish(self, state: &mut State) {
+        if self.saturated {
+            state.vxsat = 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VIntOp {
+    vadd,
+    vsub,
+    vrsub,
+    vminu,
+    vmin,
+    vmaxu,
+    vmax,
+    vand,
+    vor,
+    vxor,
+    vsll,
+    vsrl,
+    vsra,
+    vadc,
+    vsbc,
+    vmerge,
+    vmv,
+    vsaddu,
+    vsadd,
+    vssubu,
+    vssub,
+    vsmul,
+    vssrl,
+    vssra,
+    vaaddu,
+    vaadd,
+    vasubu,
+    vasub,
+    vdivu,
+    vdiv,
+    vremu,
+    vrem,
+    vmulhu,
+    vmul,
+    vmulhsu,
+    vmulh,
+    vmacc,
+    vnmsac,
+    vmadd,
+    vnmsub,
+}
+impl VIntOp {
+    /// vadc, vsbc and vmerge read v0 as an operand rather than as a mask.
+    fn takes_carry(self) -> bool {
+        matches!(self, VIntOp::vadc | VIntOp::vsbc | VIntOp::vmerge)
+    }
+    /// One SEW-wide element from vs2 (`a`), the second operand (`b`) and the old vd (`d`).
+    fn eval(self, a: u64, b: u64, d: u64, carry: bool, sew: u32, fixed: &mut FixedPoint) -> u64 {
+        use VIntOp::*;
+        let (sa, sb) = (sext(a, sew) as i128, sext(b, sew) as i128);
+        let (ua, ub) = (a as i128, b as i128);
+        let shift = (b & (sew as u64 - 1)) as u32;
+        match self {
+            vadd => a.wrapping_add(b),
+            vsub => a.wrapping_sub(b),
+            vrsub => b.wrapping_sub(a),
+            vminu => a.min(b),
+            vmin => {
+                if sa < sb {
+                    a
+                } else {
+                    b
+                }
+            }
+            vmaxu => a.max(b),
+            vmax => {
+                if sa > sb {
+                    a
+                } else {
+                    b
+                }
+            }
+            vand => a & b,
+            vor => a | b,
+            vxor => a ^ b,
+            vsll => a << shift,
+            vsrl => a >> shift,
+            vsra => (sa >> shift) as u64,
+            vadc => a.wrapping_add(b).wrapping_add(carry as u64),
+            vsbc => a.wrapping_sub(b).wrapping_sub(carry as u64),
+            vmerge => {
+                if carry {
+                    b
+                } else {
+                    a
+                }
+            }
+            vmv => b,
+            vsaddu => fixed.clamp_unsigned(ua + ub, sew),
+            vsadd => fixed.clamp_signed(sa + sb, sew),
+            vssubu => fixed.clamp_unsigned(ua - ub, sew),
+            vssub => fixed.clamp_signed(sa - sb, sew),
+            vsmul => {
+                let product = fixed.roundoff(sa * sb, sew - 1);
+                fixed.clamp_signed(product, sew)
+            }
+            vssrl => fixed.roundoff(ua, shift) as u64,
+            vssra => fixed.roundoff(sa, shift) as u64,
+            vaaddu => fixed.roundoff(ua + ub, 1) as u64,
+            vaadd => fixed.roundoff(sa + sb, 1) as u64,
+            vasubu => fixed.roundoff(ua - ub, 1) as u64,
+            vasub => fixed.roundoff(sa - sb, 1) as u64,
+            // Division by zero and overflow give the same results as the scalar M extension;
+            // the i128 arithmetic makes the overflow case wrap to the SEW-wide minimum.
+            vdivu => a.checked_div(b).unwrap_or(u64::MAX),
+            vdiv if b == 0 => u64::MAX,
+            vdiv => (sa / sb) as u64,
+            vremu if b == 0 => a,
+            vremu => a % b,
+            vrem if b == 0 => a,
+            vrem => (sa % sb) as u64,
+            vmulhu => ((a as u128 * b as u128) >> sew) as u64,
+            vmul => a.wrapping_mul(b),
+            vmulhsu => ((sa * ub) >> sew) as u64,
+            vmulh => ((sa * sb) >> sew) as u64,
+            vmacc => d.wrapping_add(b.wrapping_mul(a)),
+            vnmsac => d.wrapping_sub(b.wrapping_mul(a)),
+            vmadd => a.wrapping_add(b.wrapping_mul(d)),
+            vnmsub => a.wrapping_sub(b.wrapping_mul(d)),
+        }
+    }
+}
+
+/// Single-width integer and fixed-point arithmetic: `vd[i] = op(vs2[i], vs1[i] | rs1 | imm)`.
+pub struct VIntBinary {
+    op: VIntOp,
+    vd: VR,
+    vs2: VR,
+    src: VSource,
+    vm: bool,
+}
+impl VIntBinary {
+    pub fn new(inst: u32, op: VIntOp) -> Self {
+        use VIntOp::*;
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let src = match op {
+            vsll | vsrl | vsra | vssrl | vssra => VSource::new_unsigned(inst),
+            _ => VSource::new(inst),
+        };
+        VIntBinary {
+            op,
+            vd,
+            vs2,
+            src,
+            vm,
+        }
+    }
+}
+impl Instruction for VIntBinary {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        let suffix = self.src.suffix();
+        let name = match self.op {
+            VIntOp::vmv => format!("vmv.v.{}", &suffix[1..]),
+            op if op.takes_carry() => format!("{}m", mnemonic(op, suffix)),
+            op => mnemonic(op, suffix),
+        };
+        trace!(
+            state,
+            "{} {:?}, {:?}, {}",
+            name,
+            self.vd,
+            self.vs2,
+            self.src
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        check_group(self.vd, config.lmul8)?;
+        check_group(self.vs2, config.lmul8)?;
+        self.src.check_group(config.lmul8)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        let mut fixed = FixedPoint::new(state);
+        let vl = state.vl as usize;
+        let vm = self.vm || self.op.takes_carry();
+        for_each_element(state, vm, vl, |state, i| {
+            let a = state.vregs.get(self.vs2, i, sew);
+            let b = self.src.element(state, i, sew);
+            let d = state.vregs.get(self.vd, i, sew);
+            let carry = !self.vm && state.vregs.mask(VR::v0, i);
+            let value = self.op.eval(a, b, d, carry, sew, &mut fixed);
+            state.set_vreg(self.vd, i, sew, value);
+            Ok(())
+        })?;
+        fixed.finish(state);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VIntWidenOp {
+    vwaddu,
+    vwadd,
+    vwsubu,
+    vwsub,
+    vwaddu_w,
+    vwadd_w,
+    vwsubu_w,
+    vwsub_w,
+    vwmulu,
+    vwmulsu,
+    vwmul,
+    vwmaccu,
+    vwmacc,
+    vwmaccus,
+    vwmaccsu,
+}
+impl VIntWidenOp {
+    /// The `.w` forms take vs2 at 2*SEW already.
+    fn wide_vs2(self) -> bool {
+        use VIntWidenOp::*;
+        matches!(self, vwaddu_w | vwadd_w | vwsubu_w | vwsub_w)
+    }
+    /// One 2*SEW-wide element from vs2 (`a`), the SEW-wide second operand (`b`) and the old
+    /// vd (`d`).
+    fn eval(self, a: u64, b: u64, d: u64, sew: u32) -> u64 {
+        use VIntWidenOp::*;
+        let a_bits = if self.wide_vs2() { sew * 2 } else { sew };
+        let (ua, sa) = (a as i128, sext(a, a_bits) as i128);
+        let (ub, sb) = (b as i128, sext(b, sew) as i128);
+        let d = d as i128;
+        let value = match self {
+            vwaddu | vwaddu_w => ua + ub,
+            vwadd | vwadd_w => sa + sb,
+            vwsubu | vwsubu_w => ua - ub,
+            vwsub | vwsub_w => sa - sb,
+            vwmulu => ua * ub,
+            vwmulsu => sa * ub,
+            vwmul => sa * sb,
+            vwmaccu => d + ub * ua,
+            vwmacc => d + sb * sa,
+            vwmaccus => d + ub * sa,
+            vwmaccsu => d + sb * ua,
+        };
+        value as u64
+    }
+}
+
+/// Widening integer arithmetic, writing 2*SEW-wide elements.
+pub struct VIntWiden {
+    op: VIntWidenOp,
+    vd: VR,
+    vs2: VR,
+    src: VSource,
+    vm: bool,
+}
+impl VIntWiden {
+    pub fn new(inst: u32, op: VIntWidenOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let src = VSource::new(inst);
+        VIntWiden {
+            op,
+            vd,
+            vs2,
+            src,
+            vm,
+        }
+    }
+}
+impl Instruction for VIntWiden {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "{} {:?}, {:?}, {}",
+            mnemonic(self.op, self.src.suffix()),
+            self.vd,
+            self.vs2,
+            self.src
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        if sew * 2 > state.elen {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        let vs2_eew = if self.op.wide_vs2() { sew * 2 } else { sew };
+        check_group(self.vd, config.lmul8 * 2)?;
+        check_group(self.vs2, config.lmul8 * vs2_eew / sew)?;
+        self.src.check_group(config.lmul8)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        let vl = state.vl as usize;
+        for_each_element(state, self.vm, vl, |state, i| {
+            let a = state.vregs.get(self.vs2, i, vs2_eew);
+            let b = self.src.element(state, i, sew);
+            let d = state.vregs.get(self.vd, i, sew * 2);
+            let value = self.op.eval(a, b, d, sew);
+            state.set_vreg(self.vd, i, sew * 2, value);
+            Ok(())
+        })?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VIntNarrowOp {
+    vnsrl,
+    vnsra,
+    vnclipu,
+    vnclip,
+}
+
+/// Narrowing shifts and clips of 2*SEW-wide vs2 elements down to SEW.
+pub struct VIntNarrow {
+    op: VIntNarrowOp,
+    vd: VR,
+    vs2: VR,
+    src: VSource,
+    vm: bool,
+}
+impl VIntNarrow {
+    pub fn new(inst: u32, op: VIntNarrowOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let src = VSource::new_unsigned(inst);
+        VIntNarrow {
+            op,
+            vd,
+            vs2,
+            src,
+            vm,
+        }
+    }
+}
+impl Instruction for VIntNarrow {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "{:?}.w{} {:?}, {:?}, {}",
+            self.op,
+            &self.src.suffix()[1..],
+            self.vd,
+            self.vs2,
+            self.src
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        let wide = sew * 2;
+        if wide > state.elen {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        check_group(self.vd, config.lmul8)?;
+        check_group(self.vs2, config.lmul8 * 2)?;
+        self.src.check_group(config.lmul8)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        let mut fixed = FixedPoint::new(state);
+        let vl = state.vl as usize;
+        for_each_element(state, self.vm, vl, |state, i| {
+            let a = state.vregs.get(self.vs2, i, wide);
+            let shift = (self.src.element(state, i, sew) & (wide as u64 - 1)) as u32;
+            let value = match self.op {
+                VIntNarrowOp::vnsrl => a >> shift,
+                VIntNarrowOp::vnsra => (sext(a, wide) >> shift) as u64,
+                VIntNarrowOp::vnclipu => {
+                    let value = fixed.roundoff(a as i128, shift);
+                    fixed.clamp_unsigned(value, sew)
+                }
+                VIntNarrowOp::vnclip => {
+                    let value = fixed.roundoff(sext(a, wide) as i128, shift);
+                    fixed.clamp_signed(value, sew)
+                }
+            };
+            state.set_vreg(self.vd, i, sew, value);
+            Ok(())
+        })?;
+        fixed.finish(state);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VIntCompareOp {
+    vmseq,
+    vmsne,
+    vmsltu,
+    vmslt,
+    vmsleu,
+    vmsle,
+    vmsgtu,
+    vmsgt,
+    vmadc,
+    vmsbc,
+}
+impl VIntCompareOp {
+    fn eval(self, a: u64, b: u64, carry: bool, sew: u32) -> bool {
+        use VIntCompareOp::*;
+        let (sa, sb) = (sext(a, sew), sext(b, sew));
+        match self {
+            vmseq => a == b,
+            vmsne => a != b,
+            vmsltu => a < b,
+            vmslt => sa < sb,
+            vmsleu => a <= b,
+            vmsle => sa <= sb,
+            vmsgtu => a > b,
+            vmsgt => sa > sb,
+            vmadc => (a as u128 + b as u128 + carry as u128) >> sew != 0,
+            vmsbc => (a as i128 - b as i128 - carry as i128) < 0,
+        }
+    }
+}
+
+/// Integer compares, and the carry and borrow outputs of vadc and vsbc, written as a mask.
+pub struct VIntCompare {
+    op: VIntCompareOp,
+    vd: VR,
+    vs2: VR,
+    src: VSource,
+    vm: bool,
+}
+impl VIntCompare {
+    pub fn new(inst: u32, op: VIntCompareOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let src = VSource::new(inst);
+        VIntCompare {
+            op,
+            vd,
+            vs2,
+            src,
+            vm,
+        }
+    }
+}
+impl Instruction for VIntCompare {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "{} {:?}, {:?}, {}",
+            mnemonic(self.op, self.src.suffix()),
+            self.vd,
+            self.vs2,
+            self.src
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        check_group(self.vs2, config.lmul8)?;
+        self.src.check_group(config.lmul8)?;
+        // vmadc and vmsbc with vm=0 take v0 as carry-in on every element.
+        let carry_in = !self.vm && matches!(self.op, VIntCompareOp::vmadc | VIntCompareOp::vmsbc);
+        let vl = state.vl as usize;
+        // The destination may overlap a source, so the mask is written once all are read.
+        let mut results = vec![];
+        for_each_element(state, self.vm || carry_in, vl, |state, i| {
+            let a = state.vregs.get(self.vs2, i, sew);
+            let b = self.src.element(state, i, sew);
+            let carry = carry_in && state.vregs.mask(VR::v0, i);
+            results.push((i, self.op.eval(a, b, carry, sew)));
+            Ok(())
+        })?;
+        for (i, value) in results {
+            state.set_vmask(self.vd, i, value);
+        }
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+/// vzext.vf2 through vsext.vf8: widens elements of SEW / `factor` bits to SEW.
+pub struct VIntExtend {
+    vd: VR,
+    vs2: VR,
+    vm: bool,
+    factor: u32,
+    signed: bool,
+}
+impl VIntExtend {
+    pub fn new(inst: u32) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        // vs1 encodes the factor in bits 2:1 (1 for vf8, 2 for vf4, 3 for vf2) and the
+        // signedness in bit 0.
+        let factor = 16 >> x(inst, 16, 2);
+        let signed = x(inst, 15, 1) == 1;
+        VIntExtend {
+            vd,
+            vs2,
+            vm,
+            factor,
+            signed,
+        }
+    }
+}
+impl Instruction for VIntExtend {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        let kind = if self.signed { "vsext" } else { "vzext" };
+        trace!(
+            state,
+            "{}.vf{} {:?}, {:?}",
+            kind,
+            self.factor,
+            self.vd,
+            self.vs2
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        let eew = sew / self.factor;
+        if eew < 8 {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        check_group(self.vd, config.lmul8)?;
+        check_group(self.vs2, config.lmul8 / self.factor)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        let vl = state.vl as usize;
+        for_each_element(state, self.vm, vl, |state, i| {
+            let value = state.vregs.get(self.vs2, i, eew);
+            let value = if self.signed {
+                sext(value, eew) as u64
+            } else {
+                value
+            };
+            state.set_vreg(self.vd, i, sew, value);
+            Ok(())
+        })?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VIntReduceOp {
+    vredsum,
+    vredand,
+    vredor,
+    vredxor,
+    vredminu,
+    vredmin,
+    vredmaxu,
+    vredmax,
+    vwredsumu,
+    vwredsum,
+}
+impl VIntReduceOp {
+    fn widening(self) -> bool {
+        matches!(self, VIntReduceOp::vwredsumu | VIntReduceOp::vwredsum)
+    }
+    /// Folds a SEW-wide element into the accumulator.
+    fn fold(self, acc: u64, element: u64, sew: u32) -> u64 {
+        use VIntReduceOp::*;
+        let (sa, se) = (sext(acc, sew), sext(element, sew));
+        match self {
+            vredsum | vwredsumu => acc.wrapping_add(element),
+            vredand => acc & element,
+            vredor => acc | element,
+            vredxor => acc ^ element,
+            vredminu => acc.min(element),
+            vredmin => {
+                if se < sa {
+                    element
+                } else {
+                    acc
+                }
+            }
+            vredmaxu => acc.max(element),
+            vredmax => {
+                if se > sa {
+                    element
+                } else {
+                    acc
+                }
+            }
+            vwredsum => acc.wrapping_add(se as u64),
+        }
+    }
+}
+
+/// Integer reductions: `vd[0] = op(vs1[0], vs2[*])` over the active elements.
+pub struct VIntReduce {
+    op: VIntReduceOp,
+    vd: VR,
+    vs2: VR,
+    vs1: VR,
+    vm: bool,
+}
+impl VIntReduce {
+    pub fn new(inst: u32, op: VIntReduceOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let vs1 = VR::from_num(x(inst, 15, 5));
+        VIntReduce {
+            op,
+            vd,
+            vs2,
+            vs1,
+            vm,
+        }
+    }
+}
+impl Instruction for VIntReduce {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "{:?}.vs {:?}, {:?}, {:?}",
+            self.op,
+            self.vd,
+            self.vs2,
+            self.vs1
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        let acc_eew = if self.op.widening() { sew * 2 } else { sew };
+        if acc_eew > state.elen {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        check_group(self.vs2, config.lmul8)?;
+        check_vstart_zero(state)?;
+        let vl = state.vl as usize;
+        if vl > 0 {
+            let mut acc = state.vregs.get(self.vs1, 0, acc_eew);
+            for i in 0..vl {
+                if active(state, self.vm, i) {
+                    let element = state.vregs.get(self.vs2, i, sew);
+                    acc = self.op.fold(acc, element, sew) & mask_bits(acc_eew);
+                }
+            }
+            state.set_vreg(self.vd, 0, acc_eew, acc);
+        }
+        state.pc += 4;
+        Ok(())
+    }
+}
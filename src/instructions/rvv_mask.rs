@@ -0,0 +1,235 @@
+use super::{
+    rvv::{
+        active, check_group, check_mask_overlap, check_vstart_zero, for_each_element, group_size,
+        rvv_arith_type, vtype,
+    },
+    Instruction,
+};
+use crate::{
+    processor::{State, XprName, VR},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VMaskOp {
+    vmandn,
+    vmand,
+    vmor,
+    vmxor,
+    vmorn,
+    vmnand,
+    vmnor,
+    vmxnor,
+}
+
+/// Bitwise logic between mask registers, over the first vl bits.
+pub struct VMaskLogical {
+    op: VMaskOp,
+    vd: VR,
+    vs2: VR,
+    vs1: VR,
+}
+impl VMaskLogical {
+    pub fn new(inst: u32, op: VMaskOp) -> Self {
+        let (vd, vs2, _) = rvv_arith_type(inst);
+        let vs1 = VR::from_num(x(inst, 15, 5));
+        VMaskLogical { op, vd, vs2, vs1 }
+    }
+}
+impl Instruction for VMaskLogical {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "{:?}.mm {:?}, {:?}, {:?}",
+            self.op,
+            self.vd,
+            self.vs2,
+            self.vs1
+        );
+        vtype(state)?;
+        let vl = state.vl as usize;
+        for_each_element(state, true, vl, |state, i| {
+            let a = state.vregs.mask(self.vs2, i);
+            let b = state.vregs.mask(self.vs1, i);
+            let value = match self.op {
+                VMaskOp::vmandn => a && !b,
+                VMaskOp::vmand => a && b,
+                VMaskOp::vmor => a || b,
+                VMaskOp::vmxor => a != b,
+                VMaskOp::vmorn => a || !b,
+                VMaskOp::vmnand => !(a && b),
+                VMaskOp::vmnor => !(a || b),
+                VMaskOp::vmxnor => a == b,
+            };
+            state.set_vmask(self.vd, i, value);
+            Ok(())
+        })?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+/// Indices of the active elements below vl whose bit is set in `vs2`.
+fn set_bits(state: &State, vs2: VR, vm: bool) -> impl Iterator<Item = usize> + '_ {
+    (0..state.vl as usize).filter(move |&i| active(state, vm, i) && state.vregs.mask(vs2, i))
+}
+
+pub struct VCPOP_M {
+    rd: XprName,
+    vs2: VR,
+    vm: bool,
+}
+impl VCPOP_M {
+    pub fn new(inst: u32) -> Self {
+        let (_, vs2, vm) = rvv_arith_type(inst);
+        let rd = XprName::from_num(x(inst, 7, 5));
+        VCPOP_M { rd, vs2, vm }
+    }
+}
+impl Instruction for VCPOP_M {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "vcpop.m {:?}, {:?}", self.rd, self.vs2);
+        vtype(state)?;
+        check_vstart_zero(state)?;
+        let count = set_bits(state, self.vs2, self.vm).count();
+        state.set_reg(self.rd, count as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct VFIRST_M {
+    rd: XprName,
+    vs2: VR,
+    vm: bool,
+}
+impl VFIRST_M {
+    pub fn new(inst: u32) -> Self {
+        let (_, vs2, vm) = rvv_arith_type(inst);
+        let rd = XprName::from_num(x(inst, 7, 5));
+        VFIRST_M { rd, vs2, vm }
+    }
+}
+impl Instruction for VFIRST_M {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "vfirst.m {:?}, {:?}", self.rd, self.vs2);
+        vtype(state)?;
+        check_vstart_zero(state)?;
+        let first = set_bits(state, self.vs2, self.vm).next();
+        state.set_reg(self.rd, first.map_or(-1, |i| i as i64));
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VMaskSetOp {
+    vmsbf,
+    vmsif,
+    vmsof,
+}
+
+/// vmsbf.m, vmsif.m and vmsof.m: set bits before, up to and including, or only at the first
+/// set bit of vs2.
+pub struct VMaskSet {
+    op: VMaskSetOp,
+    vd: VR,
+    vs2: VR,
+    vm: bool,
+}
+impl VMaskSet {
+    pub fn new(inst: u32, op: VMaskSetOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        VMaskSet { op, vd, vs2, vm }
+    }
+}
+impl Instruction for VMaskSet {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "{:?}.m {:?}, {:?}", self.op, self.vd, self.vs2);
+        vtype(state)?;
+        check_vstart_zero(state)?;
+        if self.vd == self.vs2 {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        check_mask_overlap(self.vd, self.vm)?;
+        let mut found = false;
+        let vl = state.vl as usize;
+        for_each_element(state, self.vm, vl, |state, i| {
+            let bit = state.vregs.mask(self.vs2, i);
+            let value = match self.op {
+                VMaskSetOp::vmsbf => !found && !bit,
+                VMaskSetOp::vmsif => !found,
+                VMaskSetOp::vmsof => !found && bit,
+            };
+            found |= bit;
+            state.set_vmask(self.vd, i, value);
+            Ok(())
+        })?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+/// Writes each active element the number of set bits of vs2 in the active elements before it.
+pub struct VIOTA_M {
+    vd: VR,
+    vs2: VR,
+    vm: bool,
+}
+impl VIOTA_M {
+    pub fn new(inst: u32) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        VIOTA_M { vd, vs2, vm }
+    }
+}
+impl Instruction for VIOTA_M {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "viota.m {:?}, {:?}", self.vd, self.vs2);
+        let config = vtype(state)?;
+        let sew = config.sew;
+        check_group(self.vd, config.lmul8)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        check_vstart_zero(state)?;
+        let regs = self.vd as usize..self.vd as usize + group_size(config.lmul8);
+        if regs.contains(&(self.vs2 as usize)) {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        let mut count = 0;
+        let vl = state.vl as usize;
+        for_each_element(state, self.vm, vl, |state, i| {
+            let bit = state.vregs.mask(self.vs2, i);
+            state.set_vreg(self.vd, i, sew, count);
+            count += bit as u64;
+            Ok(())
+        })?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct VID_V {
+    vd: VR,
+    vm: bool,
+}
+impl VID_V {
+    pub fn new(inst: u32) -> Self {
+        let (vd, _, vm) = rvv_arith_type(inst);
+        VID_V { vd, vm }
+    }
+}
+impl Instruction for VID_V {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "vid.v {:?}", self.vd);
+        let config = vtype(state)?;
+        check_group(self.vd, config.lmul8)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        let vl = state.vl as usize;
+        for_each_element(state, self.vm, vl, |state, i| {
+            state.set_vreg(self.vd, i, config.sew, i as u64);
+            Ok(())
+        })?;
+        state.pc += 4;
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+use super::{
+    rvv::{
+        check_group, check_mask_overlap, check_vstart_zero, for_each_element, group_size,
+        rvv_arith_type, sext, vtype, VSource,
+    },
+    rvv_float::float_format,
+    Instruction,
+};
+use crate::{
+    processor::{State, XprName, VR},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+/// Copies element 0 to an integer register, sign-extending it; runs even when vl is 0.
+pub struct VMV_X_S {
+    rd: XprName,
+    vs2: VR,
+}
+impl VMV_X_S {
+    pub fn new(inst: u32) -> Self {
+        let (_, vs2, _) = rvv_arith_type(inst);
+        let rd = XprName::from_num(x(inst, 7, 5));
+        VMV_X_S { rd, vs2 }
+    }
+}
+impl Instruction for VMV_X_S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "vmv.x.s {:?}, {:?}", self.rd, self.vs2);
+        let sew = vtype(state)?.sew;
+        let value = state.vregs.get(self.vs2, 0, sew);
+        state.set_reg(self.rd, sext(value, sew));
+        state.vstart = 0;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct VMV_S_X {
+    vd: VR,
+    rs1: XprName,
+}
+impl VMV_S_X {
+    pub fn new(inst: u32) -> Self {
+        let (vd, _, _) = rvv_arith_type(inst);
+        let rs1 = XprName::from_num(x(inst, 15, 5));
+        VMV_S_X { vd, rs1 }
+    }
+}
+impl Instruction for VMV_S_X {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "vmv.s.x {:?}, {:?}", self.vd, self.rs1);
+        let sew = vtype(state)?.sew;
+        if state.vstart < state.vl {
+            let value = state.get_reg(self.rs1) as u64;
+            state.set_vreg(self.vd, 0, sew, value);
+        }
+        state.vstart = 0;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VSlideOp {
+    vslideup,
+    vslidedown,
+    vslide1up,
+    vslide1down,
+}
+
+/// Slides by a scalar or immediate offset, or by one element with a scalar shifted in.
+pub struct VSlide {
+    op: VSlideOp,
+    vd: VR,
+    vs2: VR,
+    src: VSource,
+    vm: bool,
+}
+impl VSlide {
+    pub fn new(inst: u32, op: VSlideOp) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let src = VSource::new_unsigned(inst);
+        VSlide {
+            op,
+            vd,
+            vs2,
+            src,
+            vm,
+        }
+    }
+}
+impl Instruction for VSlide {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        let prefix = if let VSource::Float(_) = self.src {
+            "vf"
+        } else {
+            "v"
+        };
+        let op = format!("{:?}", self.op);
+        trace!(
+            state,
+            "{}{}.{} {:?}, {:?}, {}",
+            prefix,
+            &op[1..],
+            self.src.suffix(),
+            self.vd,
+            self.vs2,
+            self.src
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        if let VSource::Float(_) = self.src {
+            float_format(state, sew)?;
+        }
+        check_group(self.vd, config.lmul8)?;
+        check_group(self.vs2, config.lmul8)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        // Sliding up reads elements below the one being written, so vd may not be vs2.
+        let up = matches!(self.op, VSlideOp::vslideup | VSlideOp::vslide1up);
+        if up && self.vd == self.vs2 {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        let vlmax = config.vlmax(state) as u64;
+        let vl = state.vl as usize;
+        let offset = self.src.offset(state);
+        let scalar = self.src.element(state, 0, sew);
+        for_each_element(state, self.vm, vl, |state, i| {
+            let value = match self.op {
+                VSlideOp::vslideup if (i as u64) < offset => return Ok(()),
+                VSlideOp::vslideup => state.vregs.get(self.vs2, i - offset as usize, sew),
+                VSlideOp::vslidedown => match (i as u64).checked_add(offset) {
+                    Some(j) if j < vlmax => state.vregs.get(self.vs2, j as usize, sew),
+                    _ => 0,
+                },
+                VSlideOp::vslide1up if i == 0 => scalar,
+                VSlideOp::vslide1up => state.vregs.get(self.vs2, i - 1, sew),
+                VSlideOp::vslide1down if i + 1 == vl => scalar,
+                VSlideOp::vslide1down => state.vregs.get(self.vs2, i + 1, sew),
+            };
+            state.set_vreg(self.vd, i, sew, value);
+            Ok(())
+        })?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+/// vrgather and vrgatherei16: `vd[i] = vs2[index]`, or 0 when the index is past VLMAX.
+pub struct VGather {
+    vd: VR,
+    vs2: VR,
+    src: VSource,
+    vm: bool,
+    /// vrgatherei16 reads 16-bit indices whatever SEW is.
+    ei16: bool,
+}
+impl VGather {
+    pub fn new(inst: u32, ei16: bool) -> Self {
+        let (vd, vs2, vm) = rvv_arith_type(inst);
+        let src = VSource::new_unsigned(inst);
+        VGather {
+            vd,
+            vs2,
+            src,
+            vm,
+            ei16,
+        }
+    }
+}
+impl Instruction for VGather {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        let op = if self.ei16 {
+            "vrgatherei16"
+        } else {
+            "vrgather"
+        };
+        trace!(
+            state,
+            "{}.{} {:?}, {:?}, {}",
+            op,
+            self.src.suffix(),
+            self.vd,
+            self.vs2,
+            self.src
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        let index_eew = if self.ei16 { 16 } else { sew };
+        check_group(self.vd, config.lmul8)?;
+        check_group(self.vs2, config.lmul8)?;
+        self.src.check_group(config.lmul8 * index_eew / sew)?;
+        check_mask_overlap(self.vd, self.vm)?;
+        let overlaps_source = match self.src {
+            VSource::Vector(vs1) => self.vd == vs1,
+            _ => false,
+        };
+        if self.vd == self.vs2 || overlaps_source {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        let vlmax = config.vlmax(state) as u64;
+        let vl = state.vl as usize;
+        for_each_element(state, self.vm, vl, |state, i| {
+            let index = match self.src {
+                VSource::Vector(_) => self.src.element(state, i, index_eew),
+                _ => self.src.offset(state),
+            };
+            let value = if index < vlmax {
+                state.vregs.get(self.vs2, index as usize, sew)
+            } else {
+                0
+            };
+            state.set_vreg(self.vd, i, sew, value);
+            Ok(())
+        })?;
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+/// Packs the elements of vs2 selected by the mask in vs1 into the start of vd.
+pub struct VCOMPRESS_VM {
+    vd: VR,
+    vs2: VR,
+    vs1: VR,
+}
+impl VCOMPRESS_VM {
+    pub fn new(inst: u32) -> Self {
+        let (vd, vs2, _) = rvv_arith_type(inst);
+        let vs1 = VR::from_num(x(inst, 15, 5));
+        VCOMPRESS_VM { vd, vs2, vs1 }
+    }
+}
+impl Instruction for VCOMPRESS_VM {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "vcompress.vm {:?}, {:?}, {:?}",
+            self.vd,
+            self.vs2,
+            self.vs1
+        );
+        let config = vtype(state)?;
+        let sew = config.sew;
+        check_group(self.vd, config.lmul8)?;
+        check_group(self.vs2, config.lmul8)?;
+        check_vstart_zero(state)?;
+        let regs = self.vd as usize..self.vd as usize + group_size(config.lmul8);
+        if self.vd == self.vs2 || regs.contains(&(self.vs1 as usize)) {
+            return Err(Exception::IllegalInstruction(0));
+        }
+        let mut packed = 0;
+        for i in 0..state.vl as usize {
+            if state.vregs.mask(self.vs1, i) {
+                let value = state.vregs.get(self.vs2, i, sew);
+                state.set_vreg(self.vd, packed, sew, value);
+                packed += 1;
+            }
+        }
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+/// vmv1r.v through vmv8r.v: copies whole registers, regardless of vl.
+pub struct VMV_NR_R {
+    vd: VR,
+    vs2: VR,
+    nr: u32,
+}
+impl VMV_NR_R {
+    pub fn new(inst: u32) -> Self {
+        let (vd, vs2, _) = rvv_arith_type(inst);
+        let nr = x(inst, 15, 3) as u32 + 1;
+        VMV_NR_R { vd, vs2, nr }
+    }
+}
+impl Instruction for VMV_NR_R {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "vmv{}r.v {:?}, {:?}", self.nr, self.vd, self.vs2);
+        check_group(self.vd, self.nr * 8)?;
+        check_group(self.vs2, self.nr * 8)?;
+        // Elements are SEW wide, which only matters for where a nonzero vstart resumes.
+        let eew = vtype(state).map_or(8, |config| config.sew);
+        let evl = self.nr as usize * state.vregs.vlenb() * 8 / eew as usize;
+        for_each_element(state, true, evl, |state, i| {
+            let value = state.vregs.get(self.vs2, i, eew);
+            state.set_vreg(self.vd, i, eew, value);
+            Ok(())
+        })?;
+        state.pc += 4;
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+use super::{
+    rvc::{rvc_cb_type, rvc_cl_type, rvc_cs_type},
+    Instruction,
+};
+use crate::{
+    processor::{State, XprName},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+/// Byte offset of c.lbu and c.sb: `uimm[0]` in bit 6 and `uimm[1]` in bit 5.
+#[inline]
+fn zcb_byte_offset(inst: u16) -> i64 {
+    x(inst, 6, 1) + (x(inst, 5, 1) << 1)
+}
+/// Halfword offset of c.lhu, c.lh and c.sh: `uimm[1]` in bit 5.
+#[inline]
+fn zcb_half_offset(inst: u16) -> i64 {
+    x(inst, 5, 1) << 1
+}
+
+pub struct C_LBU {
+    rd: XprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl C_LBU {
+    pub fn new(inst: u16) -> Self {
+        let (rd, rs1) = rvc_cl_type(inst);
+        let offset = zcb_byte_offset(inst);
+        C_LBU { rd, rs1, offset }
+    }
+}
+impl Instruction for C_LBU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lbu {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access(addr)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_LHU {
+    rd: XprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl C_LHU {
+    pub fn new(inst: u16) -> Self {
+        let (rd, rs1) = rvc_cl_type(inst);
+        let offset = zcb_half_offset(inst);
+        C_LHU { rd, rs1, offset }
+    }
+}
+impl Instruction for C_LHU {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lhu {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access_u16(addr)?;
+        state.set_reg(self.rd, mem as i64);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_LH {
+    rd: XprName,
+    rs1: XprName,
+    offset: i64,
+}
+impl C_LH {
+    pub fn new(inst: u16) -> Self {
+        let (rd, rs1) = rvc_cl_type(inst);
+        let offset = zcb_half_offset(inst);
+        C_LH { rd, rs1, offset }
+    }
+}
+impl Instruction for C_LH {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "lh {:?}, {}({:?})", self.rd, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let mem = state.access_u16(addr)?;
+        state.set_reg(self.rd, mem as i16 as i64);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_SB {
+    rs1: XprName,
+    rs2: XprName,
+    offset: i64,
+}
+impl C_SB {
+    pub fn new(inst: u16) -> Self {
+        let (rs2, rs1) = rvc_cs_type(inst);
+        let offset = zcb_byte_offset(inst);
+        C_SB { rs1, rs2, offset }
+    }
+}
+impl Instruction for C_SB {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sb {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u8(addr, rs2 as u8)?;
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_SH {
+    rs1: XprName,
+    rs2: XprName,
+    offset: i64,
+}
+impl C_SH {
+    pub fn new(inst: u16) -> Self {
+        let (rs2, rs1) = rvc_cs_type(inst);
+        let offset = zcb_half_offset(inst);
+        C_SH { rs1, rs2, offset }
+    }
+}
+impl Instruction for C_SH {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sh {:?}, {}({:?})", self.rs2, self.offset, self.rs1);
+        let addr = state.get_reg(self.rs1).wrapping_add(self.offset);
+        let rs2 = state.get_reg(self.rs2);
+        state.store_u16(addr, rs2 as u16)?;
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_ZEXT_B {
+    rd: XprName,
+}
+impl C_ZEXT_B {
+    pub fn new(inst: u16) -> Self {
+        let rd = rvc_cb_type(inst);
+        C_ZEXT_B { rd }
+    }
+}
+impl Instruction for C_ZEXT_B {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "zext.b {:?}, {:?}", self.rd, self.rd);
+        state.set_reg(self.rd, state.get_reg(self.rd) as u8 as i64);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_SEXT_B {
+    rd: XprName,
+}
+impl C_SEXT_B {
+    pub fn new(inst: u16) -> Self {
+        let rd = rvc_cb_type(inst);
+        C_SEXT_B { rd }
+    }
+}
+impl Instruction for C_SEXT_B {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sext.b {:?}, {:?}", self.rd, self.rd);
+        state.set_reg(self.rd, state.get_reg(self.rd) as i8 as i64);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_ZEXT_H {
+    rd: XprName,
+}
+impl C_ZEXT_H {
+    pub fn new(inst: u16) -> Self {
+        let rd = rvc_cb_type(inst);
+        C_ZEXT_H { rd }
+    }
+}
+impl Instruction for C_ZEXT_H {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "zext.h {:?}, {:?}", self.rd, self.rd);
+        state.set_reg(self.rd, state.get_reg(self.rd) as u16 as i64);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_SEXT_H {
+    rd: XprName,
+}
+impl C_SEXT_H {
+    pub fn new(inst: u16) -> Self {
+        let rd = rvc_cb_type(inst);
+        C_SEXT_H { rd }
+    }
+}
+impl Instruction for C_SEXT_H {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "sext.h {:?}, {:?}", self.rd, self.rd);
+        state.set_reg(self.rd, state.get_reg(self.rd) as i16 as i64);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_ZEXT_W {
+    rd: XprName,
+}
+impl C_ZEXT_W {
+    pub fn new(inst: u16) -> Self {
+        let rd = rvc_cb_type(inst);
+        C_ZEXT_W { rd }
+    }
+}
+impl Instruction for C_ZEXT_W {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "zext.w {:?}, {:?}", self.rd, self.rd);
+        state.set_reg(self.rd, state.get_reg(self.rd) as u32 as i64);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_NOT {
+    rd: XprName,
+}
+impl C_NOT {
+    pub fn new(inst: u16) -> Self {
+        let rd = rvc_cb_type(inst);
+        C_NOT { rd }
+    }
+}
+impl Instruction for C_NOT {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "not {:?}, {:?}", self.rd, self.rd);
+        state.set_reg(self.rd, !state.get_reg(self.rd));
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct C_MUL {
+    rd: XprName,
+    rs2: XprName,
+}
+impl C_MUL {
+    pub fn new(inst: u16) -> Self {
+        let (rs2, rd) = rvc_cs_type(inst);
+        C_MUL { rd, rs2 }
+    }
+}
+impl Instruction for C_MUL {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "mul {:?}, {:?}, {:?}", self.rd, self.rd, self.rs2);
+        let rd = state.get_reg(self.rd);
+        let rs2 = state.get_reg(self.rs2);
+        state.set_reg(self.rd, rd.wrapping_mul(rs2));
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        machine::tests::{machine_with, BASE},
+        processor::XprName,
+    };
+
+    const CODE: [u32; 5] = [
+        0x0000_1517, // auipc a0, 1
+        0x1ff0_0593, // addi a1, zero, 0x1ff
+        0x8130_8d2c, // c.sh a1, 2(a0); c.lbu a2, 2(a0)
+        0x9e4d_8174, // c.lbu a3, 3(a0); c.mul a2, a1
+        0x0001_9ef5, // c.not a3; c.nop
+    ];
+
+    #[test]
+    fn zcb_loads_stores_and_arithmetic() {
+        let mut machine = machine_with("rv64imac_zcb", &CODE);
+        machine.run(7);
+        assert_eq!(machine.state.memory.read_u16(BASE + 0x1002), Ok(0x1ff));
+        assert_eq!(machine.reg(XprName::a2), 0xff * 0x1ff);
+        assert_eq!(machine.reg(XprName::a3), !1);
+        assert_eq!(machine.pc(), BASE + 18);
+    }
+
+    #[test]
+    fn zcb_encodings_are_reserved_without_it() {
+        let mut machine = machine_with("rv64imac", &CODE);
+        machine.run(3);
+        assert_eq!(machine.state.mcause, 2);
+        assert_eq!(machine.state.mepc, (BASE + 8) as i64);
+        assert_eq!(machine.state.mtval, 0x8d2c);
+    }
+}
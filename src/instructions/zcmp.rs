@@ -0,0 +1,308 @@
+use super::Instruction;
+use crate::{
+    processor::{State, XprName, XLEN},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+use XprName::*;
+
+const SAVED_REGS: [XprName; 12] = [s0, s1, s2, s3, s4, s5, s6, s7, s8, s9, s10, s11];
+
+/// Registers named by `rlist`, from `ra` upwards; values below 4 are reserved and rejected
+/// by the decoder. `{ra, s0-s10}` cannot be encoded, so 15 also takes s11.
+#[inline]
+fn zcmp_rlist(inst: u16) -> Vec<XprName> {
+    let saved = match x(inst, 4, 4) {
+        15 => 12,
+        rlist => rlist as usize - 4,
+    };
+    let mut regs = vec![ra];
+    regs.extend(&SAVED_REGS[..saved]);
+    regs
+}
+/// The `sreg` encoding of cm.mvsa01 and cm.mva01s: s0-s1, then s2-s7.
+#[inline]
+fn zcmp_sreg(value: i64) -> XprName {
+    match value {
+        0 | 1 => XprName::from_num(8 + value),
+        _ => XprName::from_num(16 + value),
+    }
+}
+
+/// The register list of a push or pop together with its stack adjustment, which covers the
+/// registers rounded up to 16 bytes plus `spimm` more 16-byte blocks.
+struct StackFrame {
+    regs: Vec<XprName>,
+    spimm: i64,
+}
+impl StackFrame {
+    fn new(inst: u16) -> Self {
+        StackFrame {
+            regs: zcmp_rlist(inst),
+            spimm: x(inst, 2, 2) * 16,
+        }
+    }
+    fn word(state: &State) -> i64 {
+        state.xlen_bits() as i64 / 8
+    }
+    fn stack_adj(&self, state: &State) -> i64 {
+        let bytes = self.regs.len() as i64 * Self::word(state);
+        ((bytes + 15) & !15) + self.spimm
+    }
+    /// Stores the registers just below `sp`, the highest-numbered one at the top.
+    fn push(&self, state: &mut State) -> Result<(), Exception> {
+        let word = Self::word(state);
+        let mut addr = state.get_reg(sp);
+        for &reg in self.regs.iter().rev() {
+            addr = addr.wrapping_sub(word);
+            let value = state.get_reg(reg);
+            match state.xlen {
+                XLEN::RV32 => state.store_u32(addr, value as u32)?,
+                XLEN::RV64 | XLEN::RV128 => state.store_u64(addr, value as u64)?,
+            }
+        }
+        let stack_adj = self.stack_adj(state);
+        state.set_reg(sp, state.get_reg(sp).wrapping_sub(stack_adj));
+        Ok(())
+    }
+    /// Reloads what `push` saved and releases the frame.
+    fn pop(&self, state: &mut State) -> Result<(), Exception> {
+        let word = Self::word(state);
+        let stack_adj = self.stack_adj(state);
+        let top = state.get_reg(sp).wrapping_add(stack_adj);
+        // Load everything first so that a fault leaves the registers and sp untouched.
+        let mut values = vec![];
+        let mut addr = top;
+        for _ in &self.regs {
+            addr = addr.wrapping_sub(word);
+            values.push(match state.xlen {
+                XLEN::RV32 => state.access_u32(addr)? as i32 as i64,
+                XLEN::RV64 | XLEN::RV128 => state.access_u64(addr)? as i64,
+            });
+        }
+        for (&reg, value) in self.regs.iter().rev().zip(values) {
+            state.set_reg(reg, value);
+        }
+        state.set_reg(sp, top);
+        Ok(())
+    }
+}
+
+pub struct CM_PUSH {
+    frame: StackFrame,
+}
+impl CM_PUSH {
+    pub fn new(inst: u16) -> Self {
+        CM_PUSH {
+            frame: StackFrame::new(inst),
+        }
+    }
+}
+impl Instruction for CM_PUSH {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "cm.push {:?}, -{}",
+            self.frame.regs,
+            self.frame.stack_adj(state)
+        );
+        self.frame.push(state)?;
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct CM_POP {
+    frame: StackFrame,
+}
+impl CM_POP {
+    pub fn new(inst: u16) -> Self {
+        CM_POP {
+            frame: StackFrame::new(inst),
+        }
+    }
+}
+impl Instruction for CM_POP {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "cm.pop {:?}, {}",
+            self.frame.regs,
+            self.frame.stack_adj(state)
+        );
+        self.frame.pop(state)?;
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct CM_POPRETZ {
+    frame: StackFrame,
+}
+impl CM_POPRETZ {
+    pub fn new(inst: u16) -> Self {
+        CM_POPRETZ {
+            frame: StackFrame::new(inst),
+        }
+    }
+}
+impl Instruction for CM_POPRETZ {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "cm.popretz {:?}, {}",
+            self.frame.regs,
+            self.frame.stack_adj(state)
+        );
+        self.frame.pop(state)?;
+        state.set_reg(a0, 0);
+        state.jump(state.get_reg(ra) & !1)
+    }
+}
+
+pub struct CM_POPRET {
+    frame: StackFrame,
+}
+impl CM_POPRET {
+    pub fn new(inst: u16) -> Self {
+        CM_POPRET {
+            frame: StackFrame::new(inst),
+        }
+    }
+}
+impl Instruction for CM_POPRET {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(
+            state,
+            "cm.popret {:?}, {}",
+            self.frame.regs,
+            self.frame.stack_adj(state)
+        );
+        self.frame.pop(state)?;
+        state.jump(state.get_reg(ra) & !1)
+    }
+}
+
+pub struct CM_MVSA01 {
+    r1s: XprName,
+    r2s: XprName,
+}
+impl CM_MVSA01 {
+    pub fn new(inst: u16) -> Self {
+        let r1s = zcmp_sreg(x(inst, 7, 3));
+        let r2s = zcmp_sreg(x(inst, 2, 3));
+        CM_MVSA01 { r1s, r2s }
+    }
+}
+impl Instruction for CM_MVSA01 {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "cm.mvsa01 {:?}, {:?}", self.r1s, self.r2s);
+        let (a0_value, a1_value) = (state.get_reg(a0), state.get_reg(a1));
+        state.set_reg(self.r1s, a0_value);
+        state.set_reg(self.r2s, a1_value);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+pub struct CM_MVA01S {
+    r1s: XprName,
+    r2s: XprName,
+}
+impl CM_MVA01S {
+    pub fn new(inst: u16) -> Self {
+        let r1s = zcmp_sreg(x(inst, 7, 3));
+        let r2s = zcmp_sreg(x(inst, 2, 3));
+        CM_MVA01S { r1s, r2s }
+    }
+}
+impl Instruction for CM_MVA01S {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "cm.mva01s {:?}, {:?}", self.r1s, self.r2s);
+        let (r1s_value, r2s_value) = (state.get_reg(self.r1s), state.get_reg(self.r2s));
+        state.set_reg(a0, r1s_value);
+        state.set_reg(a1, r2s_value);
+        state.pc += 2;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::tests::{machine_with, BASE};
+
+    const CODE: [u32; 4] = [
+        0xbaf6_b8f6, // cm.push {ra, s0-s11}, -(adj + 16); cm.pop {ra, s0-s11}, adj + 16
+        0xbc52_b852, // cm.push {ra, s0}, -16; cm.popretz {ra, s0}, 16
+        0xbe52_b852, // cm.push {ra, s0}, -16; cm.popret {ra, s0}, 16
+        0xace2_ac26, // cm.mvsa01 s0, s1; cm.mva01s s1, s0
+    ];
+
+    #[test]
+    fn push_and_pop_lay_out_the_frame_per_xlen() {
+        // Thirteen registers take 52 or 104 bytes, rounded up to 16.
+        for (isa, word, stack_adj) in [("rv32imac_zcmp", 4, 64), ("rv64imac_zcmp", 8, 112)] {
+            let mut machine = machine_with(isa, &CODE);
+            let top = BASE + 0x1000;
+            machine.set_reg(sp, top as i64);
+            let regs: Vec<XprName> = [ra].into_iter().chain(SAVED_REGS).collect();
+            for (i, &reg) in regs.iter().enumerate() {
+                machine.set_reg(reg, 0x100 + i as i64);
+            }
+
+            machine.step();
+            assert_eq!(machine.state.get_reg_u(sp), top - stack_adj - 16);
+            // ra lowest, s11 just below the old sp.
+            for (i, &reg) in regs.iter().enumerate() {
+                let addr = top - word * (regs.len() - i) as u64;
+                let mut value = [0; 8];
+                machine
+                    .read_memory(addr, &mut value[..word as usize])
+                    .unwrap();
+                assert_eq!(u64::from_le_bytes(value), 0x100 + i as u64, "{:?}", reg);
+                machine.set_reg(reg, 0);
+            }
+
+            machine.step();
+            assert_eq!(machine.state.get_reg_u(sp), top);
+            for (i, &reg) in regs.iter().enumerate() {
+                assert_eq!(machine.reg(reg), 0x100 + i as i64);
+            }
+        }
+    }
+
+    #[test]
+    fn popret_returns_and_popretz_clears_a0() {
+        let mut machine = machine_with("rv64imac_zcmp", &CODE);
+        machine.set_pc(BASE + 4);
+        machine.set_reg(sp, (BASE + 0x1000) as i64);
+        machine.set_reg(ra, (BASE + 8) as i64);
+        machine.set_reg(a0, 5);
+        machine.run(2);
+        assert_eq!(machine.pc(), BASE + 8);
+        assert_eq!(machine.reg(a0), 0);
+
+        machine.set_reg(ra, (BASE + 12) as i64);
+        machine.set_reg(a0, 5);
+        machine.run(2);
+        assert_eq!(machine.pc(), BASE + 12);
+        assert_eq!(machine.reg(a0), 5);
+        assert_eq!(machine.state.get_reg_u(sp), BASE + 0x1000);
+    }
+
+    #[test]
+    fn moves_between_a0_a1_and_saved_registers() {
+        let mut machine = machine_with("rv64imac_zcmp", &CODE);
+        machine.set_pc(BASE + 12);
+        machine.set_reg(a0, 1);
+        machine.set_reg(a1, 2);
+        machine.step();
+        assert_eq!((machine.reg(s0), machine.reg(s1)), (1, 2));
+        machine.set_reg(a0, 0);
+        machine.set_reg(a1, 0);
+        machine.step();
+        assert_eq!((machine.reg(a0), machine.reg(a1)), (2, 1));
+    }
+}
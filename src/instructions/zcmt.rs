@@ -0,0 +1,83 @@
+use super::Instruction;
+use crate::{
+    processor::{State, XprName},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+/// Loads entry `index` of the jump table at `jvt.base`. Table reads are instruction fetches.
+fn jump_table_target(state: &mut State, index: i64) -> Result<i64, Exception> {
+    let word = state.xlen_bits() as i64 / 8;
+    let addr = (state.jvt & !0x3f).wrapping_add(index * word);
+    let target = state.fetch_xlen(addr)?;
+    Ok(state.sext_xlen(target as i64) & !1)
+}
+
+pub struct CM_JT {
+    index: i64,
+}
+impl CM_JT {
+    pub fn new(inst: u16) -> Self {
+        let index = x(inst, 2, 8);
+        CM_JT { index }
+    }
+}
+impl Instruction for CM_JT {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "cm.jt {}", self.index);
+        let target = jump_table_target(state, self.index)?;
+        state.jump(target)
+    }
+}
+
+pub struct CM_JALT {
+    index: i64,
+}
+impl CM_JALT {
+    pub fn new(inst: u16) -> Self {
+        let index = x(inst, 2, 8);
+        CM_JALT { index }
+    }
+}
+impl Instruction for CM_JALT {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "cm.jalt {}", self.index);
+        let target = jump_table_target(state, self.index)?;
+        let link = state.pc + 2;
+        state.jump(target)?;
+        state.set_reg(XprName::ra, link);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        machine::tests::{machine_with, BASE},
+        processor::XprName,
+    };
+
+    #[test]
+    fn jumps_go_through_the_table_at_jvt() {
+        for (isa, word) in [("rv32imac_zcmt", 4), ("rv64imac_zcmt", 8)] {
+            let mut code = [0; 0x21];
+            code[0] = 0x0001_a006; // cm.jt 1; c.nop
+            code[0x10] = 0x0001_a082; // cm.jalt 32; c.nop
+            let mut machine = machine_with(isa, &code);
+            let table = BASE + 0x1000;
+            machine.state.jvt = table as i64;
+            for (index, target) in [(1, BASE + 0x40), (32, BASE + 0x80)] {
+                let entry = &(target | 1).to_le_bytes()[..word];
+                machine
+                    .write_memory(table + index * word as u64, entry)
+                    .unwrap();
+            }
+
+            machine.step();
+            assert_eq!(machine.pc(), BASE + 0x40);
+            machine.step();
+            assert_eq!(machine.pc(), BASE + 0x80);
+            assert_eq!(machine.state.get_reg_u(XprName::ra), BASE + 0x42);
+        }
+    }
+}
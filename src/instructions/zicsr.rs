@@ -0,0 +1,172 @@
+use super::Instruction;
+use crate::{
+    csr::CsrName,
+    processor::{State, XprName},
+    trap::Exception,
+    utils::{trace, x},
+};
+
+pub fn zicsr_type(inst: u32) -> (XprName, i64, CsrName) {
+    let rd = x(inst, 7, 5);
+    let rs1 = x(inst, 15, 5);
+    let csr = x(inst, 20, 12);
+    (
+        XprName::from_num(rd),
+        rs1,
+        CsrName::from_num(csr).expect("unknown CSRs are rejected by the decoder"),
+    )
+}
+
+pub struct CSRRW {
+    rd: XprName,
+    rs1: XprName,
+    csr: CsrName,
+}
+impl CSRRW {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, csr) = zicsr_type(inst);
+        let rs1 = XprName::from_num(rs1);
+        CSRRW { rd, rs1, csr }
+    }
+}
+impl Instruction for CSRRW {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "csrrw {:?}, {:?}, {:?}", self.rd, self.csr, self.rs1);
+        let rs1 = state.get_reg_u(self.rs1);
+        // csrrw with rd = x0 must not read the CSR at all.
+        if self.rd != XprName::zero {
+            let old = state.csr_read(self.csr);
+            state.set_reg(self.rd, old as i64);
+        }
+        state.csr_write(self.csr, rs1);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CSRRS {
+    rd: XprName,
+    rs1: XprName,
+    csr: CsrName,
+}
+impl CSRRS {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, csr) = zicsr_type(inst);
+        let rs1 = XprName::from_num(rs1);
+        CSRRS { rd, rs1, csr }
+    }
+}
+impl Instruction for CSRRS {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "csrrs {:?}, {:?}, {:?}", self.rd, self.csr, self.rs1);
+        let rs1 = state.get_reg_u(self.rs1);
+        let old = state.csr_read(self.csr);
+        if self.rs1 != XprName::zero {
+            state.csr_write(self.csr, old | rs1);
+        }
+        state.set_reg(self.rd, old as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CSRRC {
+    rd: XprName,
+    rs1: XprName,
+    csr: CsrName,
+}
+impl CSRRC {
+    pub fn new(inst: u32) -> Self {
+        let (rd, rs1, csr) = zicsr_type(inst);
+        let rs1 = XprName::from_num(rs1);
+        CSRRC { rd, rs1, csr }
+    }
+}
+impl Instruction for CSRRC {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "csrrc {:?}, {:?}, {:?}", self.rd, self.csr, self.rs1);
+        let rs1 = state.get_reg_u(self.rs1);
+        let old = state.csr_read(self.csr);
+        if self.rs1 != XprName::zero {
+            state.csr_write(self.csr, old & !rs1);
+        }
+        state.set_reg(self.rd, old as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CSRRWI {
+    rd: XprName,
+    uimm: u64,
+    csr: CsrName,
+}
+impl CSRRWI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, uimm, csr) = zicsr_type(inst);
+        let uimm = uimm as u64;
+        CSRRWI { rd, uimm, csr }
+    }
+}
+impl Instruction for CSRRWI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "csrrwi {:?}, {:?}, {}", self.rd, self.csr, self.uimm);
+        if self.rd != XprName::zero {
+            let old = state.csr_read(self.csr);
+            state.set_reg(self.rd, old as i64);
+        }
+        state.csr_write(self.csr, self.uimm);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CSRRSI {
+    rd: XprName,
+    uimm: u64,
+    csr: CsrName,
+}
+impl CSRRSI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, uimm, csr) = zicsr_type(inst);
+        let uimm = uimm as u64;
+        CSRRSI { rd, uimm, csr }
+    }
+}
+impl Instruction for CSRRSI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "csrrsi {:?}, {:?}, {}", self.rd, self.csr, self.uimm);
+        let old = state.csr_read(self.csr);
+        if self.uimm != 0 {
+            state.csr_write(self.csr, old | self.uimm);
+        }
+        state.set_reg(self.rd, old as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
+
+pub struct CSRRCI {
+    rd: XprName,
+    uimm: u64,
+    csr: CsrName,
+}
+impl CSRRCI {
+    pub fn new(inst: u32) -> Self {
+        let (rd, uimm, csr) = zicsr_type(inst);
+        let uimm = uimm as u64;
+        CSRRCI { rd, uimm, csr }
+    }
+}
+impl Instruction for CSRRCI {
+    fn execute(&self, state: &mut State) -> Result<(), Exception> {
+        trace!(state, "csrrci {:?}, {:?}, {}", self.rd, self.csr, self.uimm);
+        let old = state.csr_read(self.csr);
+        if self.uimm != 0 {
+            state.csr_write(self.csr, old & !self.uimm);
+        }
+        state.set_reg(self.rd, old as i64);
+        state.pc += 4;
+        Ok(())
+    }
+}
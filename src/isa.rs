@@ -0,0 +1,205 @@
+use std::str::FromStr;
+
+use crate::{csr::misa_extensions, error::SimError, processor::XLEN};
+
+/// Single-letter extensions that can be enabled, in canonical ISA-string order.
+const SINGLE_LETTER_EXTENSIONS: &str = "imafdcv";
+/// Multi-letter extensions accepted after the single-letter ones, besides `zvl<N>b`.
+const MULTI_LETTER_EXTENSIONS: &[&str] = &[
+    "zicsr", "zifencei", "zcb", "zcmp", "zcmt", "zba", "zbb", "zbc", "zbs", "zbkb", "zbkc", "zbkx",
+    "zknd", "zkne", "zknh", "zksed", "zksh", "zkr", "zkt", "zve32x", "zve32f", "zve64x", "zve64f",
+    "zve64d",
+];
+/// Shorthands for groups of the scalar cryptography extensions. Zkt only promises
+/// data-independent timing, which a simulator has anyway.
+const SHORTHANDS: &[(&str, &[&str])] = &[
+    ("zkn", &["zbkb", "zbkc", "zbkx", "zkne", "zknd", "zknh"]),
+    ("zks", &["zbkb", "zbkc", "zbkx", "zksed", "zksh"]),
+    (
+        "zk",
+        &["zbkb", "zbkc", "zbkx", "zkne", "zknd", "zknh", "zkr", "zkt"],
+    ),
+];
+
+/// The minimum VLEN named by a `zvl<N>b` extension.
+fn zvl_bits(extension: &str) -> Option<u32> {
+    extension
+        .strip_prefix("zvl")?
+        .strip_suffix('b')?
+        .parse()
+        .ok()
+        .filter(|bits: &u32| bits.is_power_of_two() && (32..=65536).contains(bits))
+}
+
+/// A parsed ISA string such as `rv64imafdc` or `rv32gc_zicsr`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Isa {
+    pub xlen: XLEN,
+    /// Bits of `misa` for the single-letter extensions, plus S and U which are always present.
+    pub misa: u64,
+    /// Multi-letter extensions, lowercased.
+    pub extensions: Vec<String>,
+    /// VLEN and ELEN in bits, both 0 when no vector extension is enabled.
+    pub vlen: u32,
+    pub elen: u32,
+}
+
+impl Default for Isa {
+    fn default() -> Self {
+        "rv64imafdc".parse().unwrap()
+    }
+}
+
+impl FromStr for Isa {
+    type Err = SimError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        let (xlen, rest) = if let Some(rest) = s.strip_prefix("rv32") {
+            (XLEN::RV32, rest)
+        } else if let Some(rest) = s.strip_prefix("rv64") {
+            (XLEN::RV64, rest)
+        } else {
+            return Err(SimError::ParseError(format!(
+                "ISA string {:?} must start with rv32 or rv64",
+                s
+            )));
+        };
+
+        let mut parts = rest.split('_');
+        let letters = parts.next().unwrap_or_default();
+        if letters.is_empty() {
+            return Err(SimError::ParseError(format!(
+                "ISA string {:?} must name a base ISA (i, e or g)",
+                s
+            )));
+        }
+        let mut extensions: Vec<String> = vec![];
+        let mut single = String::new();
+        for (i, letter) in letters.char_indices() {
+            match letter {
+                'g' if i == 0 => {
+                    single.push_str("imafd");
+                    extensions.extend(["zicsr".to_string(), "zifencei".to_string()]);
+                }
+                'e' if i == 0 => {
+                    return Err(SimError::ParseError(
+                        "the E base ISA is not supported".into(),
+                    ))
+                }
+                _ if i == 0 && letter != 'i' => {
+                    return Err(SimError::ParseError(format!(
+                        "ISA string {:?} must name a base ISA (i, e or g)",
+                        s
+                    )))
+                }
+                _ if SINGLE_LETTER_EXTENSIONS.contains(letter) => single.push(letter),
+                _ => {
+                    return Err(SimError::ParseError(format!(
+                        "unsupported extension {:?}",
+                        letter
+                    )))
+                }
+            }
+        }
+        if single.contains('d') && !single.contains('f') {
+            return Err(SimError::ParseError("the D extension requires F".into()));
+        }
+
+        let parts = parts.flat_map(|part| {
+            SHORTHANDS
+                .iter()
+                .find(|(shorthand, _)| *shorthand == part)
+                .map_or(vec![part], |(_, extensions)| extensions.to_vec())
+        });
+        for extension in parts {
+            if !MULTI_LETTER_EXTENSIONS.contains(&extension) && zvl_bits(extension).is_none() {
+                return Err(SimError::ParseError(format!(
+                    "unsupported extension {:?}",
+                    extension
+                )));
+            }
+            if !extensions.iter().any(|e| e == extension) {
+                extensions.push(extension.to_string());
+            }
+        }
+
+        let has = |name: &str| extensions.iter().any(|e| e == name);
+        if (has("zcb") || has("zcmp") || has("zcmt")) && !single.contains('c') {
+            return Err(SimError::ParseError(
+                "the Zcb, Zcmp and Zcmt extensions require C".into(),
+            ));
+        }
+        // Zcmp and Zcmt reuse the encodings of c.fsdsp and friends.
+        if (has("zcmp") || has("zcmt")) && single.contains('d') {
+            return Err(SimError::ParseError(
+                "the Zcmp and Zcmt extensions are incompatible with D".into(),
+            ));
+        }
+
+        // V implies Zve64d with VLEN of at least 128; Zvl*b can only raise VLEN.
+        let (elen, min_vlen) = if single.contains('v') {
+            (64, 128)
+        } else if has("zve64x") || has("zve64f") || has("zve64d") {
+            (64, 64)
+        } else if has("zve32x") || has("zve32f") {
+            (32, 32)
+        } else {
+            (0, 0)
+        };
+        let zvl = extensions.iter().filter_map(|e| zvl_bits(e)).max();
+        if zvl.is_some() && elen == 0 {
+            return Err(SimError::ParseError(
+                "the Zvl*b extensions require V or a Zve* extension".into(),
+            ));
+        }
+        if (single.contains('v') || has("zve64d")) && !single.contains('d') {
+            return Err(SimError::ParseError(
+                "the V and Zve64d extensions require D".into(),
+            ));
+        }
+        if (has("zve32f") || has("zve64f")) && !single.contains('f') {
+            return Err(SimError::ParseError(
+                "the Zve32f and Zve64f extensions require F".into(),
+            ));
+        }
+        let vlen = zvl.unwrap_or(0).max(min_vlen);
+
+        single.push_str("su");
+        Ok(Isa {
+            xlen,
+            misa: misa_extensions(&single),
+            extensions,
+            vlen,
+            elen,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zcmp_and_zcmt_exclude_d() {
+        assert!("rv32imafc_zcmp_zcmt".parse::<Isa>().is_ok());
+        assert!("rv32imafdc_zcmp".parse::<Isa>().is_err());
+        assert!("rv64gc_zcmt".parse::<Isa>().is_err());
+        assert!("rv64gc_zcb".parse::<Isa>().is_ok());
+        assert!("rv64ima_zcb".parse::<Isa>().is_err());
+    }
+
+    #[test]
+    fn crypto_shorthands_expand() {
+        let isa: Isa = "rv64i_zks_zkn".parse().unwrap();
+        assert_eq!(
+            isa.extensions,
+            ["zbkb", "zbkc", "zbkx", "zksed", "zksh", "zkne", "zknd", "zknh"]
+        );
+        let isa: Isa = "rv32i_zk".parse().unwrap();
+        assert!(isa.extensions.iter().any(|e| e == "zkr"));
+        assert!(isa.extensions.iter().any(|e| e == "zkt"));
+        assert_eq!(isa.extensions.len(), 8);
+        assert!("rv64i_zkx".parse::<Isa>().is_err());
+    }
+}
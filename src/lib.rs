@@ -0,0 +1,28 @@
+//! A RISC-V instruction-set simulator that runs ELF programs on bare metal, under HTIF, or
+//! with Linux or newlib system calls emulated on the host.
+//!
+//! Build a [`Machine`] with [`Machine::builder`], then drive it with [`Machine::step`] or
+//! [`Machine::run`], which report why they stopped as a [`StopReason`].
+
+// Instruction and register names follow the spec's spelling (`C_ADDI`, `a0`, `XLEN`).
+#![allow(non_camel_case_types, clippy::upper_case_acronyms)]
+
+pub mod csr;
+mod decode;
+pub mod error;
+pub mod htif;
+pub mod instructions;
+pub mod isa;
+pub mod loader;
+pub mod machine;
+pub mod memory;
+pub mod mmu;
+pub mod processor;
+mod signature;
+pub mod softfloat;
+pub mod syscall;
+pub mod trap;
+mod utils;
+
+pub use machine::{Machine, MachineBuilder, StopReason};
+pub use processor::{FprName, State, XprName};
@@ -0,0 +1,443 @@
+use goblin::{
+    elf::{
+        header::{EI_CLASS, ELFCLASS32, ELFCLASS64, EM_RISCV},
+        program_header::{PF_R, PF_W, PF_X, PT_LOAD, PT_PHDR},
+        section_header::SHN_UNDEF,
+        sym::{STT_FILE, STT_SECTION},
+        Elf,
+    },
+    error::{self, Error},
+    Object,
+};
+use std::fs;
+use std::path::Path;
+
+use crate::{
+    memory::{Memory, MemoryError, Permissions},
+    mmu::PAGE_SIZE,
+    processor::{Reg, State, XprName, XLEN},
+};
+
+// Auxiliary vector entry types.
+const AT_NULL: u64 = 0;
+const AT_PHDR: u64 = 3;
+const AT_PHENT: u64 = 4;
+const AT_PHNUM: u64 = 5;
+const AT_PAGESZ: u64 = 6;
+const AT_ENTRY: u64 = 9;
+const AT_UID: u64 = 11;
+const AT_EUID: u64 = 12;
+const AT_GID: u64 = 13;
+const AT_EGID: u64 = 14;
+const AT_HWCAP: u64 = 16;
+const AT_SECURE: u64 = 23;
+const AT_RANDOM: u64 = 25;
+const AT_EXECFN: u64 = 31;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub addr: u64,
+    pub size: u64,
+}
+
+/// Symbols of the loaded program, sorted by address.
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+}
+
+impl SymbolTable {
+    fn new(elf: &Elf) -> Self {
+        elf.syms
+            .iter()
+            .filter(|sym| {
+                sym.st_shndx != SHN_UNDEF as usize
+                    && sym.st_name != 0
+                    && !matches!(sym.st_type(), STT_SECTION | STT_FILE)
+            })
+            .filter_map(|sym| {
+                Some(Symbol {
+                    name: elf.strtab.get_at(sym.st_name)?.to_string(),
+                    addr: sym.st_value,
+                    size: sym.st_size,
+                })
+            })
+            .collect()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|symbol| symbol.name == name)
+    }
+
+    /// Finds the symbol covering `addr` and the offset into it. Sizeless symbols such as
+    /// assembly labels cover everything up to the next symbol.
+    pub fn symbolize(&self, addr: u64) -> Option<(&Symbol, u64)> {
+        let index = self.symbols.partition_point(|symbol| symbol.addr <= addr);
+        let symbol = &self.symbols[index.checked_sub(1)?];
+        let offset = addr - symbol.addr;
+        (symbol.size == 0 || offset < symbol.size).then_some((symbol, offset))
+    }
+}
+
+impl FromIterator<Symbol> for SymbolTable {
+    fn from_iter<I: IntoIterator<Item = Symbol>>(iter: I) -> Self {
+        let mut symbols: Vec<Symbol> = iter.into_iter().collect();
+        symbols.sort_by_key(|symbol| symbol.addr);
+        SymbolTable { symbols }
+    }
+}
+
+/// What the loader learned about the executable.
+#[derive(Debug)]
+pub struct Program {
+    pub entry: u64,
+    /// Address of the program headers in guest memory, or 0 if no segment maps them.
+    pub phdr: u64,
+    pub phent: u64,
+    pub phnum: u64,
+    pub symbols: SymbolTable,
+}
+
+impl State {
+    /// Loads the `PT_LOAD` segments of a RISC-V ELF executable, restricts each to its
+    /// `p_flags`, and points the pc at the entry point.
+    pub fn read_elf(&mut self, path: impl AsRef<Path>) -> error::Result<Program> {
+        let buffer = fs::read(path)?;
+        let elf = match Object::parse(&buffer)? {
+            Object::Elf(elf) => elf,
+            _ => return Err(Error::Malformed("not an ELF file".into())),
+        };
+        if elf.header.e_machine != EM_RISCV {
+            return Err(Error::Malformed(format!(
+                "not a RISC-V executable (e_machine {})",
+                elf.header.e_machine
+            )));
+        }
+        self.xlen = match elf.header.e_ident[EI_CLASS] {
+            ELFCLASS32 => XLEN::RV32,
+            ELFCLASS64 => XLEN::RV64,
+            class => return Err(Error::Malformed(format!("unsupported ELF class {}", class))),
+        };
+
+        for ph in elf.program_headers.iter().filter(|ph| ph.p_type == PT_LOAD) {
+            let outside_ram =
+                || Error::Malformed(format!("segment at {:#x} lies outside RAM", ph.p_vaddr));
+            if ph.p_filesz > ph.p_memsz {
+                return Err(Error::Malformed(format!(
+                    "segment at {:#x} has more file bytes than memory bytes",
+                    ph.p_vaddr
+                )));
+            }
+            let data = usize::try_from(ph.p_offset)
+                .ok()
+                .zip(usize::try_from(ph.p_filesz).ok())
+                .and_then(|(start, len)| buffer.get(start..start.checked_add(len)?))
+                .ok_or_else(|| {
+                    Error::Malformed(format!("segment at {:#x} is truncated", ph.p_vaddr))
+                })?;
+            self.memory
+                .write(ph.p_vaddr, data)
+                .map_err(|_| outside_ram())?;
+            // The part of the segment beyond the file image is BSS.
+            self.memory
+                .zero(ph.p_vaddr + ph.p_filesz, ph.p_memsz - ph.p_filesz)
+                .map_err(|_| outside_ram())?;
+
+            let mut permissions = Permissions::empty();
+            permissions.set(Permissions::R, ph.p_flags & PF_R != 0);
+            permissions.set(Permissions::W, ph.p_flags & PF_W != 0);
+            permissions.set(Permissions::X, ph.p_flags & PF_X != 0);
+            self.memory.protect(ph.p_vaddr, ph.p_memsz, permissions);
+        }
+
+        // Prefer PT_PHDR; otherwise find the loaded segment whose file image covers them.
+        let phoff = elf.header.e_phoff;
+        let phdr = elf
+            .program_headers
+            .iter()
+            .find(|ph| ph.p_type == PT_PHDR)
+            .map(|ph| ph.p_vaddr)
+            .or_else(|| {
+                elf.program_headers
+                    .iter()
+                    .find(|ph| {
+                        ph.p_type == PT_LOAD
+                            && ph.p_offset <= phoff
+                            && phoff < ph.p_offset + ph.p_filesz
+                    })
+                    .map(|ph| ph.p_vaddr + (phoff - ph.p_offset))
+            })
+            .unwrap_or(0);
+
+        self.pc = elf.header.e_entry as i64;
+        Ok(Program {
+            entry: elf.header.e_entry,
+            phdr,
+            phent: elf.header.e_phentsize as u64,
+            phnum: elf.header.e_phnum as u64,
+            symbols: SymbolTable::new(&elf),
+        })
+    }
+
+    /// Builds the stack a Linux kernel hands a new process at the top of RAM and points `sp`
+    /// at it: `argc`, the `argv` and `envp` pointer arrays, and the auxiliary vector, with
+    /// the strings they refer to above them.
+    pub fn init_stack(
+        &mut self,
+        program: &Program,
+        argv: &[String],
+        envp: &[String],
+    ) -> Result<(), MemoryError> {
+        let word = (self.xlen_bits() / 8) as usize;
+        let mut top = self.memory.base() + self.memory.size();
+        let mut push_string = |memory: &mut Memory, s: &String| {
+            let mut data = s.as_bytes().to_vec();
+            data.push(0);
+            push(memory, &mut top, &data)
+        };
+        let envp = envp
+            .iter()
+            .map(|s| push_string(&mut self.memory, s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let argv = argv
+            .iter()
+            .map(|s| push_string(&mut self.memory, s))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Seeds the libc stack protector; fixed so that runs are reproducible.
+        let random = push(&mut self.memory, &mut top, b"jisaku-riscv-sim")?;
+
+        let auxv = [
+            (AT_PHDR, program.phdr),
+            (AT_PHENT, program.phent),
+            (AT_PHNUM, program.phnum),
+            (AT_PAGESZ, PAGE_SIZE),
+            (AT_ENTRY, program.entry),
+            (AT_UID, 0),
+            (AT_EUID, 0),
+            (AT_GID, 0),
+            (AT_EGID, 0),
+            (AT_HWCAP, self.misa as u64 & 0x3ff_ffff),
+            (AT_SECURE, 0),
+            (AT_RANDOM, random),
+            (AT_EXECFN, argv[0]),
+            (AT_NULL, 0),
+        ];
+        let mut words = vec![argv.len() as u64];
+        words.extend(&argv);
+        words.push(0);
+        words.extend(&envp);
+        words.push(0);
+        words.extend(auxv.iter().flat_map(|&(key, value)| [key, value]));
+
+        let table: Vec<u8> = words
+            .iter()
+            .flat_map(|value| value.to_le_bytes().into_iter().take(word))
+            .collect();
+        // The ABI wants `sp` 16-byte aligned at process entry.
+        let sp = top
+            .checked_sub(table.len() as u64)
+            .ok_or(MemoryError::Unmapped(0))?
+            & !0xf;
+        self.memory.write(sp, &table)?;
+        self.set_reg(XprName::sp, sp as Reg);
+        Ok(())
+    }
+}
+
+/// Copies `data` to just below `top`, moves `top` down to it and returns the address.
+fn push(memory: &mut Memory, top: &mut u64, data: &[u8]) -> Result<u64, MemoryError> {
+    *top = top
+        .checked_sub(data.len() as u64)
+        .ok_or(MemoryError::Unmapped(0))?;
+    memory.write(*top, data)?;
+    Ok(*top)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, processor::Reg, trap::Exception};
+
+    const BASE: u64 = 0x8000_0000;
+
+    /// A little-endian ELF64 executable with a text segment at `BASE`, a data segment with
+    /// four file bytes and a page of BSS at `BASE + 0x2000`, and a `_start` symbol.
+    fn elf64() -> Vec<u8> {
+        let text = 0x0000_0073_0010_0513u64.to_le_bytes(); // addi a0, zero, 1; ecall
+        let data = [1, 2, 3, 4];
+        let strtab = b"\0_start\0";
+        let shstrtab = b"\0.symtab\0.strtab\0.shstrtab\0";
+        let mut symtab = vec![0; 24];
+        symtab.extend(1u32.to_le_bytes());
+        symtab.extend([0x12, 0]);
+        symtab.extend(1u16.to_le_bytes());
+        symtab.extend(BASE.to_le_bytes());
+        symtab.extend(8u64.to_le_bytes());
+
+        let (text_off, data_off, symtab_off) = (0x1000, 0x1008, 0x1010);
+        let strtab_off = symtab_off + symtab.len() as u64;
+        let shstrtab_off = strtab_off + strtab.len() as u64;
+        let shoff = shstrtab_off + shstrtab.len() as u64;
+
+        let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+        elf.resize(16, 0);
+        elf.extend(2u16.to_le_bytes());
+        elf.extend(243u16.to_le_bytes());
+        elf.extend(1u32.to_le_bytes());
+        elf.extend(BASE.to_le_bytes());
+        elf.extend(64u64.to_le_bytes());
+        elf.extend(shoff.to_le_bytes());
+        elf.extend(0u32.to_le_bytes());
+        for half in [64, 56, 2, 64, 4, 3] {
+            elf.extend((half as u16).to_le_bytes());
+        }
+        let segments = [
+            (PF_R | PF_X, text_off, BASE, 8, 8),
+            (PF_R | PF_W, data_off, BASE + 0x2000, 4, 0x2000),
+        ];
+        for (flags, offset, vaddr, filesz, memsz) in segments {
+            elf.extend(PT_LOAD.to_le_bytes());
+            elf.extend(flags.to_le_bytes());
+            for field in [offset, vaddr, vaddr, filesz, memsz, 0x1000] {
+                elf.extend(field.to_le_bytes());
+            }
+        }
+        elf.resize(text_off as usize, 0);
+        elf.extend(text);
+        elf.extend(data);
+        elf.resize(symtab_off as usize, 0);
+        elf.extend(&symtab);
+        elf.extend(strtab);
+        elf.extend(shstrtab);
+
+        // Null, .symtab (linked to .strtab), .strtab and .shstrtab section headers.
+        let sections: [(u32, u32, u64, u64, u32, u32, u64); 4] = [
+            (0, 0, 0, 0, 0, 0, 0),
+            (1, 2, symtab_off, symtab.len() as u64, 2, 1, 24),
+            (9, 3, strtab_off, strtab.len() as u64, 0, 0, 0),
+            (17, 3, shstrtab_off, shstrtab.len() as u64, 0, 0, 0),
+        ];
+        for (name, kind, offset, size, link, info, entsize) in sections {
+            elf.extend(name.to_le_bytes());
+            elf.extend(kind.to_le_bytes());
+            for field in [0, 0, offset, size] {
+                elf.extend(field.to_le_bytes());
+            }
+            elf.extend(link.to_le_bytes());
+            elf.extend(info.to_le_bytes());
+            elf.extend(1u64.to_le_bytes());
+            elf.extend(entsize.to_le_bytes());
+        }
+        elf
+    }
+
+    #[test]
+    fn segments_load_with_zeroed_bss_and_their_permissions() {
+        let path = std::env::temp_dir().join(format!("jisaku-loader-{}.elf", std::process::id()));
+        fs::write(&path, elf64()).unwrap();
+        let mut state = State::new();
+        state.memory = Memory::new(BASE, 1 << 20);
+        // Stale bytes where the BSS goes must not survive the load.
+        state.memory.write(BASE + 0x2004, &[0xff; 0x1000]).unwrap();
+        let program = state.read_elf(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(program.entry, BASE);
+        assert_eq!(state.pc, BASE as Reg);
+        assert_eq!(state.xlen, XLEN::RV64);
+        assert_eq!(state.memory.read_u32(BASE), Ok(0x0010_0513));
+        assert_eq!(state.memory.read_u32(BASE + 0x2000), Ok(0x0403_0201));
+        assert_eq!(state.memory.read_u64(BASE + 0x2004), Ok(0));
+        assert_eq!(state.memory.read_u64(BASE + 0x3ff8), Ok(0));
+
+        assert!(state
+            .memory
+            .permits(BASE, 8, Permissions::R | Permissions::X));
+        assert!(!state.memory.permits(BASE + 0x2000, 4, Permissions::X));
+        // Only a process gets a read-only text segment; bare metal may patch its own code.
+        state.protect_segments = true;
+        assert_eq!(
+            state.store_u32(BASE as i64, 0),
+            Err(Exception::StoreAccessFault(BASE))
+        );
+        assert!(state.store_u32(BASE as i64 + 0x3ffc, 0).is_ok());
+        state.protect_segments = false;
+        assert!(state.store_u32(BASE as i64, 0).is_ok());
+
+        let start = program.symbols.lookup("_start").unwrap();
+        assert_eq!((start.addr, start.size), (BASE, 8));
+        assert_eq!(program.symbols.symbolize(BASE + 4), Some((start, 4)));
+        assert_eq!(program.symbols.symbolize(BASE + 8), None);
+    }
+
+    #[test]
+    fn initial_stack_holds_argv_envp_and_auxv_per_xlen() {
+        for (xlen, word) in [(XLEN::RV32, 4), (XLEN::RV64, 8)] {
+            let mut state = State::new();
+            state.xlen = xlen;
+            state.memory = Memory::new(BASE, 1 << 20);
+            let program = Program {
+                entry: BASE + 0x100,
+                phdr: BASE + 64,
+                phent: 56,
+                phnum: 2,
+                symbols: SymbolTable::default(),
+            };
+            let argv = ["prog".to_string(), "-v".to_string()];
+            state
+                .init_stack(&program, &argv, &["HOME=/".to_string()])
+                .unwrap();
+
+            let sp = state.get_reg_u(XprName::sp);
+            assert_eq!(sp % 16, 0);
+            // argc, two argv pointers, one envp pointer, two nulls and fourteen auxv pairs.
+            let words: Vec<u64> = (0..34)
+                .map(|i| {
+                    let mut bytes = [0; 8];
+                    state
+                        .memory
+                        .read(sp + i * word, &mut bytes[..word as usize])
+                        .unwrap();
+                    u64::from_le_bytes(bytes)
+                })
+                .collect();
+            let string = |addr: u64| {
+                let mut bytes = vec![];
+                for addr in addr.. {
+                    match state.memory.read_u8(addr).unwrap() {
+                        0 => break,
+                        byte => bytes.push(byte),
+                    }
+                }
+                String::from_utf8(bytes).unwrap()
+            };
+
+            assert_eq!(words[0], 2);
+            assert_eq!(string(words[1]), "prog");
+            assert_eq!(string(words[2]), "-v");
+            assert_eq!(words[3], 0);
+            assert_eq!(string(words[4]), "HOME=/");
+            assert_eq!(words[5], 0);
+
+            let auxv: Vec<(u64, u64)> = words[6..]
+                .chunks(2)
+                .map(|pair| (pair[0], pair[1]))
+                .take_while(|&(key, _)| key != AT_NULL)
+                .collect();
+            let aux = |key| auxv.iter().find(|&&(k, _)| k == key).unwrap().1;
+            assert_eq!(aux(AT_PHDR), BASE + 64);
+            assert_eq!(aux(AT_PHENT), 56);
+            assert_eq!(aux(AT_PHNUM), 2);
+            assert_eq!(aux(AT_PAGESZ), PAGE_SIZE);
+            assert_eq!(aux(AT_ENTRY), BASE + 0x100);
+            assert_eq!(aux(AT_HWCAP), state.misa as u64 & 0x3ff_ffff);
+            assert_eq!(aux(AT_EXECFN), words[1]);
+            // The 16 random bytes and the strings sit above the table, below the top of RAM.
+            let random = aux(AT_RANDOM);
+            assert!(sp + 34 * word <= random);
+            assert!(random + 16 <= words[2] && words[4] + 7 <= BASE + (1 << 20));
+            assert_eq!(words[32..], [AT_NULL, 0]);
+        }
+    }
+}
@@ -0,0 +1,544 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    csr::{MSTATUS_FS_INITIAL, MSTATUS_VS_INITIAL},
+    error::SimError,
+    htif::Htif,
+    isa::Isa,
+    loader::SymbolTable,
+    memory::{Memory, MemoryError, DEFAULT_RAM_BASE, DEFAULT_RAM_SIZE},
+    processor::{FprName, Reg, State, XprName, DEFAULT_ENTROPY_SEED},
+    syscall::{Personality, Syscalls},
+    trap::Exception,
+};
+
+/// Why `Machine::run` handed control back.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StopReason {
+    /// The requested number of steps ran without the guest stopping.
+    InstructionLimit,
+    /// The guest exited with this status, through a system call or HTIF.
+    Exited(i64),
+    /// A trap handler faulted on its own first instruction, so execution cannot progress.
+    TrapLoop { pc: u64, exception: Exception },
+}
+
+/// Configures a `Machine`. Without an ELF the machine starts blank with the pc at the base
+/// of RAM, ready for code placed with `Machine::load`.
+pub struct MachineBuilder {
+    isa: Option<Isa>,
+    memory_base: u64,
+    memory_size: u64,
+    syscalls: Option<Personality>,
+    trace: bool,
+    entropy_seed: u64,
+    elf: Option<PathBuf>,
+    args: Vec<String>,
+    env: Vec<String>,
+}
+
+impl Default for MachineBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MachineBuilder {
+    pub fn new() -> Self {
+        MachineBuilder {
+            isa: None,
+            memory_base: DEFAULT_RAM_BASE,
+            memory_size: DEFAULT_RAM_SIZE,
+            syscalls: None,
+            trace: false,
+            entropy_seed: DEFAULT_ENTROPY_SEED,
+            elf: None,
+            args: vec![],
+            env: vec![],
+        }
+    }
+
+    /// The ISA to simulate. With an ELF, its XLEN must match the ELF class; otherwise the
+    /// ELF class decides XLEN and the extensions default to `imafdc`.
+    pub fn isa(mut self, isa: Isa) -> Self {
+        self.isa = Some(isa);
+        self
+    }
+
+    pub fn memory(mut self, base: u64, size: u64) -> Self {
+        self.memory_base = base;
+        self.memory_size = size;
+        self
+    }
+
+    /// Services ECALL as the given OS would instead of trapping, and gives an ELF the
+    /// initial stack that OS builds.
+    pub fn syscalls(mut self, personality: Personality) -> Self {
+        self.syscalls = Some(personality);
+        self
+    }
+
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Seeds the Zkr entropy source read through the `seed` CSR. The same seed replays the
+    /// same entropy.
+    pub fn entropy_seed(mut self, seed: u64) -> Self {
+        self.entropy_seed = seed;
+        self
+    }
+
+    pub fn elf(mut self, path: impl AsRef<Path>) -> Self {
+        self.elf = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Guest `argv`; the ELF path is used as `argv[0]` when this is empty.
+    pub fn args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Guest environment, as `NAME=value` strings.
+    pub fn env(mut self, env: Vec<String>) -> Self {
+        self.env = env;
+        self
+    }
+
+    pub fn build(self) -> Result<Machine, SimError> {
+        let mut state = State::new();
+        state.trace = self.trace;
+        state.entropy = self.entropy_seed;
+        state.memory = Memory::new(self.memory_base, self.memory_size);
+        state.pc = self.memory_base as Reg;
+        if let Some(isa) = &self.isa {
+            state.set_isa(isa);
+        }
+        if self.syscalls.is_some() {
+            // Like a kernel starting a process, hand the program usable FP and vector units.
+            state.mstatus |= MSTATUS_FS_INITIAL as Reg;
+            if state.has_vector() {
+                state.mstatus |= MSTATUS_VS_INITIAL as Reg;
+            }
+            state.protect_segments = true;
+        }
+
+        let Some(path) = &self.elf else {
+            if let Some(personality) = self.syscalls {
+                state.syscalls = Some(Syscalls::new(personality, &state.memory));
+            }
+            return Ok(Machine {
+                state,
+                symbols: SymbolTable::default(),
+            });
+        };
+        let program = state
+            .read_elf(path)
+            .map_err(|e| SimError::from(format!("{}: {}", path.display(), e)))?;
+        if let Some(isa) = &self.isa {
+            if isa.xlen != state.xlen {
+                return Err(SimError::from(format!(
+                    "{}: ELF is {:?} but the ISA is {:?}",
+                    path.display(),
+                    state.xlen,
+                    isa.xlen
+                )));
+            }
+        }
+        state.htif = Htif::new(&program.symbols, &state.memory);
+        if let Some(personality) = self.syscalls {
+            state.syscalls = Some(Syscalls::new(personality, &state.memory));
+            let args = if self.args.is_empty() {
+                vec![path.to_string_lossy().into_owned()]
+            } else {
+                self.args
+            };
+            state
+                .init_stack(&program, &args, &self.env)
+                .map_err(|e| SimError::from(format!("cannot build the initial stack: {:?}", e)))?;
+        }
+        Ok(Machine {
+            state,
+            symbols: program.symbols,
+        })
+    }
+}
+
+/// A simulated hart with its memory, ready to run.
+pub struct Machine {
+    pub state: State,
+    symbols: SymbolTable,
+}
+
+impl Machine {
+    pub fn builder() -> MachineBuilder {
+        MachineBuilder::new()
+    }
+
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Executes one instruction, or takes one trap or interrupt.
+    pub fn step(&mut self) -> StopReason {
+        self.run(1)
+    }
+
+    /// Executes up to `steps` instructions, stopping early if the guest exits or gets stuck.
+    pub fn run(&mut self, steps: u64) -> StopReason {
+        for _ in 0..steps {
+            if let Some(reason) = self.stop_reason() {
+                return reason;
+            }
+            if let Err(reason) = self.execute() {
+                return reason;
+            }
+        }
+        self.stop_reason().unwrap_or(StopReason::InstructionLimit)
+    }
+
+    fn stop_reason(&mut self) -> Option<StopReason> {
+        self.state.poll_htif();
+        self.state.exit_code.map(StopReason::Exited)
+    }
+
+    fn execute(&mut self) -> Result<(), StopReason> {
+        let state = &mut self.state;
+        if state.trace {
+            state.print_regs();
+            match self.symbols.symbolize(state.zext_xlen(state.pc)) {
+                Some((symbol, offset)) => {
+                    print!("{:#x} <{}+{:#x}>:\t", state.pc, symbol.name, offset)
+                }
+                None => print!("{:#x}:\t", state.pc),
+            }
+        }
+        if state.take_interrupt() {
+            return Ok(());
+        }
+        let result = state.fetch_inst().and_then(|inst| {
+            state
+                .decode_inst(inst)?
+                .execute(state)
+                .map_err(|exception| match exception {
+                    // Checks made while executing, such as those on vtype, leave the
+                    // encoding to be filled in here.
+                    Exception::IllegalInstruction(0) => Exception::IllegalInstruction(inst.bits()),
+                    exception => exception,
+                })
+        });
+        match result {
+            Ok(()) => state.minstret = state.minstret.wrapping_add(1),
+            Err(exception) => {
+                if state.trace {
+                    println!("exception: {:?}", exception);
+                }
+                let pc = state.pc;
+                state.take_trap(exception);
+                // A handler that faults on its own first instruction would spin forever.
+                if state.pc == pc {
+                    return Err(StopReason::TrapLoop {
+                        pc: state.zext_xlen(pc),
+                        exception,
+                    });
+                }
+            }
+        }
+        state.pc = state.zext_xlen(state.pc) as i64;
+        Ok(())
+    }
+
+    pub fn pc(&self) -> u64 {
+        self.state.zext_xlen(self.state.pc)
+    }
+    pub fn set_pc(&mut self, pc: u64) {
+        self.state.pc = self.state.sext_xlen(pc as i64);
+    }
+    pub fn reg(&self, xpr: XprName) -> i64 {
+        self.state.get_reg(xpr)
+    }
+    pub fn set_reg(&mut self, xpr: XprName, value: i64) {
+        self.state.set_reg(xpr, value);
+    }
+    /// Raw bits of a floating-point register; single-precision values are NaN-boxed.
+    pub fn freg(&self, fpr: FprName) -> u64 {
+        self.state.fregs.get(fpr)
+    }
+    pub fn set_freg(&mut self, fpr: FprName, value: u64) {
+        self.state.fregs.set(fpr, value);
+    }
+
+    /// Writes the riscv-arch-test signature; see `State::dump_signature`.
+    pub fn dump_signature(
+        &mut self,
+        path: impl AsRef<Path>,
+        granularity: u64,
+    ) -> Result<(), SimError> {
+        self.state.dump_signature(&self.symbols, path, granularity)
+    }
+
+    /// Copies `data` into physical memory, such as raw code to run on a blank machine.
+    pub fn load(&mut self, addr: u64, data: &[u8]) -> Result<(), MemoryError> {
+        self.state.memory.write(addr, data)
+    }
+    pub fn read_memory(&self, addr: u64, data: &mut [u8]) -> Result<(), MemoryError> {
+        self.state.memory.read(addr, data)
+    }
+    pub fn write_memory(&mut self, addr: u64, data: &[u8]) -> Result<(), MemoryError> {
+        self.state.memory.write(addr, data)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{csr::MSTATUS_VS, processor::Privilege};
+
+    pub(crate) const BASE: u64 = 0x8000_0000;
+
+    /// Builds a machine with 1 MiB of RAM at `BASE` and `code` loaded at its start.
+    /// Compressed instructions are packed two to a word, the first in the low half.
+    pub(crate) fn machine_from(builder: MachineBuilder, code: &[u32]) -> Machine {
+        let mut machine = builder.memory(BASE, 1 << 20).build().unwrap();
+        let code: Vec<u8> = code.iter().flat_map(|inst| inst.to_le_bytes()).collect();
+        machine.load(BASE, &code).unwrap();
+        machine
+    }
+
+    pub(crate) fn machine_with(isa: &str, code: &[u32]) -> Machine {
+        machine_from(Machine::builder().isa(isa.parse().unwrap()), code)
+    }
+
+    pub(crate) fn machine(code: &[u32]) -> Machine {
+        machine_from(Machine::builder(), code)
+    }
+
+    #[test]
+    fn step_executes_one_instruction() {
+        // addi a0, zero, 5; addi a0, a0, 1
+        let mut machine = machine(&[0x0050_0513, 0x0015_0513]);
+        assert_eq!(machine.step(), StopReason::InstructionLimit);
+        assert_eq!(machine.reg(XprName::a0), 5);
+        assert_eq!(machine.pc(), BASE + 4);
+        assert_eq!(machine.run(1), StopReason::InstructionLimit);
+        assert_eq!(machine.reg(XprName::a0), 6);
+    }
+
+    #[test]
+    fn run_stops_when_the_guest_exits() {
+        // addi a0, zero, 3; addi a7, zero, 93; ecall
+        let mut machine = machine(&[0x0030_0513, 0x05d0_0893, 0x0000_0073]);
+        machine.state.syscalls = Some(Syscalls::new(Personality::Linux, &machine.state.memory));
+        assert_eq!(machine.run(100), StopReason::Exited(3));
+    }
+
+    #[test]
+    fn run_reports_a_trap_loop() {
+        // With mtvec at 0, outside RAM, the first fetch of the handler faults again.
+        let mut machine = machine(&[0x0000_0000]);
+        assert_eq!(
+            machine.run(100),
+            StopReason::TrapLoop {
+                pc: 0,
+                exception: Exception::InstructionAccessFault(0)
+            }
+        );
+    }
+
+    #[test]
+    fn compressed_instructions_decode_per_xlen() {
+        // c.addi16sp sp, -64; c.addiw a0, 1
+        let mut machine = machine(&[0x2505_7139]);
+        assert_eq!(machine.run(2), StopReason::InstructionLimit);
+        assert_eq!(machine.reg(XprName::sp), -64);
+        assert_eq!(machine.reg(XprName::a0), 1);
+        assert_eq!(machine.pc(), BASE + 4);
+
+        // On RV32 the same funct3 is c.jal.
+        let mut machine = machine_with("rv32imac", &[0x2505_7139]);
+        machine.run(2);
+        assert_eq!(machine.reg(XprName::ra), (BASE + 4) as i32 as i64);
+        assert_eq!(machine.pc(), BASE + 2 + 0x620);
+    }
+
+    #[test]
+    fn compressed_hints_execute_as_no_ops() {
+        let mut machine = machine(&[
+            0x0501_0005, // c.nop 1; c.addi a0, 0
+            0x6005_4015, // c.li zero, 5; c.lui zero, 1
+            0x902a_802a, // c.mv zero, a0; c.add zero, a0
+            0x0006_0502, // c.slli a0, 0; c.slli zero, 1
+            0x0001_8101, // c.srli a0, 0; c.nop
+        ]);
+        machine.set_reg(XprName::a0, 7);
+        assert_eq!(machine.run(10), StopReason::InstructionLimit);
+        assert_eq!(machine.pc(), BASE + 20);
+        assert_eq!(machine.reg(XprName::a0), 7);
+        assert_eq!(machine.reg(XprName::zero), 0);
+        assert_eq!(machine.state.mcause, 0);
+    }
+
+    #[test]
+    fn c_ntl_hints_are_no_ops() {
+        // c.ntl.p1, c.ntl.pall, c.ntl.s1 and c.ntl.all are c.add zero, x2..x5.
+        let mut machine = machine(&[0x900e_900a, 0x9016_9012]);
+        machine.set_reg(XprName::sp, 0x100);
+        assert_eq!(machine.run(4), StopReason::InstructionLimit);
+        assert_eq!(machine.pc(), BASE + 8);
+        assert_eq!(machine.reg(XprName::sp), 0x100);
+        assert_eq!(machine.state.mcause, 0);
+    }
+
+    #[test]
+    fn reserved_compressed_encodings_trap() {
+        // c.lui a0, 0; c.jr zero; c.addi4spn a0, sp, 0
+        for inst in [0x6501, 0x8002, 0x0008] {
+            let mut machine = machine(&[inst]);
+            machine.step();
+            assert_eq!(machine.state.mcause, 2, "{:#06x}", inst);
+            assert_eq!(machine.state.mtval, inst as i64);
+        }
+    }
+
+    #[test]
+    fn bit_manipulation_follows_the_isa_string() {
+        // addi a1, zero, 0x100; clz a0, a1
+        let code = [0x1000_0593, 0x6005_9513];
+        let mut machine = machine_with("rv64gc_zbb", &code);
+        machine.run(2);
+        assert_eq!(machine.reg(XprName::a0), 55);
+
+        // Without Zbb the same encoding is illegal.
+        let mut machine = machine_with("rv64gc", &code);
+        machine.run(2);
+        assert_eq!(machine.state.mepc, (BASE + 4) as i64);
+        assert_eq!(machine.reg(XprName::a0), 0);
+    }
+
+    #[test]
+    fn vector_length_follows_zvl() {
+        let code = [
+            0x0d00_7557, // vsetvli a0, zero, e32, m1, ta, ma
+            0x5208_a0d7, // vid.v v1
+            0x0210_a157, // vredsum.vs v2, v1, v1
+            0x4220_25d7, // vmv.x.s a1, v2
+        ];
+        for (isa, vlmax, sum) in [("rv64gcv", 4, 6), ("rv64gcv_zvl256b", 8, 28)] {
+            let mut machine = machine_with(isa, &code);
+            machine.state.mstatus = MSTATUS_VS_INITIAL as Reg;
+            machine.run(4);
+            assert_eq!(machine.reg(XprName::a0), vlmax);
+            assert_eq!(machine.reg(XprName::a1), sum);
+        }
+    }
+
+    #[test]
+    fn vector_unit_starts_off_and_turns_dirty() {
+        // vsetvli a0, zero, e32, m1, ta, ma
+        let mut machine = machine_with("rv64gcv", &[0x0d00_7557]);
+        machine.step();
+        assert_eq!(machine.state.mcause, 2);
+        assert_eq!(machine.state.mtval, 0x0d00_7557);
+        // csrr a1, vl
+        let mut machine = machine_with("rv64gcv", &[0xc200_25f3]);
+        machine.step();
+        assert_eq!(machine.state.mtval, 0xc200_25f3);
+
+        // A process gets the unit in the Initial state; writing vl and vtype dirties it.
+        let mut machine = machine_from(
+            Machine::builder()
+                .isa("rv64gcv".parse().unwrap())
+                .syscalls(Personality::Linux),
+            &[0x0d00_7557, 0x00a5_1073], // vsetvli as above; csrw vxrm, a0
+        );
+        assert_eq!(
+            machine.state.mstatus as u64 & MSTATUS_VS,
+            MSTATUS_VS_INITIAL
+        );
+        machine.step();
+        assert_eq!(machine.reg(XprName::a0), 4);
+        assert_eq!(machine.state.mstatus as u64 & MSTATUS_VS, MSTATUS_VS);
+        machine.state.mstatus = MSTATUS_VS_INITIAL as Reg;
+        machine.step();
+        assert_eq!(machine.state.vxrm, 0);
+        assert_eq!(machine.state.mstatus as u64 & MSTATUS_VS, MSTATUS_VS);
+    }
+
+    #[test]
+    fn vector_checks_at_execution_report_the_encoding() {
+        let code = [
+            0x0d80_7557, // vsetvli a0, zero, e64, m1, ta, ma
+            0x0220_90d7, // vfadd.vv v1, v2, v1
+        ];
+        // Zve64x has no floating point, which is only found out once SEW is known.
+        let mut machine = machine_with("rv64gc_zve64x", &code);
+        machine.state.mstatus = (MSTATUS_FS_INITIAL | MSTATUS_VS_INITIAL) as Reg;
+        machine.run(2);
+        assert_eq!(machine.state.mcause, 2);
+        assert_eq!(machine.state.mepc, (BASE + 4) as i64);
+        assert_eq!(machine.state.mtval, 0x0220_90d7);
+
+        // With FS Off, vector floating point is illegal even where it exists.
+        let mut machine = machine_with("rv64gcv", &code);
+        machine.state.mstatus = MSTATUS_VS_INITIAL as Reg;
+        machine.run(2);
+        assert_eq!(machine.state.mepc, (BASE + 4) as i64);
+        assert_eq!(machine.state.mtval, 0x0220_90d7);
+    }
+
+    #[test]
+    fn seed_replays_the_entropy_seed() {
+        let samples = |seed| {
+            let builder = Machine::builder()
+                .isa("rv64gc_zkr".parse().unwrap())
+                .entropy_seed(seed);
+            let mut machine = machine_from(
+                builder,
+                &[
+                    0x0150_1573, // csrrw a0, seed, zero
+                    0x0150_15f3, // csrrw a1, seed, zero
+                ],
+            );
+            machine.run(2);
+            (machine.reg(XprName::a0), machine.reg(XprName::a1))
+        };
+        let (first, second) = samples(1);
+        assert_eq!(first >> 30, 0b10);
+        assert_ne!(first, second);
+        assert_eq!(samples(1), (first, second));
+        assert_ne!(samples(2), (first, second));
+    }
+
+    #[test]
+    fn seed_must_be_written_from_machine_mode() {
+        // csrr a0, seed
+        let mut machine = machine_with("rv64gc_zkr", &[0x0150_2573]);
+        machine.step();
+        assert_eq!(machine.state.mcause, 2);
+        assert_eq!(machine.state.mtval, 0x0150_2573);
+
+        for prv in [Privilege::User, Privilege::Supervisor] {
+            // csrrw a0, seed, zero
+            let mut machine = machine_with("rv64gc_zkr", &[0x0150_1573]);
+            machine.state.prv = prv;
+            machine.step();
+            assert_eq!(machine.state.mcause, 2);
+            assert_eq!(machine.state.mtval, 0x0150_1573);
+        }
+    }
+
+    #[test]
+    fn memory_round_trips() {
+        let mut machine = machine(&[]);
+        machine.write_memory(BASE + 0x100, &[1, 2, 3]).unwrap();
+        let mut data = [0; 3];
+        machine.read_memory(BASE + 0x100, &mut data).unwrap();
+        assert_eq!(data, [1, 2, 3]);
+        assert_eq!(
+            machine.read_memory(0, &mut data),
+            Err(MemoryError::Unmapped(0))
+        );
+    }
+}
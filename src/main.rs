@@ -1,40 +1,73 @@
-mod decode;
-mod error;
-pub mod instructions;
-mod mmu;
-mod processor;
-mod utils;
+mod cli;
 
-use processor::State;
+use std::{env, process};
 
-use crate::decode::InstructionRaw;
+use jisaku_riscv_sim::{mmu::PAGE_SIZE, Machine, StopReason};
 
 fn main() {
-    let mut state = State::new();
-    state.read_elf();
-    loop {
-        state.print_regs();
-        print!("{:#x}:\t", state.pc);
-        let inst = state.get_inst();
-        match inst {
-            Ok(inst) => inst.execute(&mut state),
-            Err(e) => panic!("{}", e),
+    let options = match cli::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            process::exit(2);
         }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
     }
-}
 
-/*
-テスト用
-   let insts = vec![
-       0x41, 0x11, // addi sp,sp,-16
-       0x06, 0xe4, // sd ra,8(sp)
-       0xef, 0x00, 0x60, 0x12, // jal ra,101da
-       0x13, 0x85, 0x81, 0x76, // addi a0,gp,1896 # 14760
-       0x93, 0x05, 0x40, 0x06, // li a1,100
-       0xef, 0x00, 0x60, 0x0e, // jal ra,101a6
-       0xa2, 0x60, // ld ra,8(sp)
-       0x01, 0x45, // li a0,0
-       0x41, 0x01, // addi sp,sp,16
-       0x82, 0x80, // ret
-   ];
-*/
+    // A process never has page 0 mapped, so that NULL dereferences fault. The hole is cut out
+    // of RAM rather than shifting it, so the default RAM still ends at the top of RV32's
+    // address space.
+    let (memory_base, memory_size) = match options.syscalls {
+        Some(_) if options.memory_base < PAGE_SIZE => (
+            PAGE_SIZE,
+            options
+                .memory_size
+                .saturating_sub(PAGE_SIZE - options.memory_base),
+        ),
+        _ => (options.memory_base, options.memory_size),
+    };
+    let mut builder = Machine::builder()
+        .memory(memory_base, memory_size)
+        .trace(options.trace)
+        .elf(options.elf())
+        .args(options.args.clone())
+        .env(options.env.clone());
+    if let Some(isa) = options.isa.clone() {
+        builder = builder.isa(isa);
+    }
+    if let Some(personality) = options.syscalls {
+        builder = builder.syscalls(personality);
+    }
+    if let Some(seed) = options.entropy_seed {
+        builder = builder.entropy_seed(seed);
+    }
+    let mut machine = match builder.build() {
+        Ok(machine) => machine,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    let code = match machine.run(options.max_instructions.unwrap_or(u64::MAX)) {
+        StopReason::Exited(code) => code as i32,
+        StopReason::InstructionLimit => {
+            eprintln!("instruction limit reached at {:#x}", machine.pc());
+            1
+        }
+        StopReason::TrapLoop { pc, exception } => {
+            eprintln!("trap loop at {:#x}: {:?}", pc, exception);
+            1
+        }
+    };
+    if let Some(path) = &options.signature {
+        if let Err(e) = machine.dump_signature(path, options.signature_granularity) {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        }
+    }
+    process::exit(code);
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use bitflags::bitflags;
+
+pub const DEFAULT_RAM_BASE: u64 = 0;
+pub const DEFAULT_RAM_SIZE: u64 = 1 << 32;
+
+const PAGE_SHIFT: u64 = 12;
+const PAGE_SIZE: usize = 1 << PAGE_SHIFT;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MemoryError {
+    /// The access touched an address outside RAM.
+    Unmapped(u64),
+}
+
+bitflags! {
+    /// Access rights of a loaded segment, taken from the ELF `p_flags`.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct Permissions: u8 {
+        const R = 1 << 0;
+        const W = 1 << 1;
+        const X = 1 << 2;
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Region {
+    pub start: u64,
+    pub end: u64,
+    pub permissions: Permissions,
+}
+
+/// Physical RAM of a fixed size at a fixed base address. Backing pages are allocated on first
+/// write, so untouched memory reads as zero and costs nothing.
+pub struct Memory {
+    base: u64,
+    size: u64,
+    pages: HashMap<u64, Box<[u8; PAGE_SIZE]>>,
+    /// Ranges with restricted access rights; everything else is readable, writable and
+    /// executable.
+    regions: Vec<Region>,
+}
+
+impl Memory {
+    pub fn new(base: u64, size: u64) -> Self {
+        Memory {
+            base,
+            size,
+            pages: HashMap::new(),
+            regions: vec![],
+        }
+    }
+
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn is_mapped(&self, addr: u64, len: usize) -> bool {
+        addr >= self.base
+            && addr
+                .checked_add(len as u64)
+                .is_some_and(|end| end - self.base <= self.size)
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Restricts `[start, start + len)` to `permissions`.
+    pub fn protect(&mut self, start: u64, len: u64, permissions: Permissions) {
+        self.regions.push(Region {
+            start,
+            end: start.saturating_add(len),
+            permissions,
+        });
+    }
+
+    /// Whether every protected region overlapping `[addr, addr + len)` grants `required`.
+    pub fn permits(&self, addr: u64, len: usize, required: Permissions) -> bool {
+        let end = addr.saturating_add(len as u64);
+        self.regions
+            .iter()
+            .filter(|region| region.start < end && addr < region.end)
+            .all(|region| region.permissions.contains(required))
+    }
+
+    fn check_range(&self, addr: u64, len: usize) -> Result<(), MemoryError> {
+        if !self.is_mapped(addr, len) {
+            return Err(MemoryError::Unmapped(addr));
+        }
+        Ok(())
+    }
+
+    /// Splits `[addr, addr + len)` at page boundaries into (page number, offset, length).
+    fn chunks(addr: u64, len: usize) -> impl Iterator<Item = (u64, usize, usize)> {
+        let mut addr = addr;
+        let mut remaining = len;
+        std::iter::from_fn(move || {
+            if remaining == 0 {
+                return None;
+            }
+            let offset = (addr as usize) & (PAGE_SIZE - 1);
+            let chunk = remaining.min(PAGE_SIZE - offset);
+            let item = (addr >> PAGE_SHIFT, offset, chunk);
+            addr += chunk as u64;
+            remaining -= chunk;
+            Some(item)
+        })
+    }
+
+    pub fn read(&self, addr: u64, data: &mut [u8]) -> Result<(), MemoryError> {
+        self.check_range(addr, data.len())?;
+        let mut done = 0;
+        for (page, offset, len) in Self::chunks(addr, data.len()) {
+            let dest = &mut data[done..done + len];
+            match self.pages.get(&page) {
+                Some(page) => dest.copy_from_slice(&page[offset..offset + len]),
+                None => dest.fill(0),
+            }
+            done += len;
+        }
+        Ok(())
+    }
+
+    pub fn write(&mut self, addr: u64, data: &[u8]) -> Result<(), MemoryError> {
+        self.check_range(addr, data.len())?;
+        let mut done = 0;
+        for (page, offset, len) in Self::chunks(addr, data.len()) {
+            let page = self
+                .pages
+                .entry(page)
+                .or_insert_with(|| Box::new([0; PAGE_SIZE]));
+            page[offset..offset + len].copy_from_slice(&data[done..done + len]);
+            done += len;
+        }
+        Ok(())
+    }
+
+    /// Zeroes `[addr, addr + len)`, releasing whole pages instead of allocating them.
+    pub fn zero(&mut self, addr: u64, len: u64) -> Result<(), MemoryError> {
+        let len = usize::try_from(len).map_err(|_| MemoryError::Unmapped(addr))?;
+        self.check_range(addr, len)?;
+        for (page, offset, len) in Self::chunks(addr, len) {
+            if len == PAGE_SIZE {
+                self.pages.remove(&page);
+            } else if let Some(page) = self.pages.get_mut(&page) {
+                page[offset..offset + len].fill(0);
+            }
+        }
+        Ok(())
+    }
+
+    fn load<const N: usize>(&self, addr: u64) -> Result<[u8; N], MemoryError> {
+        let mut data = [0; N];
+        self.read(addr, &mut data)?;
+        Ok(data)
+    }
+
+    pub fn read_u8(&self, addr: u64) -> Result<u8, MemoryError> {
+        Ok(u8::from_le_bytes(self.load(addr)?))
+    }
+    pub fn read_u16(&self, addr: u64) -> Result<u16, MemoryError> {
+        Ok(u16::from_le_bytes(self.load(addr)?))
+    }
+    pub fn read_u32(&self, addr: u64) -> Result<u32, MemoryError> {
+        Ok(u32::from_le_bytes(self.load(addr)?))
+    }
+    pub fn read_u64(&self, addr: u64) -> Result<u64, MemoryError> {
+        Ok(u64::from_le_bytes(self.load(addr)?))
+    }
+    pub fn read_u128(&self, addr: u64) -> Result<u128, MemoryError> {
+        Ok(u128::from_le_bytes(self.load(addr)?))
+    }
+
+    pub fn write_u8(&mut self, addr: u64, value: u8) -> Result<(), MemoryError> {
+        self.write(addr, &value.to_le_bytes())
+    }
+    pub fn write_u16(&mut self, addr: u64, value: u16) -> Result<(), MemoryError> {
+        self.write(addr, &value.to_le_bytes())
+    }
+    pub fn write_u32(&mut self, addr: u64, value: u32) -> Result<(), MemoryError> {
+        self.write(addr, &value.to_le_bytes())
+    }
+    pub fn write_u64(&mut self, addr: u64, value: u64) -> Result<(), MemoryError> {
+        self.write(addr, &value.to_le_bytes())
+    }
+    pub fn write_u128(&mut self, addr: u64, value: u128) -> Result<(), MemoryError> {
+        self.write(addr, &value.to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: u64 = 0x8000_0000;
+
+    #[test]
+    fn pages_are_allocated_on_first_write() {
+        let mut memory = Memory::new(BASE, 1 << 30);
+        assert_eq!(memory.read_u64(BASE + 0x1000), Ok(0));
+        assert!(memory.pages.is_empty());
+
+        // A write across a page boundary allocates both pages.
+        memory.write_u32(BASE + 0xffe, 0xdead_beef).unwrap();
+        assert_eq!(memory.pages.len(), 2);
+        assert_eq!(memory.read_u32(BASE + 0xffe), Ok(0xdead_beef));
+        assert_eq!(memory.read_u16(BASE + 0x1000), Ok(0xdead));
+
+        // Zeroing a whole page frees it; a partial page is cleared in place.
+        memory.zero(BASE + 0x1000, 0x1000).unwrap();
+        assert_eq!(memory.pages.len(), 1);
+        memory.zero(BASE + 0xfff, 1).unwrap();
+        assert_eq!(memory.read_u16(BASE + 0xffe), Ok(0x00ef));
+    }
+
+    #[test]
+    fn accesses_outside_ram_are_unmapped() {
+        let mut memory = Memory::new(BASE, 0x2000);
+        assert_eq!(
+            memory.write_u32(BASE + 0x1ffe, 0),
+            Err(MemoryError::Unmapped(BASE + 0x1ffe))
+        );
+        assert_eq!(
+            memory.read_u8(BASE - 1),
+            Err(MemoryError::Unmapped(BASE - 1))
+        );
+        assert_eq!(
+            memory.read_u64(u64::MAX - 3),
+            Err(MemoryError::Unmapped(u64::MAX - 3))
+        );
+        assert!(memory.pages.is_empty());
+    }
+}
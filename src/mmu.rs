@@ -1,47 +1,378 @@
-use goblin::{error::Result, Object};
-use std::fs;
-use std::path::Path;
+use crate::{
+    csr::{MSTATUS_MXR, MSTATUS_SUM},
+    processor::{Privilege, State, XLEN},
+    trap::Exception,
+};
 
-use crate::processor::State;
+pub const PAGE_SHIFT: u64 = 12;
+pub const PAGE_SIZE: u64 = 1 << PAGE_SHIFT;
+
+const PTE_V: u64 = 1 << 0;
+const PTE_R: u64 = 1 << 1;
+const PTE_W: u64 = 1 << 2;
+const PTE_X: u64 = 1 << 3;
+const PTE_U: u64 = 1 << 4;
+const PTE_A: u64 = 1 << 6;
+const PTE_D: u64 = 1 << 7;
+/// Bits 63:54 of an Sv39/48/57 PTE belong to extensions (Svpbmt, Svnapot) not implemented here.
+const PTE_RESERVED: u64 = 0x3ff << 54;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AccessType {
+    Fetch,
+    Load,
+    Store,
+}
+impl AccessType {
+    fn page_fault(&self, vaddr: u64) -> Exception {
+        match self {
+            AccessType::Fetch => Exception::InstructionPageFault(vaddr),
+            AccessType::Load => Exception::LoadPageFault(vaddr),
+            AccessType::Store => Exception::StorePageFault(vaddr),
+        }
+    }
+    fn access_fault(&self, vaddr: u64) -> Exception {
+        match self {
+            AccessType::Fetch => Exception::InstructionAccessFault(vaddr),
+            AccessType::Load => Exception::LoadAccessFault(vaddr),
+            AccessType::Store => Exception::StoreAccessFault(vaddr),
+        }
+    }
+}
+
+/// Shape of a page-table format selected by `satp.MODE`.
+struct PagingMode {
+    levels: u64,
+    vpn_bits: u64,
+    pte_size: u64,
+}
+const SV32: PagingMode = PagingMode {
+    levels: 2,
+    vpn_bits: 10,
+    pte_size: 4,
+};
+const SV39: PagingMode = PagingMode {
+    levels: 3,
+    vpn_bits: 9,
+    pte_size: 8,
+};
+const SV48: PagingMode = PagingMode {
+    levels: 4,
+    vpn_bits: 9,
+    pte_size: 8,
+};
+const SV57: PagingMode = PagingMode {
+    levels: 5,
+    vpn_bits: 9,
+    pte_size: 8,
+};
 
 impl State {
-    pub fn read_elf(&mut self) -> Result<()> {
-        let path = Path::new("./test/test.out");
-        let buffer = fs::read(path)?;
-        match Object::parse(&buffer)? {
-            Object::Elf(elf) => {
-                // println!("elf: {:#?}", &elf);
-                let shdr_strtab = &elf.shdr_strtab;
-                for section in &elf.section_headers {
-                    println!(
-                    "elf.section_headers = {:#?}, file_offset = {:#x}, size = {:#x}, addr = {:#x}",
-                    &shdr_strtab[section.sh_name],
-                    section.sh_offset,
-                    section.sh_size,
-                    section.sh_addr
-                );
-                    for idx in 0..section.sh_size {
-                        let offset = idx + section.sh_offset;
-                        self.memory
-                            .insert(section.sh_addr + idx, buffer[offset as usize]);
-                    }
-                }
+    fn paging_mode(&self) -> Option<PagingMode> {
+        let satp = self.satp as u64;
+        match self.xlen {
+            XLEN::RV32 => (satp >> 31 & 1 == 1).then_some(SV32),
+            XLEN::RV64 | XLEN::RV128 => match satp >> 60 {
+                8 => Some(SV39),
+                9 => Some(SV48),
+                10 => Some(SV57),
+                _ => None,
+            },
+        }
+    }
+
+    /// Translates a virtual address to a physical one, walking the page table named by `satp`
+    /// and setting the accessed/dirty bits of the leaf PTE.
+    pub fn translate(&mut self, vaddr: u64, access: AccessType) -> Result<u64, Exception> {
+        let prv = match access {
+            AccessType::Fetch => self.prv,
+            AccessType::Load | AccessType::Store => self.data_privilege(),
+        };
+        let Some(mode) = self.paging_mode().filter(|_| prv != Privilege::Machine) else {
+            return Ok(vaddr);
+        };
+        let page_fault = access.page_fault(vaddr);
 
-                self.pc = elf.header.e_entry as i64;
+        let va_bits = PAGE_SHIFT + mode.levels * mode.vpn_bits;
+        if self.xlen != XLEN::RV32 {
+            // The unused upper bits must all equal the most significant translated bit.
+            let upper = (vaddr as i64) >> (va_bits - 1);
+            if upper != 0 && upper != -1 {
+                return Err(page_fault);
             }
-            Object::PE(pe) => {
-                println!("pe: {:#?}", &pe);
+        }
+
+        let (ppn_mask, satp_ppn) = match self.xlen {
+            XLEN::RV32 => ((1 << 22) - 1, self.satp as u64 & ((1 << 22) - 1)),
+            XLEN::RV64 | XLEN::RV128 => ((1 << 44) - 1, self.satp as u64 & ((1 << 44) - 1)),
+        };
+        let mstatus = self.mstatus as u64;
+        let mut table = satp_ppn << PAGE_SHIFT;
+        for level in (0..mode.levels).rev() {
+            let shift = PAGE_SHIFT + level * mode.vpn_bits;
+            let vpn = vaddr >> shift & ((1 << mode.vpn_bits) - 1);
+            let pte_addr = table + vpn * mode.pte_size;
+            let pte = match mode.pte_size {
+                4 => self.memory.read_u32(pte_addr).map(u64::from),
+                _ => self.memory.read_u64(pte_addr),
+            };
+            let Ok(mut pte) = pte else {
+                return Err(access.access_fault(vaddr));
+            };
+
+            if pte & PTE_V == 0
+                || (pte & PTE_R == 0 && pte & PTE_W != 0)
+                || (mode.pte_size == 8 && pte & PTE_RESERVED != 0)
+            {
+                return Err(page_fault);
             }
-            Object::Mach(mach) => {
-                println!("mach: {:#?}", &mach);
+            let ppn = pte >> 10 & ppn_mask;
+            if pte & (PTE_R | PTE_X) == 0 {
+                // A pointer to the next level of the table, where D, A and U are reserved.
+                if level == 0 || pte & (PTE_D | PTE_A | PTE_U) != 0 {
+                    return Err(page_fault);
+                }
+                table = ppn << PAGE_SHIFT;
+                continue;
             }
-            Object::Archive(archive) => {
-                println!("archive: {:#?}", &archive);
+
+            let user_page = pte & PTE_U != 0;
+            let privilege_ok = match prv {
+                Privilege::User => user_page,
+                Privilege::Supervisor => {
+                    !user_page || (access != AccessType::Fetch && mstatus & MSTATUS_SUM != 0)
+                }
+                Privilege::Machine => true,
+            };
+            let permission_ok = match access {
+                AccessType::Fetch => pte & PTE_X != 0,
+                AccessType::Load => {
+                    pte & PTE_R != 0 || (mstatus & MSTATUS_MXR != 0 && pte & PTE_X != 0)
+                }
+                AccessType::Store => pte & PTE_W != 0,
+            };
+            // Superpages must be aligned to their own size.
+            let superpage_mask = (1 << (level * mode.vpn_bits)) - 1;
+            if !privilege_ok || !permission_ok || ppn & superpage_mask != 0 {
+                return Err(page_fault);
             }
-            Object::Unknown(magic) => {
-                println!("unknown magic: {:#x}", magic)
+
+            let updated = pte
+                | PTE_A
+                | if access == AccessType::Store {
+                    PTE_D
+                } else {
+                    0
+                };
+            if updated != pte {
+                pte = updated;
+                let written = match mode.pte_size {
+                    4 => self.memory.write_u32(pte_addr, pte as u32),
+                    _ => self.memory.write_u64(pte_addr, pte),
+                };
+                if written.is_err() {
+                    return Err(access.access_fault(vaddr));
+                }
             }
+
+            let offset = vaddr & ((1 << shift) - 1);
+            return Ok((ppn & !superpage_mask) << PAGE_SHIFT | offset);
         }
-        Ok(())
+        unreachable!("the walk ends at a leaf or faults by level 0")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        csr::MSTATUS_MPRV,
+        machine::{
+            tests::{machine, BASE},
+            Machine,
+        },
+        processor::XprName,
+    };
+
+    const ROOT: u64 = BASE + 0x1_0000;
+    const MID: u64 = BASE + 0x1_1000;
+    const LEAF: u64 = BASE + 0x1_2000;
+    const PAGE: u64 = BASE + 0x2_0000;
+    /// VPN[2] = 1, VPN[1] = 0 and VPN[0] = 1 under Sv39.
+    const VADDR: u64 = 0x4000_1000;
+
+    fn pte(addr: u64, flags: u64) -> u64 {
+        addr >> PAGE_SHIFT << 10 | flags
+    }
+
+    /// An S-mode Sv39 state whose `VADDR` page maps to `PAGE` with the given leaf flags.
+    fn sv39(code: &[u32], flags: u64) -> Machine {
+        let mut machine = machine(code);
+        machine
+            .state
+            .memory
+            .write_u64(ROOT + 8, pte(MID, PTE_V))
+            .unwrap();
+        machine
+            .state
+            .memory
+            .write_u64(MID, pte(LEAF, PTE_V))
+            .unwrap();
+        machine
+            .state
+            .memory
+            .write_u64(LEAF + 8, pte(PAGE, flags))
+            .unwrap();
+        machine.state.satp = (8 << 60 | ROOT >> PAGE_SHIFT) as i64;
+        machine.state.prv = Privilege::Supervisor;
+        machine
+    }
+
+    #[test]
+    fn sv39_walk_sets_accessed_and_dirty() {
+        let mut machine = sv39(
+            &[
+                0x0005_b503, // ld a0, 0(a1)
+                0x00a5_b423, // sd a0, 8(a1)
+            ],
+            PTE_V | PTE_R | PTE_W,
+        );
+        // Fetch in M-mode, but load and store through the page table as S-mode would.
+        machine.state.prv = Privilege::Machine;
+        machine.state.mstatus = (MSTATUS_MPRV | 1 << 11) as i64;
+        machine.set_reg(XprName::a1, (VADDR + 0x10) as i64);
+        machine.state.memory.write_u64(PAGE + 0x10, 42).unwrap();
+
+        machine.run(1);
+        assert_eq!(machine.reg(XprName::a0), 42);
+        assert_eq!(
+            machine.state.memory.read_u64(LEAF + 8).unwrap() & (PTE_A | PTE_D),
+            PTE_A
+        );
+        machine.run(1);
+        assert_eq!(machine.state.memory.read_u64(PAGE + 0x18).unwrap(), 42);
+        assert_eq!(
+            machine.state.memory.read_u64(LEAF + 8).unwrap() & (PTE_A | PTE_D),
+            PTE_A | PTE_D
+        );
+    }
+
+    #[test]
+    fn sv39_faults_on_misaligned_superpages_and_noncanonical_addresses() {
+        let mut machine = sv39(&[], PTE_V | PTE_R | PTE_A);
+        assert_eq!(
+            machine.state.translate(VADDR + 0x234, AccessType::Load),
+            Ok(PAGE + 0x234)
+        );
+        // A 1 GiB leaf whose PPN is not 1 GiB-aligned.
+        let gigapage = 0x8000_0000;
+        machine
+            .state
+            .memory
+            .write_u64(ROOT + 16, pte(PAGE, PTE_V | PTE_R | PTE_A))
+            .unwrap();
+        assert_eq!(
+            machine.state.translate(gigapage, AccessType::Load),
+            Err(Exception::LoadPageFault(gigapage))
+        );
+        // Bits 63:39 must copy bit 38, even though the low bits name a mapped page.
+        let noncanonical = VADDR | 1 << 40;
+        assert_eq!(
+            machine.state.translate(noncanonical, AccessType::Store),
+            Err(Exception::StorePageFault(noncanonical))
+        );
+    }
+
+    #[test]
+    fn sv39_rejects_leaf_bits_in_non_leaf_ptes() {
+        for flag in [PTE_A, PTE_D, PTE_U] {
+            let mut machine = sv39(&[], PTE_V | PTE_R | PTE_W | PTE_A | PTE_D);
+            assert_eq!(machine.state.translate(VADDR, AccessType::Store), Ok(PAGE));
+            machine
+                .state
+                .memory
+                .write_u64(MID, pte(LEAF, PTE_V | flag))
+                .unwrap();
+            assert_eq!(
+                machine.state.translate(VADDR, AccessType::Load),
+                Err(Exception::LoadPageFault(VADDR))
+            );
+            assert_eq!(
+                machine.state.translate(VADDR, AccessType::Store),
+                Err(Exception::StorePageFault(VADDR))
+            );
+        }
+    }
+
+    #[test]
+    fn sv39_enforces_user_pages_sum_and_mxr() {
+        let mut machine = sv39(&[], PTE_V | PTE_R | PTE_X | PTE_U | PTE_A);
+        let load_fault = Err(Exception::LoadPageFault(VADDR));
+        // S-mode may load from a user page only with SUM, and never execute from one.
+        assert_eq!(machine.state.translate(VADDR, AccessType::Load), load_fault);
+        machine.state.mstatus = MSTATUS_SUM as i64;
+        assert_eq!(machine.state.translate(VADDR, AccessType::Load), Ok(PAGE));
+        assert_eq!(
+            machine.state.translate(VADDR, AccessType::Fetch),
+            Err(Exception::InstructionPageFault(VADDR))
+        );
+
+        // U-mode may not touch a supervisor page.
+        machine
+            .state
+            .memory
+            .write_u64(LEAF + 8, pte(PAGE, PTE_V | PTE_X | PTE_A))
+            .unwrap();
+        assert_eq!(machine.state.translate(VADDR, AccessType::Fetch), Ok(PAGE));
+        machine.state.prv = Privilege::User;
+        assert_eq!(
+            machine.state.translate(VADDR, AccessType::Fetch),
+            Err(Exception::InstructionPageFault(VADDR))
+        );
+
+        // An execute-only page is readable only with MXR.
+        machine.state.prv = Privilege::Supervisor;
+        assert_eq!(machine.state.translate(VADDR, AccessType::Load), load_fault);
+        machine.state.mstatus |= MSTATUS_MXR as i64;
+        assert_eq!(machine.state.translate(VADDR, AccessType::Load), Ok(PAGE));
+    }
+
+    #[test]
+    fn sv32_walks_two_levels_of_four_byte_ptes() {
+        let mut machine = machine(&[]);
+        machine.state.xlen = XLEN::RV32;
+        let leaf = pte(PAGE, PTE_V | PTE_R | PTE_W | PTE_A) as u32;
+        machine
+            .state
+            .memory
+            .write_u32(ROOT + 4, pte(LEAF, PTE_V) as u32)
+            .unwrap();
+        machine.state.memory.write_u32(LEAF + 4, leaf).unwrap();
+        // A 4 MiB megapage over the base of RAM.
+        let megapage = pte(BASE, PTE_V | PTE_R | PTE_A) as u32;
+        machine.state.memory.write_u32(ROOT + 8, megapage).unwrap();
+        machine.state.satp = (1 << 31 | ROOT >> PAGE_SHIFT) as i64;
+        machine.state.prv = Privilege::Supervisor;
+
+        assert_eq!(
+            machine.state.translate(0x0040_1abc, AccessType::Load),
+            Ok(PAGE + 0xabc)
+        );
+        assert_eq!(
+            machine.state.translate(0x0080_1234, AccessType::Load),
+            Ok(BASE + 0x1234)
+        );
+        assert_eq!(
+            machine.state.translate(0x0080_1234, AccessType::Store),
+            Err(Exception::StorePageFault(0x0080_1234))
+        );
+        assert_eq!(
+            machine.state.translate(0x0040_1000, AccessType::Store),
+            Ok(PAGE)
+        );
+        assert_eq!(
+            machine.state.memory.read_u32(LEAF + 4),
+            Ok(leaf | PTE_D as u32)
+        );
     }
 }
@@ -1,16 +1,40 @@
-use std::collections::HashMap;
-
 use enumn::N;
 use strum_macros::EnumString;
 
-use crate::{error::SimResult, instructions::Instruction, utils::x};
+use crate::{
+    csr::{misa_extensions, MSTATUS_FS, MSTATUS_MPP, MSTATUS_MPRV, MSTATUS_VS},
+    decode::InstructionRaw,
+    htif::Htif,
+    isa::Isa,
+    memory::{Memory, Permissions, DEFAULT_RAM_BASE, DEFAULT_RAM_SIZE},
+    mmu::{AccessType, PAGE_SHIFT},
+    softfloat::{RoundingMode, F32},
+    syscall::Syscalls,
+    trap::{get_field, Exception},
+    utils::x,
+};
 
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum XLEN {
     RV32,
     RV64,
     RV128,
 }
 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, N, Clone, Copy)]
+#[repr(u8)]
+pub enum Privilege {
+    User = 0,
+    Supervisor = 1,
+    Machine = 3,
+}
+impl Privilege {
+    /// Decodes an MPP/SPP field; the reserved level 2 is treated as U-mode.
+    pub fn from_bits(value: u64) -> Self {
+        Self::n(value as u8).unwrap_or(Privilege::User)
+    }
+}
+
 #[derive(Debug, PartialEq, EnumString, N, Clone, Copy)]
 pub enum XprName {
     #[strum(serialize = "zero")]
@@ -78,9 +102,9 @@ pub enum XprName {
     #[strum(serialize = "t6")]
     t6,
 }
-impl Into<usize> for XprName {
-    fn into(self) -> usize {
-        self as usize
+impl From<XprName> for usize {
+    fn from(value: XprName) -> Self {
+        value as usize
     }
 }
 impl XprName {
@@ -89,7 +113,7 @@ impl XprName {
     }
 }
 
-#[derive(Debug, PartialEq, EnumString, N)]
+#[derive(Debug, PartialEq, EnumString, N, Clone, Copy)]
 pub enum FprName {
     #[strum(serialize = "ft0")]
     ft0,
@@ -156,8 +180,13 @@ pub enum FprName {
     #[strum(serialize = "ft11")]
     ft11,
 }
+impl FprName {
+    pub fn from_num(value: i64) -> Self {
+        Self::n(value).unwrap()
+    }
+}
 
-#[derive(Debug, PartialEq, EnumString)]
+#[derive(Debug, PartialEq, EnumString, N, Clone, Copy)]
 pub enum VR {
     #[strum(serialize = "v0")]
     v0,
@@ -224,11 +253,21 @@ pub enum VR {
     #[strum(serialize = "v31")]
     v31,
 }
+impl VR {
+    pub fn from_num(value: i64) -> Self {
+        Self::n(value).unwrap()
+    }
+}
 
-type Reg = i64;
+pub type Reg = i64;
 pub struct XPR {
     data: [Reg; 32],
 }
+impl Default for XPR {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl XPR {
     pub fn new() -> Self {
         XPR { data: [0; 32] }
@@ -237,15 +276,28 @@ impl XPR {
         self.data[reg.into()]
     }
     pub fn set<N: Into<usize>>(&mut self, reg: N, value: Reg) {
-        self.data[reg.into()] = value;
+        // x0 is hardwired to zero, so writes to it are discarded.
+        let reg = reg.into();
+        if reg != 0 {
+            self.data[reg] = value;
+        }
     }
 }
 
-type FReg = f64;
-struct FPR {
+/// Raw register bits; single-precision values are NaN-boxed in the upper half.
+type FReg = u64;
+pub struct FPR {
     data: [FReg; 32],
 }
+impl Default for FPR {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl FPR {
+    pub fn new() -> Self {
+        FPR { data: [0; 32] }
+    }
     pub fn get(&self, name: FprName) -> FReg {
         self.data[name as usize]
     }
@@ -254,13 +306,79 @@ impl FPR {
     }
 }
 
+/// Vector registers as little-endian bytes, so that a register group is one contiguous run of
+/// elements. Empty when no vector extension is enabled.
+pub struct VPR {
+    vlenb: usize,
+    data: Vec<u8>,
+}
+impl Default for VPR {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+impl VPR {
+    pub fn new(vlen: u32) -> Self {
+        let vlenb = vlen as usize / 8;
+        VPR {
+            vlenb,
+            data: vec![0; vlenb * 32],
+        }
+    }
+    /// VLEN in bytes.
+    pub fn vlenb(&self) -> usize {
+        self.vlenb
+    }
+    /// Element `index` of `eew` bits, counted from the start of the group at `vr`.
+    pub fn get(&self, vr: VR, index: usize, eew: u32) -> u64 {
+        let size = eew as usize / 8;
+        let start = vr as usize * self.vlenb + index * size;
+        let mut bytes = [0; 8];
+        bytes[..size].copy_from_slice(&self.data[start..start + size]);
+        u64::from_le_bytes(bytes)
+    }
+    pub fn set(&mut self, vr: VR, index: usize, eew: u32, value: u64) {
+        let size = eew as usize / 8;
+        let start = vr as usize * self.vlenb + index * size;
+        self.data[start..start + size].copy_from_slice(&value.to_le_bytes()[..size]);
+    }
+    /// Bit `index` of a mask register.
+    pub fn mask(&self, vr: VR, index: usize) -> bool {
+        self.data[vr as usize * self.vlenb + index / 8] >> (index % 8) & 1 != 0
+    }
+    pub fn set_mask(&mut self, vr: VR, index: usize, value: bool) {
+        let byte = &mut self.data[vr as usize * self.vlenb + index / 8];
+        *byte = *byte & !(1 << (index % 8)) | (value as u8) << (index % 8);
+    }
+}
+
+/// Starting value of `State::entropy` unless the machine is given a seed.
+pub const DEFAULT_ENTROPY_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
 pub struct State {
     pub xlen: XLEN,
 
     pub pc: Reg,
     pub regs: XPR,
+    pub fregs: FPR,
+    pub fflags: Reg,
+    pub frm: Reg,
+    /// Base of the Zcmt jump table; the mode field is always 0.
+    pub jvt: Reg,
+    pub vregs: VPR,
+    /// Holds vill in bit XLEN-1, as the CSR reads.
+    pub vtype: Reg,
+    pub vl: Reg,
+    pub vstart: Reg,
+    pub vxrm: Reg,
+    pub vxsat: Reg,
+    /// Widest vector element in bits, 0 without a vector extension.
+    pub elen: u32,
+    /// The Zkr entropy source behind the seed CSR: a splitmix64 counter, so runs are
+    /// reproducible.
+    pub entropy: u64,
 
-    pub prv: Reg, // TODO: Can this be an enum instead?
+    pub prv: Privilege,
     pub misa: Reg,
     pub mstatus: Reg,
     pub mepc: Reg,
@@ -273,8 +391,8 @@ pub struct State {
     pub mip: Reg,
     pub medeleg: Reg,
     pub mideleg: Reg,
-    //   uint32_t mcounteren;
-    //   uint32_t scounteren;
+    pub mcounteren: Reg,
+    pub scounteren: Reg,
     pub sepc: Reg,
     pub stval: Reg,
     pub sscratch: Reg,
@@ -284,7 +402,24 @@ pub struct State {
 
     pub dpc: Reg,
 
-    pub memory: HashMap<u64, u8>,
+    pub memory: Memory,
+    /// Check fetches, loads and stores against the ELF segment permissions, as an OS does for
+    /// a user-mode process. Bare-metal programs may write their own text, so this is off by
+    /// default.
+    pub protect_segments: bool,
+    /// Address reserved by the last LR, cleared by any store that touches it.
+    pub reservation: Option<u64>,
+    /// Multi-letter extensions enabled by the ISA string; single letters live in `misa`.
+    pub extensions: Vec<String>,
+
+    /// Print each instruction and the register file as it executes.
+    pub trace: bool,
+    /// Set once the guest asks to exit, holding its exit status.
+    pub exit_code: Option<i64>,
+    /// User-mode system-call emulation; when absent, ECALL traps like on bare metal.
+    pub syscalls: Option<Syscalls>,
+    /// Host-Target Interface, present when the program defines `tohost`.
+    pub htif: Option<Htif>,
     //   pub dscratch0, dscratch1: Reg,
     //   dcsr_t dcsr;
     //   pub tselect: Reg,
@@ -293,14 +428,32 @@ pub struct State {
     //   bool debug_mode;
 }
 
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl State {
     pub fn new() -> Self {
         State {
             pc: 0,
             xlen: XLEN::RV64,
             regs: XPR::new(),
-            prv: 0,
-            misa: 0,
+            fregs: FPR::new(),
+            fflags: 0,
+            frm: 0,
+            jvt: 0,
+            vregs: VPR::default(),
+            vtype: 0,
+            vl: 0,
+            vstart: 0,
+            vxrm: 0,
+            vxsat: 0,
+            elen: 0,
+            entropy: DEFAULT_ENTROPY_SEED,
+            prv: Privilege::Machine,
+            misa: misa_extensions("imafdcsu") as Reg,
             mstatus: 0,
             mepc: 0,
             mtval: 0,
@@ -312,8 +465,8 @@ impl State {
             mip: 0,
             medeleg: 0,
             mideleg: 0,
-            //   uint32_t mcounteren;
-            //   uint32_t scounteren;
+            mcounteren: 0,
+            scounteren: 0,
             sepc: 0,
             stval: 0,
             sscratch: 0,
@@ -323,96 +476,463 @@ impl State {
 
             dpc: 0,
 
-            memory: HashMap::new(),
+            memory: Memory::new(DEFAULT_RAM_BASE, DEFAULT_RAM_SIZE),
+            protect_segments: false,
+            reservation: None,
+            extensions: vec![],
+
+            trace: false,
+            exit_code: None,
+            syscalls: None,
+            htif: None,
         }
     }
 
+    /// Switches to the XLEN and extensions named by an ISA string.
+    pub fn set_isa(&mut self, isa: &Isa) {
+        self.xlen = isa.xlen;
+        self.misa = isa.misa as Reg;
+        self.extensions = isa.extensions.clone();
+        self.vregs = VPR::new(isa.vlen);
+        self.elen = isa.elen;
+        // vill is set out of reset, so vector code has to start with a vsetvl.
+        self.vtype = self.vill();
+        self.vl = 0;
+        self.vstart = 0;
+    }
+    /// Whether an extension is enabled, by its single letter (`"m"`) or full name (`"zicsr"`).
+    pub fn has_extension(&self, name: &str) -> bool {
+        match name.as_bytes() {
+            [letter @ b'a'..=b'z'] => self.misa as u64 & 1 << (letter - b'a') != 0,
+            _ => self.extensions.iter().any(|extension| extension == name),
+        }
+    }
+
+    pub fn has_vector(&self) -> bool {
+        self.vregs.vlenb() != 0
+    }
+    /// VLEN in bits.
+    pub fn vlen(&self) -> u32 {
+        self.vregs.vlenb() as u32 * 8
+    }
+    /// The vtype value with only vill set.
+    pub fn vill(&self) -> Reg {
+        (1u64 << (self.xlen_bits() - 1)) as Reg
+    }
+
     pub fn print_regs(&self) {
         use XprName::*;
         let xprs = vec![
             ra, sp, gp, tp, a0, a1, a2, a3, a4, a5, a6, a7, s0, s1, s2, s3, s4, s5, s6, s7, s8, t0,
             t1, t2, t3, t4, t5, t6,
         ];
-        for i in 0..xprs.len() {
-            print!("{:?}: {:016x}\t", xprs[i], self.get_reg(xprs[i]));
+        for (i, xpr) in xprs.iter().enumerate() {
+            match self.xlen {
+                XLEN::RV32 => print!("{:?}: {:08x}\t", xpr, self.get_reg_u(*xpr)),
+                _ => print!("{:?}: {:016x}\t", xpr, self.get_reg(*xpr)),
+            }
             if i % 4 == 3 {
-                print!("\n");
+                println!();
             }
         }
     }
     pub fn get_reg(&self, xpr: XprName) -> i64 {
         self.regs.get(xpr)
     }
+    /// Reads a register as an unsigned XLEN-bit value.
+    pub fn get_reg_u(&self, xpr: XprName) -> u64 {
+        self.zext_xlen(self.regs.get(xpr))
+    }
     pub fn set_reg(&mut self, xpr: XprName, value: i64) {
-        self.regs.set(xpr, value);
-    }
-
-    pub fn get_inst(&self) -> SimResult<Box<dyn Instruction>> {
-        use crate::InstructionRaw::*;
-        let base = self.access(self.pc);
-        let inst = if x(base, 0, 2) != 0b11 {
-            let inst = u16::from_le_bytes([self.access(self.pc), self.access(self.pc + 1)]);
-            print!("{:04x?}    \t", inst);
-            B16(inst)
-        } else if x(base, 2, 3) != 0b111 {
-            let inst = u32::from_le_bytes([
-                self.access(self.pc),
-                self.access(self.pc + 1),
-                self.access(self.pc + 2),
-                self.access(self.pc + 3),
-            ]);
-            print!("{:08x?}\t", inst);
+        self.regs.set(xpr, self.sext_xlen(value));
+    }
+
+    /// Registers hold XLEN-bit values sign-extended to 64 bits, so RV32 results wrap here.
+    pub fn sext_xlen(&self, value: i64) -> i64 {
+        match self.xlen {
+            XLEN::RV32 => value as i32 as i64,
+            XLEN::RV64 | XLEN::RV128 => value,
+        }
+    }
+    pub fn zext_xlen(&self, value: i64) -> u64 {
+        match self.xlen {
+            XLEN::RV32 => value as u32 as u64,
+            XLEN::RV64 | XLEN::RV128 => value as u64,
+        }
+    }
+    pub fn xlen_bits(&self) -> u32 {
+        match self.xlen {
+            XLEN::RV32 => 32,
+            XLEN::RV64 | XLEN::RV128 => 64,
+        }
+    }
+    /// Mask applied to register-specified shift amounts.
+    pub fn shamt_mask(&self) -> i64 {
+        match self.xlen {
+            XLEN::RV32 => 0x1f,
+            XLEN::RV64 | XLEN::RV128 => 0x3f,
+        }
+    }
+
+    /// Reads a single-precision value, treating improperly NaN-boxed bits as the canonical NaN.
+    pub fn get_freg_s(&self, fpr: FprName) -> u64 {
+        let value = self.fregs.get(fpr);
+        if value >> 32 == 0xffff_ffff {
+            value & 0xffff_ffff
+        } else {
+            F32.canonical_nan()
+        }
+    }
+    pub fn set_freg_s(&mut self, fpr: FprName, value: u64) {
+        self.fregs.set(fpr, value | 0xffff_ffff_0000_0000);
+        self.mark_fs_dirty();
+    }
+    pub fn get_freg_d(&self, fpr: FprName) -> u64 {
+        self.fregs.get(fpr)
+    }
+    pub fn set_freg_d(&mut self, fpr: FprName, value: u64) {
+        self.fregs.set(fpr, value);
+        self.mark_fs_dirty();
+    }
+    pub fn accrue_fflags(&mut self, flags: u8) {
+        if flags != 0 {
+            self.fflags |= flags as Reg;
+            self.mark_fs_dirty();
+        }
+    }
+    /// With mstatus.FS Off, floating-point instructions and CSRs are illegal.
+    pub fn fp_enabled(&self) -> bool {
+        self.mstatus as u64 & MSTATUS_FS != 0
+    }
+    /// Records that the FP registers or fcsr changed, so an OS knows to save them.
+    pub fn mark_fs_dirty(&mut self) {
+        self.mstatus |= MSTATUS_FS as Reg;
+    }
+    /// With mstatus.VS Off, vector instructions and CSRs are illegal.
+    pub fn vector_enabled(&self) -> bool {
+        self.mstatus as u64 & MSTATUS_VS != 0
+    }
+    /// Records that the vector registers or CSRs changed, so an OS knows to save them.
+    pub fn mark_vs_dirty(&mut self) {
+        self.mstatus |= MSTATUS_VS as Reg;
+    }
+    pub fn set_vreg(&mut self, vr: VR, index: usize, eew: u32, value: u64) {
+        self.vregs.set(vr, index, eew, value);
+        self.mark_vs_dirty();
+    }
+    pub fn set_vmask(&mut self, vr: VR, index: usize, value: bool) {
+        self.vregs.set_mask(vr, index, value);
+        self.mark_vs_dirty();
+    }
+    /// Privilege level that loads and stores are checked against: with MPRV set, M-mode
+    /// accesses memory as if it were running at the level held in MPP.
+    pub fn data_privilege(&self) -> Privilege {
+        let mstatus = self.mstatus as u64;
+        if self.prv == Privilege::Machine && mstatus & MSTATUS_MPRV != 0 {
+            Privilege::from_bits(get_field(mstatus, MSTATUS_MPP))
+        } else {
+            self.prv
+        }
+    }
+
+    /// Resolves an instruction's rm field, where 0b111 selects the dynamic mode in `frm`.
+    /// A reserved mode makes the instruction illegal.
+    pub fn rounding_mode(&self, rm: u8) -> Result<RoundingMode, Exception> {
+        let rm = if rm == 0b111 {
+            self.frm as u64
+        } else {
+            rm as u64
+        };
+        RoundingMode::from_bits(rm).ok_or(Exception::IllegalInstruction(0))
+    }
+
+    pub(crate) fn fetch_inst(&mut self) -> Result<InstructionRaw, Exception> {
+        use InstructionRaw::*;
+        let low = self.fetch_u16(self.pc)?;
+        let inst = if x(low, 0, 2) != 0b11 {
+            if self.trace {
+                print!("{:04x?}    \t", low);
+            }
+            B16(low)
+        } else if x(low, 2, 3) != 0b111 {
+            // The upper half may sit on another page, so it is translated separately.
+            let high = self.fetch_u16(self.pc + 2)?;
+            let inst = (high as u32) << 16 | low as u32;
+            if self.trace {
+                print!("{:08x?}\t", inst);
+            }
             B32(inst)
-        } else if x(base, 5, 1) != 1 {
-            panic!("too long instruction");
-        } else if x(base, 6, 1) != 1 {
-            panic!("too long instruction");
         } else {
-            panic!("too long instruction");
+            // Longer encodings are not defined by any supported extension.
+            return Err(Exception::IllegalInstruction(low as u64));
+        };
+        Ok(inst)
+    }
+
+    fn fetch_u16(&mut self, addr: i64) -> Result<u16, Exception> {
+        let vaddr = self.zext_xlen(addr);
+        let paddr = self.translate(vaddr, AccessType::Fetch)?;
+        if !self.permits((paddr, None), 2, Permissions::X) {
+            return Err(Exception::InstructionAccessFault(vaddr));
+        }
+        self.memory
+            .read_u16(paddr)
+            .map_err(|_| Exception::InstructionAccessFault(vaddr))
+    }
+
+    /// Reads an XLEN-bit word with instruction-fetch permissions, as Zcmt jump-table lookups do.
+    pub fn fetch_xlen(&mut self, addr: i64) -> Result<u64, Exception> {
+        let mut value = 0;
+        for i in 0..self.xlen_bits() as i64 / 16 {
+            value |= (self.fetch_u16(addr.wrapping_add(i * 2))? as u64) << (i * 16);
+        }
+        Ok(value)
+    }
+
+    /// Translates every page an access touches before any of it is performed, so a fault on
+    /// the second page of a misaligned access leaves memory untouched.
+    fn translate_range(
+        &mut self,
+        vaddr: u64,
+        len: usize,
+        access: AccessType,
+    ) -> Result<(u64, Option<(usize, u64)>), Exception> {
+        let first = self.translate(vaddr, access)?;
+        let last = self.zext_xlen(vaddr.wrapping_add(len as u64 - 1) as i64);
+        if last >> PAGE_SHIFT == vaddr >> PAGE_SHIFT {
+            return Ok((first, None));
+        }
+        let split = ((last >> PAGE_SHIFT << PAGE_SHIFT).wrapping_sub(vaddr)) as usize;
+        let second = self.translate(last >> PAGE_SHIFT << PAGE_SHIFT, access)?;
+        Ok((first, Some((split, second))))
+    }
+
+    /// Checks the physical pieces of a translated access against segment permissions, when
+    /// those are enforced at all.
+    fn permits(
+        &self,
+        pages: (u64, Option<(usize, u64)>),
+        len: usize,
+        required: Permissions,
+    ) -> bool {
+        if !self.protect_segments {
+            return true;
+        }
+        match pages {
+            (paddr, None) => self.memory.permits(paddr, len, required),
+            (paddr, Some((split, second))) => {
+                self.memory.permits(paddr, split, required)
+                    && self.memory.permits(second, len - split, required)
+            }
+        }
+    }
+
+    fn load(&mut self, addr: i64, data: &mut [u8]) -> Result<(), Exception> {
+        let vaddr = self.zext_xlen(addr);
+        let pages = self.translate_range(vaddr, data.len(), AccessType::Load)?;
+        if !self.permits(pages, data.len(), Permissions::R) {
+            return Err(Exception::LoadAccessFault(vaddr));
+        }
+        let result = match pages {
+            (paddr, None) => self.memory.read(paddr, data),
+            (paddr, Some((split, second))) => {
+                let (low, high) = data.split_at_mut(split);
+                self.memory
+                    .read(paddr, low)
+                    .and_then(|_| self.memory.read(second, high))
+            }
+        };
+        result.map_err(|_| Exception::LoadAccessFault(vaddr))
+    }
+
+    pub fn access(&mut self, addr: i64) -> Result<u8, Exception> {
+        let mut data = [0; 1];
+        self.load(addr, &mut data)?;
+        Ok(data[0])
+    }
+
+    pub fn access_u16(&mut self, addr: i64) -> Result<u16, Exception> {
+        let mut data = [0; 2];
+        self.load(addr, &mut data)?;
+        Ok(u16::from_le_bytes(data))
+    }
+
+    pub fn access_u32(&mut self, addr: i64) -> Result<u32, Exception> {
+        let mut data = [0; 4];
+        self.load(addr, &mut data)?;
+        Ok(u32::from_le_bytes(data))
+    }
+
+    pub fn access_u64(&mut self, addr: i64) -> Result<u64, Exception> {
+        let mut data = [0; 8];
+        self.load(addr, &mut data)?;
+        Ok(u64::from_le_bytes(data))
+    }
+
+    /// Atomic accesses must be naturally aligned.
+    pub fn check_aligned(
+        &self,
+        addr: i64,
+        size: u64,
+        misaligned: fn(u64) -> Exception,
+    ) -> Result<(), Exception> {
+        let addr = self.zext_xlen(addr);
+        if !addr.is_multiple_of(size) {
+            return Err(misaligned(addr));
+        }
+        Ok(())
+    }
+
+    /// Redirects control flow, raising a misaligned-fetch exception if the target violates
+    /// IALIGN (16 bits with the C extension, 32 bits without).
+    pub fn jump(&mut self, target: i64) -> Result<(), Exception> {
+        let ialign_mask = if self.misa & 1 << (b'c' - b'a') != 0 {
+            0b01
+        } else {
+            0b11
+        };
+        if target & ialign_mask != 0 {
+            return Err(Exception::InstructionAddressMisaligned(
+                self.zext_xlen(target),
+            ));
+        }
+        self.pc = target;
+        Ok(())
+    }
+
+    fn store(&mut self, addr: i64, data: &[u8]) -> Result<(), Exception> {
+        let vaddr = self.zext_xlen(addr);
+        let pages = self.translate_range(vaddr, data.len(), AccessType::Store)?;
+        let mapped = match pages {
+            (paddr, None) => self.memory.is_mapped(paddr, data.len()),
+            (paddr, Some((split, second))) => {
+                self.memory.is_mapped(paddr, split)
+                    && self.memory.is_mapped(second, data.len() - split)
+            }
+        };
+        if !mapped || !self.permits(pages, data.len(), Permissions::W) {
+            return Err(Exception::StoreAccessFault(vaddr));
+        }
+        if let Some(reserved) = self.reservation {
+            if vaddr < reserved + 8 && reserved < vaddr + data.len() as u64 {
+                self.reservation = None;
+            }
+        }
+        // Both halves were checked above, so these writes cannot fail.
+        let result = match pages {
+            (paddr, None) => self.memory.write(paddr, data),
+            (paddr, Some((split, second))) => self
+                .memory
+                .write(paddr, &data[..split])
+                .and_then(|_| self.memory.write(second, &data[split..])),
         };
+        result.map_err(|_| Exception::StoreAccessFault(vaddr))
+    }
+
+    pub fn store_u8(&mut self, addr: i64, data: u8) -> Result<(), Exception> {
+        self.store(addr, &[data])
+    }
+
+    pub fn store_u16(&mut self, addr: i64, data: u16) -> Result<(), Exception> {
+        self.store(addr, &data.to_le_bytes())
+    }
+
+    pub fn store_u32(&mut self, addr: i64, data: u32) -> Result<(), Exception> {
+        self.store(addr, &data.to_le_bytes())
+    }
+
+    pub fn store_u64(&mut self, addr: i64, data: u64) -> Result<(), Exception> {
+        self.store(addr, &data.to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        csr::MSTATUS_FS_INITIAL,
+        machine::tests::{machine, BASE},
+    };
 
-        self.decode_inst(inst)
+    const ADDR: i64 = 0x8000_0000;
+
+    #[test]
+    fn user_fp_writes_show_as_dirty_to_the_kernel() {
+        let mut machine = machine(&[
+            0xf000_0553, // fmv.w.x fa0, zero
+            0x0000_0073, // ecall
+            0x1000_25f3, // stvec: csrr a1, sstatus
+        ]);
+        machine.state.prv = Privilege::User;
+        machine.state.mstatus = MSTATUS_FS_INITIAL as Reg;
+        machine.state.medeleg = 1 << 8;
+        machine.state.stvec = (BASE + 8) as i64;
+        machine.run(3);
+        assert_eq!(machine.state.prv, Privilege::Supervisor);
+        let sstatus = machine.reg(XprName::a1) as u64;
+        assert_eq!(sstatus & MSTATUS_FS, MSTATUS_FS);
+        assert_eq!(sstatus >> 63, 1);
     }
 
-    pub fn access(&self, pc: i64) -> u8 {
-        self.memory[&(pc as u64)]
+    #[test]
+    fn store_u8_round_trips() {
+        let mut state = State::new();
+        state.store_u8(ADDR, 0xa5).unwrap();
+        assert_eq!(state.access(ADDR), Ok(0xa5));
     }
 
-    pub fn access_u16(&self, addr: i64) -> u16 {
-        u16::from_le_bytes([self.access(addr), self.access(addr + 1)])
+    #[test]
+    fn store_u16_round_trips() {
+        let mut state = State::new();
+        state.store_u16(ADDR, 0xbeef).unwrap();
+        assert_eq!(state.access_u16(ADDR), Ok(0xbeef));
+        assert_eq!(state.access(ADDR), Ok(0xef));
     }
 
-    pub fn access_u32(&self, addr: i64) -> u32 {
-        u32::from_le_bytes([
-            self.access(addr),
-            self.access(addr + 1),
-            self.access(addr + 2),
-            self.access(addr + 3),
-        ])
+    #[test]
+    fn store_u32_round_trips() {
+        let mut state = State::new();
+        state.store_u32(ADDR, 0xdead_beef).unwrap();
+        assert_eq!(state.access_u32(ADDR), Ok(0xdead_beef));
+    }
+
+    #[test]
+    fn store_u64_round_trips() {
+        let mut state = State::new();
+        state.store_u64(ADDR, 0x0123_4567_89ab_cdef).unwrap();
+        assert_eq!(state.access_u64(ADDR), Ok(0x0123_4567_89ab_cdef));
+    }
+
+    #[test]
+    fn stores_overwrite_previous_data() {
+        let mut state = State::new();
+        state.store_u64(ADDR, u64::MAX).unwrap();
+        state.store_u64(ADDR, 0x1111_1111_1111_1111).unwrap();
+        assert_eq!(state.access_u64(ADDR), Ok(0x1111_1111_1111_1111));
+
+        state.store_u8(ADDR + 1, 0x22).unwrap();
+        state.store_u16(ADDR + 2, 0x3333).unwrap();
+        assert_eq!(state.access_u64(ADDR), Ok(0x1111_1111_3333_2211));
     }
 
-    pub fn access_u64(&self, addr: i64) -> u64 {
-        u64::from_le_bytes([
-            self.access(addr),
-            self.access(addr + 1),
-            self.access(addr + 2),
-            self.access(addr + 3),
-            self.access(addr + 4),
-            self.access(addr + 5),
-            self.access(addr + 6),
-            self.access(addr + 7),
-        ])
+    #[test]
+    fn stores_may_cross_a_page_boundary() {
+        let mut state = State::new();
+        state
+            .store_u64(ADDR + 0xffc, 0x0807_0605_0403_0201)
+            .unwrap();
+        assert_eq!(state.access_u32(ADDR + 0x1000), Ok(0x0807_0605));
+        assert_eq!(state.access_u64(ADDR + 0xffc), Ok(0x0807_0605_0403_0201));
     }
 
-    pub fn store_u64(&mut self, addr: i64, data: u64) {
-        let data = data.to_le_bytes();
-        *self.memory.entry(addr as u64).or_insert(0) += data[0];
-        *self.memory.entry((addr + 1) as u64).or_insert(0) += data[1];
-        *self.memory.entry((addr + 2) as u64).or_insert(0) += data[2];
-        *self.memory.entry((addr + 3) as u64).or_insert(0) += data[3];
-        *self.memory.entry((addr + 4) as u64).or_insert(0) += data[4];
-        *self.memory.entry((addr + 5) as u64).or_insert(0) += data[5];
-        *self.memory.entry((addr + 6) as u64).or_insert(0) += data[6];
-        *self.memory.entry((addr + 7) as u64).or_insert(0) += data[7];
+    #[test]
+    fn stores_outside_ram_fault() {
+        let mut state = State::new();
+        state.memory = Memory::new(ADDR as u64, 0x1000);
+        assert_eq!(
+            state.store_u32(ADDR + 0xffe, 0),
+            Err(Exception::StoreAccessFault(ADDR as u64 + 0xffe))
+        );
+        assert_eq!(state.access_u16(ADDR + 0xffe), Ok(0));
     }
 }
@@ -0,0 +1,95 @@
+use std::{fs, path::Path};
+
+use crate::{error::SimError, loader::SymbolTable, processor::State};
+
+impl State {
+    /// Writes the memory between the `begin_signature` and `end_signature` symbols to `path`,
+    /// one `granularity`-byte little-endian word per line in hex, as riscv-arch-test and
+    /// RISCOF expect.
+    pub fn dump_signature(
+        &mut self,
+        symbols: &SymbolTable,
+        path: impl AsRef<Path>,
+        granularity: u64,
+    ) -> Result<(), SimError> {
+        let symbol = |name: &str| {
+            symbols
+                .lookup(name)
+                .map(|symbol| symbol.addr)
+                .ok_or_else(|| SimError::from(format!("no {} symbol", name)))
+        };
+        let begin = symbol("begin_signature")?;
+        let end = symbol("end_signature")?;
+
+        let mut dump = String::new();
+        for addr in (begin..end).step_by(granularity as usize) {
+            let word = match granularity {
+                4 => self
+                    .access_u32(addr as i64)
+                    .map(|word| format!("{:08x}", word)),
+                8 => self
+                    .access_u64(addr as i64)
+                    .map(|word| format!("{:016x}", word)),
+                _ => {
+                    return Err(SimError::from(format!(
+                        "unsupported signature granularity {}",
+                        granularity
+                    )))
+                }
+            }
+            .map_err(|e| SimError::from(format!("cannot read the signature: {:?}", e)))?;
+            dump.push_str(&word);
+            dump.push('\n');
+        }
+        fs::write(path, dump).map_err(SimError::IoError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        loader::Symbol,
+        machine::tests::{machine, BASE},
+    };
+
+    fn signature_symbols(begin: u64, end: u64) -> SymbolTable {
+        [("begin_signature", begin), ("end_signature", end)]
+            .into_iter()
+            .map(|(name, addr)| Symbol {
+                name: name.to_string(),
+                addr,
+                size: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn signature_is_dumped_one_word_per_line() {
+        let mut machine = machine(&[]);
+        let bytes: Vec<u8> = (0..16).collect();
+        machine.write_memory(BASE + 0x100, &bytes).unwrap();
+        let symbols = signature_symbols(BASE + 0x100, BASE + 0x110);
+        let path = std::env::temp_dir().join(format!("jisaku-signature-{}", std::process::id()));
+
+        machine.state.dump_signature(&symbols, &path, 4).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "03020100\n07060504\n0b0a0908\n0f0e0d0c\n"
+        );
+        machine.state.dump_signature(&symbols, &path, 8).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "0706050403020100\n0f0e0d0c0b0a0908\n"
+        );
+        fs::remove_file(&path).unwrap();
+
+        assert!(machine.state.dump_signature(&symbols, &path, 2).is_err());
+        let symbols = signature_symbols(BASE + 0x100, BASE + 0x100);
+        machine.state.dump_signature(&symbols, &path, 4).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        fs::remove_file(&path).unwrap();
+        let symbols: SymbolTable = std::iter::empty().collect();
+        assert!(machine.state.dump_signature(&symbols, &path, 4).is_err());
+    }
+}
@@ -0,0 +1,705 @@
+//! IEEE 754 binary32/binary64 arithmetic in software.
+//!
+//! Host floating point cannot honour the dynamic rounding modes or report the
+//! accrued exception flags, so every F/D operation goes through here on raw bits.
+//! NaN results are always the canonical NaN, as RISC-V does not propagate payloads.
+use std::cmp::Ordering;
+
+pub const NV: u8 = 0x10;
+pub const DZ: u8 = 0x08;
+pub const OF: u8 = 0x04;
+pub const UF: u8 = 0x02;
+pub const NX: u8 = 0x01;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RoundingMode {
+    RNE,
+    RTZ,
+    RDN,
+    RUP,
+    RMM,
+}
+impl RoundingMode {
+    pub fn from_bits(value: u64) -> Option<Self> {
+        match value {
+            0b000 => Some(RoundingMode::RNE),
+            0b001 => Some(RoundingMode::RTZ),
+            0b010 => Some(RoundingMode::RDN),
+            0b011 => Some(RoundingMode::RUP),
+            0b100 => Some(RoundingMode::RMM),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Format {
+    exp_bits: u32,
+    frac_bits: u32,
+}
+pub const F32: Format = Format {
+    exp_bits: 8,
+    frac_bits: 23,
+};
+pub const F64: Format = Format {
+    exp_bits: 11,
+    frac_bits: 52,
+};
+
+impl Format {
+    fn bias(&self) -> i32 {
+        (1 << (self.exp_bits - 1)) - 1
+    }
+    fn precision(&self) -> u32 {
+        self.frac_bits + 1
+    }
+    pub fn sign_bit(&self) -> u64 {
+        1 << (self.exp_bits + self.frac_bits)
+    }
+    fn exp_max(&self) -> u64 {
+        (1 << self.exp_bits) - 1
+    }
+    fn frac_mask(&self) -> u64 {
+        (1 << self.frac_bits) - 1
+    }
+    pub fn canonical_nan(&self) -> u64 {
+        (self.exp_max() << self.frac_bits) | (1 << (self.frac_bits - 1))
+    }
+    fn zero(&self, sign: bool) -> u64 {
+        if sign {
+            self.sign_bit()
+        } else {
+            0
+        }
+    }
+    fn inf(&self, sign: bool) -> u64 {
+        self.zero(sign) | (self.exp_max() << self.frac_bits)
+    }
+    fn max_finite(&self, sign: bool) -> u64 {
+        self.inf(sign) - 1
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Class {
+    Zero,
+    Inf,
+    NaN {
+        signaling: bool,
+    },
+    /// The value is `sig * 2^exp`.
+    Finite {
+        sig: u128,
+        exp: i32,
+    },
+}
+
+fn unpack(fmt: Format, bits: u64) -> (bool, Class) {
+    let sign = bits & fmt.sign_bit() != 0;
+    let exp = (bits >> fmt.frac_bits) & fmt.exp_max();
+    let frac = bits & fmt.frac_mask();
+    let class = if exp == fmt.exp_max() {
+        if frac == 0 {
+            Class::Inf
+        } else {
+            Class::NaN {
+                signaling: frac >> (fmt.frac_bits - 1) == 0,
+            }
+        }
+    } else if exp == 0 {
+        if frac == 0 {
+            Class::Zero
+        } else {
+            Class::Finite {
+                sig: frac as u128,
+                exp: 1 - fmt.bias() - fmt.frac_bits as i32,
+            }
+        }
+    } else {
+        Class::Finite {
+            sig: (frac | (1 << fmt.frac_bits)) as u128,
+            exp: exp as i32 - fmt.bias() - fmt.frac_bits as i32,
+        }
+    };
+    (sign, class)
+}
+
+fn is_nan(fmt: Format, bits: u64) -> bool {
+    matches!(unpack(fmt, bits).1, Class::NaN { .. })
+}
+
+fn is_signaling(fmt: Format, bits: u64) -> bool {
+    matches!(unpack(fmt, bits).1, Class::NaN { signaling: true })
+}
+
+/// Result for an operation with at least one NaN operand.
+fn propagate_nan(fmt: Format, operands: &[u64]) -> (u64, u8) {
+    let flags = if operands.iter().any(|&v| is_signaling(fmt, v)) {
+        NV
+    } else {
+        0
+    };
+    (fmt.canonical_nan(), flags)
+}
+
+/// Shifts right, ORing any discarded bits into the lsb so rounding still sees them.
+fn shift_right_jam(sig: u128, shift: u32) -> u128 {
+    if shift == 0 {
+        sig
+    } else if shift >= 128 {
+        (sig != 0) as u128
+    } else {
+        (sig >> shift) | ((sig & ((1 << shift) - 1) != 0) as u128)
+    }
+}
+
+/// Shifts `sig` (which must be below 2^127) right by `shift` bits and rounds.
+/// Returns the rounded value and whether any nonzero bits were discarded.
+fn round_shift(sig: u128, shift: u32, sign: bool, rm: RoundingMode) -> (u128, bool) {
+    if shift == 0 {
+        return (sig, false);
+    }
+    let (kept, rem, half) = if shift >= 128 {
+        (0, sig, None)
+    } else {
+        (
+            sig >> shift,
+            sig & ((1 << shift) - 1),
+            Some(1 << (shift - 1)),
+        )
+    };
+    if rem == 0 {
+        return (kept, false);
+    }
+    let ord = half.map_or(Ordering::Less, |half| rem.cmp(&half));
+    let up = match rm {
+        RoundingMode::RNE => ord == Ordering::Greater || (ord == Ordering::Equal && kept & 1 == 1),
+        RoundingMode::RMM => ord != Ordering::Less,
+        RoundingMode::RTZ => false,
+        RoundingMode::RDN => sign,
+        RoundingMode::RUP => !sign,
+    };
+    (kept + up as u128, true)
+}
+
+/// Rounds `sig * 2^exp` to the format. Any bits lost earlier must already be jammed into `sig`.
+fn round_pack(fmt: Format, sign: bool, sig: u128, exp: i32, rm: RoundingMode) -> (u64, u8) {
+    if sig == 0 {
+        return (fmt.zero(sign), 0);
+    }
+    let p = fmt.precision() as i32;
+    let e_min = 1 - fmt.bias();
+    let msb = exp + 127 - sig.leading_zeros() as i32;
+    let mut lsb = (msb - (p - 1)).max(e_min - (p - 1));
+    let (mut kept, inexact) = if lsb > exp {
+        round_shift(sig, (lsb - exp) as u32, sign, rm)
+    } else {
+        (sig << (exp - lsb), false)
+    };
+    if kept >> p != 0 {
+        kept >>= 1;
+        lsb += 1;
+    }
+
+    let mut flags = 0;
+    if inexact {
+        flags |= NX;
+        // Tininess is detected after rounding, i.e. as if the exponent range were unbounded.
+        if msb < e_min {
+            let unbounded_lsb = msb - (p - 1);
+            let carried = unbounded_lsb > exp && {
+                let (rounded, _) = round_shift(sig, (unbounded_lsb - exp) as u32, sign, rm);
+                rounded >> p != 0
+            };
+            if !(msb == e_min - 1 && carried) {
+                flags |= UF;
+            }
+        }
+    }
+
+    if kept >> (p - 1) == 0 {
+        return (fmt.zero(sign) | kept as u64, flags);
+    }
+    let biased = (lsb + (p - 1) + fmt.bias()) as u64;
+    if biased >= fmt.exp_max() {
+        let to_inf = match rm {
+            RoundingMode::RNE | RoundingMode::RMM => true,
+            RoundingMode::RTZ => false,
+            RoundingMode::RDN => sign,
+            RoundingMode::RUP => !sign,
+        };
+        let value = if to_inf {
+            fmt.inf(sign)
+        } else {
+            fmt.max_finite(sign)
+        };
+        return (value, flags | OF | NX);
+    }
+    (
+        fmt.zero(sign) | (biased << fmt.frac_bits) | (kept as u64 & fmt.frac_mask()),
+        flags,
+    )
+}
+
+/// Moves the leading one of a nonzero significand to bit 120, leaving headroom for carries.
+fn normalize(sig: u128, exp: i32) -> (u128, i32) {
+    let shift = sig.leading_zeros() as i32 - 7;
+    if shift >= 0 {
+        (sig << shift, exp - shift)
+    } else {
+        (shift_right_jam(sig, -shift as u32), exp - shift)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn add_finite(
+    fmt: Format,
+    sign_a: bool,
+    sig_a: u128,
+    exp_a: i32,
+    sign_b: bool,
+    sig_b: u128,
+    exp_b: i32,
+    rm: RoundingMode,
+) -> (u64, u8) {
+    let (sig_a, exp_a) = normalize(sig_a, exp_a);
+    let (sig_b, exp_b) = normalize(sig_b, exp_b);
+    let ((sign_a, sig_a, exp_a), (sign_b, sig_b, exp_b)) = if exp_a >= exp_b {
+        ((sign_a, sig_a, exp_a), (sign_b, sig_b, exp_b))
+    } else {
+        ((sign_b, sig_b, exp_b), (sign_a, sig_a, exp_a))
+    };
+    let sig_b = shift_right_jam(sig_b, (exp_a - exp_b) as u32);
+    if sign_a == sign_b {
+        return round_pack(fmt, sign_a, sig_a + sig_b, exp_a, rm);
+    }
+    match sig_a.cmp(&sig_b) {
+        Ordering::Greater => round_pack(fmt, sign_a, sig_a - sig_b, exp_a, rm),
+        Ordering::Less => round_pack(fmt, sign_b, sig_b - sig_a, exp_a, rm),
+        Ordering::Equal => (fmt.zero(rm == RoundingMode::RDN), 0),
+    }
+}
+
+pub fn add(fmt: Format, a: u64, b: u64, rm: RoundingMode) -> (u64, u8) {
+    let (sign_a, class_a) = unpack(fmt, a);
+    let (sign_b, class_b) = unpack(fmt, b);
+    match (class_a, class_b) {
+        (Class::NaN { .. }, _) | (_, Class::NaN { .. }) => propagate_nan(fmt, &[a, b]),
+        (Class::Inf, Class::Inf) if sign_a != sign_b => (fmt.canonical_nan(), NV),
+        (Class::Inf, _) => (a, 0),
+        (_, Class::Inf) => (b, 0),
+        (Class::Zero, Class::Zero) if sign_a != sign_b => (fmt.zero(rm == RoundingMode::RDN), 0),
+        (Class::Zero, _) => (b, 0),
+        (_, Class::Zero) => (a, 0),
+        (
+            Class::Finite {
+                sig: sig_a,
+                exp: exp_a,
+            },
+            Class::Finite {
+                sig: sig_b,
+                exp: exp_b,
+            },
+        ) => add_finite(fmt, sign_a, sig_a, exp_a, sign_b, sig_b, exp_b, rm),
+    }
+}
+
+pub fn sub(fmt: Format, a: u64, b: u64, rm: RoundingMode) -> (u64, u8) {
+    add(fmt, a, b ^ fmt.sign_bit(), rm)
+}
+
+pub fn mul(fmt: Format, a: u64, b: u64, rm: RoundingMode) -> (u64, u8) {
+    let (sign_a, class_a) = unpack(fmt, a);
+    let (sign_b, class_b) = unpack(fmt, b);
+    let sign = sign_a != sign_b;
+    match (class_a, class_b) {
+        (Class::NaN { .. }, _) | (_, Class::NaN { .. }) => propagate_nan(fmt, &[a, b]),
+        (Class::Inf, Class::Zero) | (Class::Zero, Class::Inf) => (fmt.canonical_nan(), NV),
+        (Class::Inf, _) | (_, Class::Inf) => (fmt.inf(sign), 0),
+        (Class::Zero, _) | (_, Class::Zero) => (fmt.zero(sign), 0),
+        (
+            Class::Finite {
+                sig: sig_a,
+                exp: exp_a,
+            },
+            Class::Finite {
+                sig: sig_b,
+                exp: exp_b,
+            },
+        ) => round_pack(fmt, sign, sig_a * sig_b, exp_a + exp_b, rm),
+    }
+}
+
+pub fn div(fmt: Format, a: u64, b: u64, rm: RoundingMode) -> (u64, u8) {
+    let (sign_a, class_a) = unpack(fmt, a);
+    let (sign_b, class_b) = unpack(fmt, b);
+    let sign = sign_a != sign_b;
+    match (class_a, class_b) {
+        (Class::NaN { .. }, _) | (_, Class::NaN { .. }) => propagate_nan(fmt, &[a, b]),
+        (Class::Inf, Class::Inf) | (Class::Zero, Class::Zero) => (fmt.canonical_nan(), NV),
+        (Class::Inf, _) => (fmt.inf(sign), 0),
+        (_, Class::Inf) | (Class::Zero, _) => (fmt.zero(sign), 0),
+        (_, Class::Zero) => (fmt.inf(sign), DZ),
+        (
+            Class::Finite {
+                sig: sig_a,
+                exp: exp_a,
+            },
+            Class::Finite {
+                sig: sig_b,
+                exp: exp_b,
+            },
+        ) => {
+            // Widen the dividend so the quotient keeps plenty of bits below the rounding point.
+            let shift = sig_a.leading_zeros() - 2;
+            let num = sig_a << shift;
+            let quot = (num / sig_b) | (num % sig_b != 0) as u128;
+            round_pack(fmt, sign, quot, exp_a - shift as i32 - exp_b, rm)
+        }
+    }
+}
+
+fn isqrt(n: u128) -> u128 {
+    let mut root = (n as f64).sqrt() as u128;
+    if root > 0 {
+        root = (root + n / root) / 2;
+    }
+    while root * root > n {
+        root -= 1;
+    }
+    while (root + 1) * (root + 1) <= n {
+        root += 1;
+    }
+    root
+}
+
+pub fn sqrt(fmt: Format, a: u64, rm: RoundingMode) -> (u64, u8) {
+    let (sign, class) = unpack(fmt, a);
+    match class {
+        Class::NaN { .. } => propagate_nan(fmt, &[a]),
+        Class::Zero => (a, 0),
+        _ if sign => (fmt.canonical_nan(), NV),
+        Class::Inf => (a, 0),
+        Class::Finite { sig, exp } => {
+            // Keep the exponent even so it can be halved exactly.
+            let mut shift = sig.leading_zeros() as i32 - 3;
+            if (exp - shift) % 2 != 0 {
+                shift -= 1;
+            }
+            let num = sig << shift;
+            let root = isqrt(num);
+            let root = root | (root * root != num) as u128;
+            round_pack(fmt, false, root, (exp - shift) / 2, rm)
+        }
+    }
+}
+
+/// Computes `a * b + c` with a single rounding.
+pub fn fma(fmt: Format, a: u64, b: u64, c: u64, rm: RoundingMode) -> (u64, u8) {
+    let (sign_a, class_a) = unpack(fmt, a);
+    let (sign_b, class_b) = unpack(fmt, b);
+    let (sign_c, class_c) = unpack(fmt, c);
+    let sign_prod = sign_a != sign_b;
+    match (class_a, class_b) {
+        (Class::Inf, Class::Zero) | (Class::Zero, Class::Inf) => {
+            return (fmt.canonical_nan(), NV);
+        }
+        _ => {}
+    }
+    if is_nan(fmt, a) || is_nan(fmt, b) || is_nan(fmt, c) {
+        return propagate_nan(fmt, &[a, b, c]);
+    }
+    match (class_a, class_b, class_c) {
+        (Class::Inf, _, _) | (_, Class::Inf, _) => match class_c {
+            Class::Inf if sign_c != sign_prod => (fmt.canonical_nan(), NV),
+            _ => (fmt.inf(sign_prod), 0),
+        },
+        (_, _, Class::Inf) => (c, 0),
+        (Class::Zero, _, _) | (_, Class::Zero, _) => add(fmt, fmt.zero(sign_prod), c, rm),
+        (
+            Class::Finite {
+                sig: sig_a,
+                exp: exp_a,
+            },
+            Class::Finite {
+                sig: sig_b,
+                exp: exp_b,
+            },
+            Class::Zero,
+        ) => round_pack(fmt, sign_prod, sig_a * sig_b, exp_a + exp_b, rm),
+        (
+            Class::Finite {
+                sig: sig_a,
+                exp: exp_a,
+            },
+            Class::Finite {
+                sig: sig_b,
+                exp: exp_b,
+            },
+            Class::Finite {
+                sig: sig_c,
+                exp: exp_c,
+            },
+        ) => add_finite(
+            fmt,
+            sign_prod,
+            sig_a * sig_b,
+            exp_a + exp_b,
+            sign_c,
+            sig_c,
+            exp_c,
+            rm,
+        ),
+        _ => unreachable!(),
+    }
+}
+
+/// Orders non-NaN values, with both zeros comparing equal.
+fn order_key(fmt: Format, bits: u64) -> i128 {
+    let magnitude = (bits & !fmt.sign_bit()) as i128;
+    if bits & fmt.sign_bit() != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Quiet equality: only signaling NaNs raise the invalid flag.
+pub fn eq(fmt: Format, a: u64, b: u64) -> (bool, u8) {
+    if is_nan(fmt, a) || is_nan(fmt, b) {
+        return (false, propagate_nan(fmt, &[a, b]).1);
+    }
+    (order_key(fmt, a) == order_key(fmt, b), 0)
+}
+
+/// Signaling less-than: any NaN raises the invalid flag.
+pub fn lt(fmt: Format, a: u64, b: u64) -> (bool, u8) {
+    if is_nan(fmt, a) || is_nan(fmt, b) {
+        return (false, NV);
+    }
+    (order_key(fmt, a) < order_key(fmt, b), 0)
+}
+
+/// Signaling less-or-equal: any NaN raises the invalid flag.
+pub fn le(fmt: Format, a: u64, b: u64) -> (bool, u8) {
+    if is_nan(fmt, a) || is_nan(fmt, b) {
+        return (false, NV);
+    }
+    (order_key(fmt, a) <= order_key(fmt, b), 0)
+}
+
+fn min_max(fmt: Format, a: u64, b: u64, want_max: bool) -> (u64, u8) {
+    let flags = propagate_nan(fmt, &[a, b]).1;
+    let value = match (is_nan(fmt, a), is_nan(fmt, b)) {
+        (true, true) => fmt.canonical_nan(),
+        (true, false) => b,
+        (false, true) => a,
+        (false, false) => {
+            // -0.0 is treated as less than +0.0.
+            let a_less = match order_key(fmt, a).cmp(&order_key(fmt, b)) {
+                Ordering::Less => true,
+                Ordering::Greater => false,
+                Ordering::Equal => a & fmt.sign_bit() != 0,
+            };
+            if a_less != want_max {
+                a
+            } else {
+                b
+            }
+        }
+    };
+    (value, flags)
+}
+
+pub fn min(fmt: Format, a: u64, b: u64) -> (u64, u8) {
+    min_max(fmt, a, b, false)
+}
+
+pub fn max(fmt: Format, a: u64, b: u64) -> (u64, u8) {
+    min_max(fmt, a, b, true)
+}
+
+/// The one-hot mask written by FCLASS.
+pub fn classify(fmt: Format, a: u64) -> u64 {
+    let (sign, class) = unpack(fmt, a);
+    let subnormal = (a >> fmt.frac_bits) & fmt.exp_max() == 0;
+    let bit = match (class, sign) {
+        (Class::Inf, true) => 0,
+        (Class::Finite { .. }, true) if !subnormal => 1,
+        (Class::Finite { .. }, true) => 2,
+        (Class::Zero, true) => 3,
+        (Class::Zero, false) => 4,
+        (Class::Finite { .. }, false) if subnormal => 5,
+        (Class::Finite { .. }, false) => 6,
+        (Class::Inf, false) => 7,
+        (Class::NaN { signaling: true }, _) => 8,
+        (Class::NaN { signaling: false }, _) => 9,
+    };
+    1 << bit
+}
+
+/// Converts to a `width`-bit integer, saturating out-of-range values.
+/// The result is sign-extended to 64 bits, even for unsigned conversions.
+pub fn to_int(fmt: Format, a: u64, rm: RoundingMode, signed: bool, width: u32) -> (i64, u8) {
+    let (max, min): (u128, u128) = if signed {
+        ((1 << (width - 1)) - 1, 1 << (width - 1))
+    } else {
+        ((1 << width) - 1, 0)
+    };
+    let (sign, class) = unpack(fmt, a);
+    let (magnitude, inexact) = match class {
+        Class::NaN { .. } => (None, false),
+        Class::Inf => (None, false),
+        Class::Zero => (Some(0), false),
+        Class::Finite { sig, exp } if exp >= 0 => {
+            if exp < 64 {
+                (Some(sig << exp), false)
+            } else {
+                (None, false)
+            }
+        }
+        Class::Finite { sig, exp } => {
+            let (value, inexact) = round_shift(sig, -exp as u32, sign, rm);
+            (Some(value), inexact)
+        }
+    };
+    let in_range = |m: u128| if sign { m <= min } else { m <= max };
+    let value = match magnitude {
+        Some(m) if in_range(m) => {
+            let value = if sign {
+                (m as i128).wrapping_neg()
+            } else {
+                m as i128
+            };
+            return (
+                sign_extend(value as u64, width),
+                if inexact { NX } else { 0 },
+            );
+        }
+        _ if matches!(class, Class::NaN { .. }) || !sign => max,
+        _ => (min as i128).wrapping_neg() as u128,
+    };
+    (sign_extend(value as u64, width), NV)
+}
+
+fn sign_extend(value: u64, width: u32) -> i64 {
+    let shift = 64 - width;
+    ((value << shift) as i64) >> shift
+}
+
+/// Converts a signed or unsigned 64-bit integer.
+pub fn from_int(fmt: Format, value: u64, signed: bool, rm: RoundingMode) -> (u64, u8) {
+    let sign = signed && (value as i64) < 0;
+    let magnitude = if sign {
+        (value as i64).unsigned_abs()
+    } else {
+        value
+    };
+    round_pack(fmt, sign, magnitude as u128, 0, rm)
+}
+
+/// Converts between formats, rounding when narrowing.
+pub fn convert(from: Format, to: Format, a: u64, rm: RoundingMode) -> (u64, u8) {
+    let (sign, class) = unpack(from, a);
+    match class {
+        Class::NaN { .. } => (to.canonical_nan(), propagate_nan(from, &[a]).1),
+        Class::Inf => (to.inf(sign), 0),
+        Class::Zero => (to.zero(sign), 0),
+        Class::Finite { sig, exp } => round_pack(to, sign, sig, exp, rm),
+    }
+}
+
+/// Narrows with round-to-odd: truncate, then set the lsb if any bits were lost.
+pub fn convert_round_to_odd(from: Format, to: Format, a: u64) -> (u64, u8) {
+    let (value, flags) = convert(from, to, a, RoundingMode::RTZ);
+    if flags & NX != 0 {
+        (value | 1, flags)
+    } else {
+        (value, flags)
+    }
+}
+
+/// Biased exponent and fraction of a finite nonzero value. Subnormals are normalized, which
+/// takes their exponent to 0 or below.
+fn normalized_fields(fmt: Format, a: u64) -> (i64, u64) {
+    let mut exp = ((a >> fmt.frac_bits) & fmt.exp_max()) as i64;
+    let mut frac = a & fmt.frac_mask();
+    if exp == 0 {
+        while frac >> (fmt.frac_bits - 1) & 1 == 0 {
+            exp -= 1;
+            frac <<= 1;
+        }
+        frac = (frac << 1) & fmt.frac_mask();
+    }
+    (exp, frac)
+}
+
+/// The 7-bit estimate tables of vfrsqrt7 and vfrec7: each entry is the function at the
+/// midpoint of its input interval, rounded to nearest, with the leading one dropped.
+fn rsqrt7_table(index: u64) -> u64 {
+    // Bit 6 is the exponent's parity; an even biased exponent covers [2, 4).
+    let scale = if index >> 6 == 0 { 2.0 } else { 1.0 };
+    let x = scale * (1.0 + ((index & 0x3f) as f64 + 0.5) / 64.0);
+    (256.0 / x.sqrt()).round() as u64 - 128
+}
+fn recip7_table(index: u64) -> u64 {
+    let x = 1.0 + (index as f64 + 0.5) / 128.0;
+    (256.0 / x).round() as u64 - 128
+}
+
+/// Estimates 1/sqrt(a) to 7 bits, as vfrsqrt7 does.
+pub fn rsqrt7(fmt: Format, a: u64) -> (u64, u8) {
+    let (sign, class) = unpack(fmt, a);
+    match class {
+        Class::NaN { signaling } => (fmt.canonical_nan(), if signaling { NV } else { 0 }),
+        Class::Zero => (fmt.inf(sign), DZ),
+        _ if sign => (fmt.canonical_nan(), NV),
+        Class::Inf => (0, 0),
+        Class::Finite { .. } => {
+            let (exp, frac) = normalized_fields(fmt, a);
+            let index = ((exp & 1) as u64) << 6 | frac >> (fmt.frac_bits - 6);
+            let out_exp = (3 * fmt.bias() as i64 - 1 - exp) / 2;
+            let sig = rsqrt7_table(index) << (fmt.frac_bits - 7);
+            ((out_exp as u64) << fmt.frac_bits | sig, 0)
+        }
+    }
+}
+
+/// Estimates 1/a to 7 bits, as vfrec7 does.
+pub fn recip7(fmt: Format, a: u64, rm: RoundingMode) -> (u64, u8) {
+    let (sign, class) = unpack(fmt, a);
+    match class {
+        Class::NaN { signaling } => (fmt.canonical_nan(), if signaling { NV } else { 0 }),
+        Class::Zero => (fmt.inf(sign), DZ),
+        Class::Inf => (fmt.zero(sign), 0),
+        Class::Finite { .. } => {
+            let (exp, frac) = normalized_fields(fmt, a);
+            // The reciprocal of a small enough subnormal overflows.
+            if exp < -1 {
+                let to_max_finite = match rm {
+                    RoundingMode::RTZ => true,
+                    RoundingMode::RDN => !sign,
+                    RoundingMode::RUP => sign,
+                    RoundingMode::RNE | RoundingMode::RMM => false,
+                };
+                let value = if to_max_finite {
+                    fmt.max_finite(sign)
+                } else {
+                    fmt.inf(sign)
+                };
+                return (value, OF | NX);
+            }
+            let mut sig = recip7_table(frac >> (fmt.frac_bits - 7)) << (fmt.frac_bits - 7);
+            let mut out_exp = 2 * fmt.bias() as i64 - 1 - exp;
+            // Large inputs give a subnormal estimate, with the leading one shifted back in.
+            if out_exp <= 0 {
+                sig = sig >> 1 | 1 << (fmt.frac_bits - 1);
+                if out_exp == -1 {
+                    sig >>= 1;
+                    out_exp = 0;
+                }
+            }
+            (fmt.zero(sign) | (out_exp as u64) << fmt.frac_bits | sig, 0)
+        }
+    }
+}
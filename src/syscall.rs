@@ -0,0 +1,1022 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, Metadata, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    os::unix::fs::{DirBuilderExt, FileExt, MetadataExt, OpenOptionsExt},
+    str::FromStr,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use enumn::N;
+
+use crate::{
+    error::SimError,
+    memory::Memory,
+    mmu::PAGE_SIZE,
+    processor::{State, XprName, XLEN},
+    trap::Exception,
+    utils::trace,
+};
+
+/// Bytes reserved for the guest stack at the top of RAM; `mmap` allocates below it.
+pub const STACK_SIZE: u64 = 8 << 20;
+
+// errno values, which the RISC-V Linux ABI shares with the host.
+const EIO: i64 = 5;
+const EBADF: i64 = 9;
+const ENOMEM: i64 = 12;
+const EFAULT: i64 = 14;
+const EINVAL: i64 = 22;
+const ENOTTY: i64 = 25;
+const ESPIPE: i64 = 29;
+const ENAMETOOLONG: i64 = 36;
+const ENOSYS: i64 = 38;
+
+const AT_SYMLINK_NOFOLLOW: u64 = 0x100;
+const AT_REMOVEDIR: u64 = 0x200;
+const AT_EMPTY_PATH: u64 = 0x1000;
+
+const O_ACCMODE: u64 = 0o3;
+const O_WRONLY: u64 = 0o1;
+const O_RDWR: u64 = 0o2;
+const O_CREAT: u64 = 0o100;
+const O_EXCL: u64 = 0o200;
+const O_TRUNC: u64 = 0o1000;
+const O_APPEND: u64 = 0o2000;
+
+// newlib's own open flags, which its `open` passes through unchanged.
+const NEWLIB_O_APPEND: u64 = 0x0008;
+const NEWLIB_O_CREAT: u64 = 0x0200;
+const NEWLIB_O_TRUNC: u64 = 0x0400;
+const NEWLIB_O_EXCL: u64 = 0x0800;
+
+const MAP_FIXED: u64 = 0x10;
+const MAP_ANONYMOUS: u64 = 0x20;
+
+const CLOCK_REALTIME: u64 = 0;
+
+const STATX_TYPE: u32 = 0x1;
+const STATX_MODE: u32 = 0x2;
+const STATX_BASIC_STATS: u32 = 0x7ff;
+
+/// Longest path accepted from the guest, including the terminating NUL.
+const PATH_MAX: usize = 4096;
+/// Upper bound on the bytes moved by a single `read`/`write`; callers handle short counts.
+const IO_CHUNK: u64 = 1 << 20;
+
+/// Which operating system's system-call ABI the guest expects.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Personality {
+    Linux,
+    /// newlib's libgloss as served by riscv-pk: the Linux numbers plus the old path-based
+    /// calls from 1024 up, with newlib's open flags.
+    Newlib,
+}
+
+impl FromStr for Personality {
+    type Err = SimError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linux" => Ok(Personality::Linux),
+            "newlib" | "pk" => Ok(Personality::Newlib),
+            _ => Err(SimError::ParseError(format!(
+                "unknown syscall personality {:?}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Linux system-call numbers from the generic table that RISC-V uses.
+#[derive(Debug, PartialEq, N, Clone, Copy)]
+#[repr(u64)]
+enum LinuxSyscall {
+    getcwd = 17,
+    fcntl = 25,
+    ioctl = 29,
+    mkdirat = 34,
+    unlinkat = 35,
+    linkat = 37,
+    faccessat = 48,
+    openat = 56,
+    close = 57,
+    lseek = 62,
+    read = 63,
+    write = 64,
+    readv = 65,
+    writev = 66,
+    newfstatat = 79,
+    fstat = 80,
+    exit = 93,
+    exit_group = 94,
+    set_tid_address = 96,
+    set_robust_list = 99,
+    clock_gettime = 113,
+    sched_yield = 124,
+    rt_sigaction = 134,
+    rt_sigprocmask = 135,
+    uname = 160,
+    gettimeofday = 169,
+    getpid = 172,
+    getppid = 173,
+    getuid = 174,
+    geteuid = 175,
+    getgid = 176,
+    getegid = 177,
+    gettid = 178,
+    brk = 214,
+    munmap = 215,
+    mmap = 222,
+    mprotect = 226,
+    madvise = 233,
+    getrandom = 278,
+    statx = 291,
+    clock_gettime64 = 403,
+}
+
+/// The pre-`*at` calls that riscv-pk keeps for newlib.
+#[derive(Debug, PartialEq, N, Clone, Copy)]
+#[repr(u64)]
+enum NewlibSyscall {
+    open = 1024,
+    link = 1025,
+    unlink = 1026,
+    mkdir = 1030,
+    access = 1033,
+    stat = 1038,
+    lstat = 1039,
+    time = 1062,
+}
+
+enum Descriptor {
+    Stdin,
+    Stdout,
+    Stderr,
+    File(File),
+}
+
+/// A guest-visible result: the value for `a0`, or a positive errno that is negated on return.
+type SysResult = Result<u64, i64>;
+
+fn host_errno(e: io::Error) -> i64 {
+    e.raw_os_error().map_or(EIO, i64::from)
+}
+
+/// Host-side state of the emulated process: open files, the program break and the region
+/// handed out by `mmap`.
+pub struct Syscalls {
+    personality: Personality,
+    files: HashMap<u64, Descriptor>,
+    brk_start: u64,
+    brk: u64,
+    /// `mmap` allocates downwards from here, towards the break.
+    mmap_top: u64,
+    start: Instant,
+    /// xorshift state for `getrandom`, so runs are reproducible.
+    random: u64,
+}
+
+impl Syscalls {
+    /// Places the break after the highest loaded segment and the `mmap` area just below the
+    /// stack at the top of RAM.
+    pub fn new(personality: Personality, memory: &Memory) -> Self {
+        let image_end = memory
+            .regions()
+            .iter()
+            .map(|region| region.end)
+            .max()
+            .unwrap_or(memory.base());
+        let brk = image_end.next_multiple_of(PAGE_SIZE);
+        let files = HashMap::from([
+            (0, Descriptor::Stdin),
+            (1, Descriptor::Stdout),
+            (2, Descriptor::Stderr),
+        ]);
+        Syscalls {
+            personality,
+            files,
+            brk_start: brk,
+            brk,
+            mmap_top: memory.base() + memory.size() - STACK_SIZE,
+            start: Instant::now(),
+            random: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    /// Performs system call `number`, returning the result or a negated errno.
+    pub fn call(&mut self, state: &mut State, number: u64, args: [u64; 6]) -> i64 {
+        match self.dispatch(state, number, args) {
+            Ok(value) => value as i64,
+            Err(errno) => -errno,
+        }
+    }
+
+    fn dispatch(&mut self, state: &mut State, number: u64, args: [u64; 6]) -> SysResult {
+        if self.personality == Personality::Newlib {
+            if let Some(syscall) = NewlibSyscall::n(number) {
+                trace!(state, "syscall {:?}{:x?}", syscall, args);
+                return self.dispatch_newlib(state, syscall, args);
+            }
+        }
+        use LinuxSyscall::*;
+        let Some(syscall) = LinuxSyscall::n(number) else {
+            trace!(state, "unimplemented syscall {}", number);
+            return Err(ENOSYS);
+        };
+        trace!(state, "syscall {:?}{:x?}", syscall, args);
+        match syscall {
+            // RV32 Linux only has the calls with 64-bit time: statx and clock_gettime64
+            // replace these, and the 64-bit kernel has no clock_gettime64.
+            newfstatat | fstat | clock_gettime
+                if state.xlen == XLEN::RV32 && self.personality == Personality::Linux =>
+            {
+                Err(ENOSYS)
+            }
+            clock_gettime64 if state.xlen != XLEN::RV32 => Err(ENOSYS),
+            getcwd => self.getcwd(state, args[0], args[1]),
+            mkdirat => self.mkdirat(state, args[1], args[2]),
+            unlinkat => self.unlinkat(state, args[1], args[2]),
+            linkat => self.linkat(state, args[1], args[3]),
+            faccessat => self.faccessat(state, args[1]),
+            openat => self.openat(state, args[0], args[1], args[2], args[3]),
+            close => self.close(args[0]),
+            lseek => self.lseek(state, args[0], args[1], args[2]),
+            read => self.read(state, args[0], args[1], args[2]),
+            write => self.write(state, args[0], args[1], args[2]),
+            readv => self.readv(state, args[0], args[1], args[2]),
+            writev => self.writev(state, args[0], args[1], args[2]),
+            newfstatat => self.newfstatat(state, args[0], args[1], args[2], args[3]),
+            fstat => self.fstat(state, args[0], args[1]),
+            statx => self.statx(state, args[0], args[1], args[2], args[4]),
+            exit | exit_group => {
+                state.exit_code = Some(state.sext_xlen(args[0] as i64));
+                Ok(0)
+            }
+            clock_gettime => self.clock_gettime(state, args[0], args[1]),
+            clock_gettime64 => self.clock_gettime64(state, args[0], args[1]),
+            gettimeofday => self.gettimeofday(state, args[0]),
+            uname => self.uname(state, args[0]),
+            brk => Ok(self.brk(state, args[0])),
+            munmap => self.munmap(state, args[0], args[1]),
+            mmap => self.mmap(state, args),
+            getrandom => self.getrandom(state, args[0], args[1]),
+            // Only the terminal query reaches here in practice; the host streams are pipes
+            // as far as the guest knows.
+            ioctl => Err(ENOTTY),
+            fcntl => self.descriptor(args[0]).map(|_| 0),
+            set_tid_address | getpid | gettid => Ok(1),
+            getppid => Ok(0),
+            getuid | geteuid | getgid | getegid => Ok(0),
+            // A single-threaded guest has no signals or robust futexes to track, and memory
+            // protection beyond the loaded segments is not modelled.
+            set_robust_list | sched_yield | rt_sigaction | rt_sigprocmask | mprotect | madvise => {
+                Ok(0)
+            }
+        }
+    }
+
+    /// The old calls behave like their `*at` successors relative to the working directory.
+    fn dispatch_newlib(
+        &mut self,
+        state: &mut State,
+        syscall: NewlibSyscall,
+        args: [u64; 6],
+    ) -> SysResult {
+        use NewlibSyscall::*;
+        let cwd = state.zext_xlen(-100);
+        match syscall {
+            open => self.openat(state, cwd, args[0], args[1], args[2]),
+            link => self.linkat(state, args[0], args[1]),
+            unlink => self.unlinkat(state, args[0], 0),
+            mkdir => self.mkdirat(state, args[0], args[1]),
+            access => self.faccessat(state, args[0]),
+            stat => self.newfstatat(state, cwd, args[0], args[1], 0),
+            lstat => self.newfstatat(state, cwd, args[0], args[1], AT_SYMLINK_NOFOLLOW),
+            time => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                if args[0] != 0 {
+                    state.write_guest_long(args[0], now.as_secs())?;
+                }
+                Ok(now.as_secs())
+            }
+        }
+    }
+
+    fn descriptor(&mut self, fd: u64) -> Result<&mut Descriptor, i64> {
+        self.files.get_mut(&fd).ok_or(EBADF)
+    }
+
+    fn read(&mut self, state: &mut State, fd: u64, buf: u64, count: u64) -> SysResult {
+        let mut data = vec![0; count.min(IO_CHUNK) as usize];
+        let len = match self.descriptor(fd)? {
+            Descriptor::Stdin => io::stdin().read(&mut data),
+            Descriptor::File(file) => file.read(&mut data),
+            Descriptor::Stdout | Descriptor::Stderr => return Err(EBADF),
+        }
+        .map_err(host_errno)?;
+        state.write_guest(buf, &data[..len])?;
+        Ok(len as u64)
+    }
+
+    fn write(&mut self, state: &mut State, fd: u64, buf: u64, count: u64) -> SysResult {
+        let data = state.read_guest(buf, count.min(IO_CHUNK))?;
+        let len = match self.descriptor(fd)? {
+            Descriptor::Stdout => {
+                let mut stdout = io::stdout();
+                stdout.write_all(&data).and_then(|_| stdout.flush())
+            }
+            Descriptor::Stderr => io::stderr().write_all(&data),
+            Descriptor::File(file) => file.write_all(&data),
+            Descriptor::Stdin => return Err(EBADF),
+        }
+        .map(|_| data.len())
+        .map_err(host_errno)?;
+        Ok(len as u64)
+    }
+
+    /// Reads an array of `struct iovec { void *base; size_t len; }`.
+    fn iovecs(&self, state: &State, iov: u64, count: u64) -> Result<Vec<(u64, u64)>, i64> {
+        let long = (state.xlen_bits() / 8) as u64;
+        (0..count)
+            .map(|i| {
+                let entry = iov + i * 2 * long;
+                Ok((
+                    state.read_guest_long(entry)?,
+                    state.read_guest_long(entry + long)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn readv(&mut self, state: &mut State, fd: u64, iov: u64, count: u64) -> SysResult {
+        let mut total = 0;
+        for (base, len) in self.iovecs(state, iov, count)? {
+            let done = self.read(state, fd, base, len)?;
+            total += done;
+            if done < len {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    fn writev(&mut self, state: &mut State, fd: u64, iov: u64, count: u64) -> SysResult {
+        let mut total = 0;
+        for (base, len) in self.iovecs(state, iov, count)? {
+            total += self.write(state, fd, base, len)?;
+        }
+        Ok(total)
+    }
+
+    /// Relative paths are resolved against the host working directory whatever `dirfd` is.
+    fn openat(
+        &mut self,
+        state: &State,
+        _dirfd: u64,
+        path: u64,
+        flags: u64,
+        mode: u64,
+    ) -> SysResult {
+        let path = state.read_guest_path(path)?;
+        let flags = self.linux_open_flags(flags);
+        let mut options = OpenOptions::new();
+        match flags & O_ACCMODE {
+            O_WRONLY => options.write(true),
+            O_RDWR => options.read(true).write(true),
+            _ => options.read(true),
+        };
+        options
+            .append(flags & O_APPEND != 0)
+            .truncate(flags & O_TRUNC != 0)
+            .mode(mode as u32 & 0o7777);
+        if flags & O_CREAT != 0 {
+            if flags & O_EXCL != 0 {
+                options.create_new(true);
+            } else {
+                options.create(true);
+            }
+        }
+        let file = options.open(&path).map_err(host_errno)?;
+        let fd = (0..).find(|fd| !self.files.contains_key(fd)).unwrap();
+        self.files.insert(fd, Descriptor::File(file));
+        Ok(fd)
+    }
+
+    fn linux_open_flags(&self, flags: u64) -> u64 {
+        match self.personality {
+            Personality::Linux => flags,
+            Personality::Newlib => [
+                (NEWLIB_O_APPEND, O_APPEND),
+                (NEWLIB_O_CREAT, O_CREAT),
+                (NEWLIB_O_TRUNC, O_TRUNC),
+                (NEWLIB_O_EXCL, O_EXCL),
+            ]
+            .into_iter()
+            .filter(|(newlib, _)| flags & newlib != 0)
+            .fold(flags & O_ACCMODE, |linux, (_, flag)| linux | flag),
+        }
+    }
+
+    fn close(&mut self, fd: u64) -> SysResult {
+        self.files.remove(&fd).map(|_| 0).ok_or(EBADF)
+    }
+
+    fn lseek(&mut self, state: &State, fd: u64, offset: u64, whence: u64) -> SysResult {
+        let offset = state.sext_xlen(offset as i64);
+        let pos = match whence {
+            0 => SeekFrom::Start(offset as u64),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => return Err(EINVAL),
+        };
+        match self.descriptor(fd)? {
+            Descriptor::File(file) => file.seek(pos).map_err(host_errno),
+            _ => Err(ESPIPE),
+        }
+    }
+
+    fn fstat(&mut self, state: &mut State, fd: u64, statbuf: u64) -> SysResult {
+        let stat = match self.descriptor(fd)? {
+            Descriptor::File(file) => stat_bytes(&file.metadata().map_err(host_errno)?),
+            // A character device, like a terminal.
+            _ => {
+                let mut stat = [0; 128];
+                stat[16..20].copy_from_slice(&0o20620u32.to_le_bytes());
+                stat[56..60].copy_from_slice(&1024u32.to_le_bytes());
+                stat
+            }
+        };
+        state.write_guest(statbuf, &stat)?;
+        Ok(0)
+    }
+
+    fn newfstatat(
+        &mut self,
+        state: &mut State,
+        dirfd: u64,
+        path: u64,
+        statbuf: u64,
+        flags: u64,
+    ) -> SysResult {
+        let path = state.read_guest_path(path)?;
+        if path.is_empty() && flags & AT_EMPTY_PATH != 0 {
+            return self.fstat(state, dirfd, statbuf);
+        }
+        let metadata = if flags & AT_SYMLINK_NOFOLLOW != 0 {
+            fs::symlink_metadata(&path)
+        } else {
+            fs::metadata(&path)
+        };
+        let stat = stat_bytes(&metadata.map_err(host_errno)?);
+        state.write_guest(statbuf, &stat)?;
+        Ok(0)
+    }
+
+    /// The only stat call RV32 Linux has, since `struct statx` carries 64-bit timestamps.
+    fn statx(
+        &mut self,
+        state: &mut State,
+        dirfd: u64,
+        path: u64,
+        flags: u64,
+        statxbuf: u64,
+    ) -> SysResult {
+        let path = state.read_guest_path(path)?;
+        let statx = if path.is_empty() && flags & AT_EMPTY_PATH != 0 {
+            match self.descriptor(dirfd)? {
+                Descriptor::File(file) => statx_bytes(&file.metadata().map_err(host_errno)?),
+                // A character device, like a terminal.
+                _ => {
+                    let mut statx = [0; 256];
+                    statx[..4].copy_from_slice(&(STATX_TYPE | STATX_MODE).to_le_bytes());
+                    statx[4..8].copy_from_slice(&1024u32.to_le_bytes());
+                    statx[28..30].copy_from_slice(&0o20620u16.to_le_bytes());
+                    statx
+                }
+            }
+        } else {
+            statx_bytes(&fs::metadata(&path).map_err(host_errno)?)
+        };
+        state.write_guest(statxbuf, &statx)?;
+        Ok(0)
+    }
+
+    fn faccessat(&mut self, state: &State, path: u64) -> SysResult {
+        let path = state.read_guest_path(path)?;
+        fs::metadata(path).map(|_| 0).map_err(host_errno)
+    }
+
+    fn unlinkat(&mut self, state: &State, path: u64, flags: u64) -> SysResult {
+        let path = state.read_guest_path(path)?;
+        if flags & AT_REMOVEDIR != 0 {
+            fs::remove_dir(path)
+        } else {
+            fs::remove_file(path)
+        }
+        .map(|_| 0)
+        .map_err(host_errno)
+    }
+
+    fn mkdirat(&mut self, state: &State, path: u64, mode: u64) -> SysResult {
+        let path = state.read_guest_path(path)?;
+        fs::DirBuilder::new()
+            .mode(mode as u32 & 0o7777)
+            .create(path)
+            .map(|_| 0)
+            .map_err(host_errno)
+    }
+
+    fn linkat(&mut self, state: &State, old: u64, new: u64) -> SysResult {
+        let old = state.read_guest_path(old)?;
+        let new = state.read_guest_path(new)?;
+        fs::hard_link(old, new).map(|_| 0).map_err(host_errno)
+    }
+
+    fn getcwd(&mut self, state: &mut State, buf: u64, size: u64) -> SysResult {
+        let cwd = std::env::current_dir().map_err(host_errno)?;
+        let mut cwd = cwd.to_string_lossy().into_owned().into_bytes();
+        cwd.push(0);
+        if cwd.len() as u64 > size {
+            return Err(EINVAL);
+        }
+        state.write_guest(buf, &cwd)?;
+        Ok(cwd.len() as u64)
+    }
+
+    fn now(&self, clock: u64) -> (u64, u64) {
+        let now = if clock == CLOCK_REALTIME {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+        } else {
+            self.start.elapsed()
+        };
+        (now.as_secs(), now.subsec_nanos() as u64)
+    }
+
+    /// Fills a `struct timespec` of two longs.
+    fn clock_gettime(&mut self, state: &mut State, clock: u64, tp: u64) -> SysResult {
+        let (sec, nsec) = self.now(clock);
+        state.write_guest_long(tp, sec)?;
+        state.write_guest_long(tp + (state.xlen_bits() / 8) as u64, nsec)?;
+        Ok(0)
+    }
+
+    /// Fills a `struct __kernel_timespec`, whose fields are 64 bits on RV32 as well.
+    fn clock_gettime64(&mut self, state: &mut State, clock: u64, tp: u64) -> SysResult {
+        let (sec, nsec) = self.now(clock);
+        state.write_guest(tp, &sec.to_le_bytes())?;
+        state.write_guest(tp + 8, &nsec.to_le_bytes())?;
+        Ok(0)
+    }
+
+    fn gettimeofday(&mut self, state: &mut State, tv: u64) -> SysResult {
+        if tv != 0 {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            state.write_guest_long(tv, now.as_secs())?;
+            state.write_guest_long(
+                tv + (state.xlen_bits() / 8) as u64,
+                now.subsec_micros() as u64,
+            )?;
+        }
+        Ok(0)
+    }
+
+    /// Fills `struct utsname`, six 65-byte strings.
+    fn uname(&mut self, state: &mut State, buf: u64) -> SysResult {
+        let machine = match state.xlen {
+            XLEN::RV32 => "riscv32",
+            XLEN::RV64 | XLEN::RV128 => "riscv64",
+        };
+        let mut utsname = [0; 65 * 6];
+        let fields = ["Linux", "jisaku", "6.1.0", "#1", machine, "(none)"];
+        for (field, value) in utsname.chunks_mut(65).zip(fields) {
+            field[..value.len()].copy_from_slice(value.as_bytes());
+        }
+        state.write_guest(buf, &utsname)?;
+        Ok(0)
+    }
+
+    /// Moves the program break, returning the new one; on failure the break is unchanged.
+    fn brk(&mut self, state: &mut State, addr: u64) -> u64 {
+        if addr < self.brk_start || addr > self.mmap_top {
+            return self.brk;
+        }
+        // Memory given back by an earlier shrink must read as zero again.
+        if addr > self.brk && state.memory.zero(self.brk, addr - self.brk).is_err() {
+            return self.brk;
+        }
+        self.brk = addr;
+        self.brk
+    }
+
+    fn mmap(&mut self, state: &mut State, args: [u64; 6]) -> SysResult {
+        let [addr, len, _prot, flags, fd, offset] = args;
+        if len == 0 {
+            return Err(EINVAL);
+        }
+        let len = len.checked_next_multiple_of(PAGE_SIZE).ok_or(ENOMEM)?;
+        let start = if flags & MAP_FIXED != 0 {
+            if !addr.is_multiple_of(PAGE_SIZE) {
+                return Err(EINVAL);
+            }
+            addr
+        } else {
+            let start = self.mmap_top.checked_sub(len).ok_or(ENOMEM)?;
+            if start < self.brk {
+                return Err(ENOMEM);
+            }
+            self.mmap_top = start;
+            start
+        };
+        state.memory.zero(start, len).map_err(|_| ENOMEM)?;
+        if flags & MAP_ANONYMOUS == 0 {
+            // Private file mappings are copies, so later writes to the file are not seen.
+            let Descriptor::File(file) = self.descriptor(fd)? else {
+                return Err(EBADF);
+            };
+            let mut data = vec![0; len as usize];
+            let mut done = 0;
+            while done < data.len() {
+                match file.read_at(&mut data[done..], offset + done as u64) {
+                    Ok(0) => break,
+                    Ok(n) => done += n,
+                    Err(e) => return Err(host_errno(e)),
+                }
+            }
+            state.write_guest(start, &data[..done])?;
+        }
+        Ok(start)
+    }
+
+    fn munmap(&mut self, state: &mut State, addr: u64, len: u64) -> SysResult {
+        if !addr.is_multiple_of(PAGE_SIZE) {
+            return Err(EINVAL);
+        }
+        let len = len.checked_next_multiple_of(PAGE_SIZE).ok_or(EINVAL)?;
+        state.memory.zero(addr, len).map_err(|_| EINVAL)?;
+        Ok(0)
+    }
+
+    fn getrandom(&mut self, state: &mut State, buf: u64, len: u64) -> SysResult {
+        let len = len.min(IO_CHUNK);
+        let data: Vec<u8> = (0..len)
+            .map(|_| {
+                self.random ^= self.random << 13;
+                self.random ^= self.random >> 7;
+                self.random ^= self.random << 17;
+                self.random as u8
+            })
+            .collect();
+        state.write_guest(buf, &data)?;
+        Ok(len)
+    }
+}
+
+/// Encodes the generic Linux `struct stat` used by 64-bit RISC-V.
+fn stat_bytes(metadata: &Metadata) -> [u8; 128] {
+    let mut stat = [0; 128];
+    let mut put = |offset: usize, bytes: &[u8]| {
+        stat[offset..offset + bytes.len()].copy_from_slice(bytes);
+    };
+    put(0, &metadata.dev().to_le_bytes());
+    put(8, &metadata.ino().to_le_bytes());
+    put(16, &metadata.mode().to_le_bytes());
+    put(20, &(metadata.nlink() as u32).to_le_bytes());
+    put(24, &metadata.uid().to_le_bytes());
+    put(28, &metadata.gid().to_le_bytes());
+    put(32, &metadata.rdev().to_le_bytes());
+    put(48, &metadata.size().to_le_bytes());
+    put(56, &(metadata.blksize() as u32).to_le_bytes());
+    put(64, &metadata.blocks().to_le_bytes());
+    put(72, &metadata.atime().to_le_bytes());
+    put(80, &metadata.atime_nsec().to_le_bytes());
+    put(88, &metadata.mtime().to_le_bytes());
+    put(96, &metadata.mtime_nsec().to_le_bytes());
+    put(104, &metadata.ctime().to_le_bytes());
+    put(112, &metadata.ctime_nsec().to_le_bytes());
+    stat
+}
+
+/// Encodes `struct statx`, reporting the fields of `STATX_BASIC_STATS`.
+fn statx_bytes(metadata: &Metadata) -> [u8; 256] {
+    let mut statx = [0; 256];
+    let mut put = |offset: usize, bytes: &[u8]| {
+        statx[offset..offset + bytes.len()].copy_from_slice(bytes);
+    };
+    put(0, &STATX_BASIC_STATS.to_le_bytes());
+    put(4, &(metadata.blksize() as u32).to_le_bytes());
+    put(16, &(metadata.nlink() as u32).to_le_bytes());
+    put(20, &metadata.uid().to_le_bytes());
+    put(24, &metadata.gid().to_le_bytes());
+    put(28, &(metadata.mode() as u16).to_le_bytes());
+    put(32, &metadata.ino().to_le_bytes());
+    put(40, &metadata.size().to_le_bytes());
+    put(48, &metadata.blocks().to_le_bytes());
+    // `struct statx_timestamp { s64 tv_sec; u32 tv_nsec; }` for atime, then ctime and mtime
+    // after the unreported btime.
+    let times = [
+        (64, metadata.atime(), metadata.atime_nsec()),
+        (96, metadata.ctime(), metadata.ctime_nsec()),
+        (112, metadata.mtime(), metadata.mtime_nsec()),
+    ];
+    for (offset, sec, nsec) in times {
+        put(offset, &sec.to_le_bytes());
+        put(offset + 8, &(nsec as u32).to_le_bytes());
+    }
+    for (offset, dev) in [(128, metadata.rdev()), (136, metadata.dev())] {
+        let major = (dev >> 8 & 0xfff) | (dev >> 32 & !0xfff);
+        let minor = (dev & 0xff) | (dev >> 12 & !0xff);
+        put(offset, &(major as u32).to_le_bytes());
+        put(offset + 4, &(minor as u32).to_le_bytes());
+    }
+    statx
+}
+
+impl State {
+    /// Services an ECALL under user-mode emulation: the number is in `a7`, the arguments in
+    /// `a0`-`a5`, and the result or negated errno goes back in `a0`.
+    pub fn syscall(&mut self) -> Result<(), Exception> {
+        let Some(mut syscalls) = self.syscalls.take() else {
+            unreachable!("ECALL only reaches here with emulation enabled");
+        };
+        use XprName::*;
+        let number = self.get_reg_u(a7);
+        let args = [a0, a1, a2, a3, a4, a5].map(|reg| self.get_reg_u(reg));
+        let ret = syscalls.call(self, number, args);
+        self.syscalls = Some(syscalls);
+        self.set_reg(a0, ret);
+        self.pc += 4;
+        Ok(())
+    }
+
+    // User-mode emulation runs with translation off, so guest pointers are physical.
+
+    fn read_guest(&self, addr: u64, len: u64) -> Result<Vec<u8>, i64> {
+        let mut data = vec![0; len as usize];
+        self.memory.read(addr, &mut data).map_err(|_| EFAULT)?;
+        Ok(data)
+    }
+
+    fn write_guest(&mut self, addr: u64, data: &[u8]) -> Result<(), i64> {
+        self.memory.write(addr, data).map_err(|_| EFAULT)
+    }
+
+    /// Reads a C `long`, which is XLEN bits wide.
+    fn read_guest_long(&self, addr: u64) -> Result<u64, i64> {
+        let long = self.read_guest(addr, (self.xlen_bits() / 8) as u64)?;
+        Ok(long
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| value << 8 | byte as u64))
+    }
+
+    fn write_guest_long(&mut self, addr: u64, value: u64) -> Result<(), i64> {
+        let len = (self.xlen_bits() / 8) as usize;
+        self.write_guest(addr, &value.to_le_bytes()[..len])
+    }
+
+    fn read_guest_path(&self, addr: u64) -> Result<String, i64> {
+        let mut path = vec![];
+        for addr in addr..addr + PATH_MAX as u64 {
+            match self.memory.read_u8(addr).map_err(|_| EFAULT)? {
+                0 => return Ok(String::from_utf8_lossy(&path).into_owned()),
+                byte => path.push(byte),
+            }
+        }
+        Err(ENAMETOOLONG)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LinuxSyscall as Nr, *};
+
+    use crate::machine::tests::BASE;
+
+    /// Builds an emulated process with enough RAM for the stack and the `mmap` area.
+    fn process(isa: &str, personality: Personality) -> State {
+        let mut state = State::new();
+        state.set_isa(&isa.parse().unwrap());
+        state.memory = Memory::new(BASE, 16 << 20);
+        state.syscalls = Some(Syscalls::new(personality, &state.memory));
+        state
+    }
+
+    /// Issues system call `number` through the ECALL path and returns `a0` zero-extended.
+    fn syscall(state: &mut State, number: u64, args: &[u64]) -> u64 {
+        use XprName::*;
+        state.set_reg(a7, number as i64);
+        for (reg, &arg) in [a0, a1, a2, a3, a4, a5].into_iter().zip(args) {
+            state.set_reg(reg, arg as i64);
+        }
+        state.syscall().unwrap();
+        state.get_reg_u(a0)
+    }
+
+    fn write_path(state: &mut State, addr: u64, path: &std::path::Path) {
+        let mut bytes = path.to_str().unwrap().as_bytes().to_vec();
+        bytes.push(0);
+        state.memory.write(addr, &bytes).unwrap();
+    }
+
+    #[test]
+    fn files_are_written_and_stated_per_xlen() {
+        for (isa, xlen) in [("rv32imac", 32), ("rv64imac", 64)] {
+            let mut state = process(isa, Personality::Linux);
+            let path = std::env::temp_dir().join(format!(
+                "jisaku-syscall-{}-{}",
+                std::process::id(),
+                xlen
+            ));
+            write_path(&mut state, BASE + 0x100, &path);
+            state.memory.write(BASE + 0x200, b"hello").unwrap();
+            state.memory.write(BASE + 0x180, &[0]).unwrap();
+
+            let flags = O_RDWR | O_CREAT | O_TRUNC;
+            let at_fdcwd = state.zext_xlen(-100);
+            let fd = syscall(
+                &mut state,
+                Nr::openat as u64,
+                &[at_fdcwd, BASE + 0x100, flags, 0o600],
+            );
+            assert_eq!(fd, 3);
+            assert_eq!(
+                syscall(&mut state, Nr::write as u64, &[fd, BASE + 0x200, 5]),
+                5
+            );
+            let metadata = fs::metadata(&path).unwrap();
+            let (mode, size, mtime) = if xlen == 32 {
+                // RV32 Linux has no fstat; statx on the descriptor takes its place.
+                let enosys = state.zext_xlen(-ENOSYS);
+                assert_eq!(
+                    syscall(&mut state, Nr::fstat as u64, &[fd, BASE + 0x300]),
+                    enosys
+                );
+                let args = [fd, BASE + 0x180, AT_EMPTY_PATH, 0x7ff, BASE + 0x300];
+                assert_eq!(syscall(&mut state, Nr::statx as u64, &args), 0);
+                let mut statx = [0; 256];
+                state.memory.read(BASE + 0x300, &mut statx).unwrap();
+                let mask = u32::from_le_bytes(statx[..4].try_into().unwrap());
+                assert_eq!(mask, STATX_BASIC_STATS);
+                (
+                    u16::from_le_bytes(statx[28..30].try_into().unwrap()) as u32,
+                    u64::from_le_bytes(statx[40..48].try_into().unwrap()),
+                    i64::from_le_bytes(statx[112..120].try_into().unwrap()),
+                )
+            } else {
+                assert_eq!(
+                    syscall(&mut state, Nr::fstat as u64, &[fd, BASE + 0x300]),
+                    0
+                );
+                let mut stat = [0; 128];
+                state.memory.read(BASE + 0x300, &mut stat).unwrap();
+                (
+                    u32::from_le_bytes(stat[16..20].try_into().unwrap()),
+                    u64::from_le_bytes(stat[48..56].try_into().unwrap()),
+                    i64::from_le_bytes(stat[88..96].try_into().unwrap()),
+                )
+            };
+            fs::remove_file(&path).unwrap();
+            assert_eq!(mode & 0o170000, 0o100000);
+            assert_eq!(size, 5);
+            assert_eq!(mtime, metadata.mtime());
+
+            // Writing to a closed descriptor fails with EBADF.
+            assert_eq!(syscall(&mut state, Nr::close as u64, &[fd]), 0);
+            let ebadf = state.zext_xlen(-EBADF);
+            assert_eq!(
+                syscall(&mut state, Nr::write as u64, &[fd, BASE + 0x200, 5]),
+                ebadf
+            );
+        }
+    }
+
+    #[test]
+    fn brk_and_mmap_hand_out_zeroed_memory_per_xlen() {
+        for isa in ["rv32imac", "rv64imac"] {
+            let mut state = process(isa, Personality::Linux);
+            let brk = syscall(&mut state, Nr::brk as u64, &[0]);
+            assert_eq!(brk, BASE);
+            assert_eq!(
+                syscall(&mut state, Nr::brk as u64, &[brk + 0x2000]),
+                brk + 0x2000
+            );
+            state.memory.write(brk + 0x1000, &[0xff; 8]).unwrap();
+            // Shrinking and growing again hands back zeroed memory.
+            assert_eq!(
+                syscall(&mut state, Nr::brk as u64, &[brk + 0x1000]),
+                brk + 0x1000
+            );
+            assert_eq!(
+                syscall(&mut state, Nr::brk as u64, &[brk + 0x2000]),
+                brk + 0x2000
+            );
+            assert_eq!(state.memory.read_u64(brk + 0x1000), Ok(0));
+            // A break below the image is refused, leaving it where it was.
+            assert_eq!(
+                syscall(&mut state, Nr::brk as u64, &[brk - 0x1000]),
+                brk + 0x2000
+            );
+
+            let top = BASE + (16 << 20) - STACK_SIZE;
+            let anonymous = [0, 0x1800, 3, MAP_ANONYMOUS | 0x02, u64::MAX, 0];
+            let first = syscall(&mut state, Nr::mmap as u64, &anonymous);
+            assert_eq!(first, top - 0x2000);
+            let second = syscall(&mut state, Nr::mmap as u64, &anonymous);
+            assert_eq!(second, top - 0x4000);
+            assert_eq!(state.memory.read_u64(first + 0x1ff8), Ok(0));
+            let einval = state.zext_xlen(-EINVAL);
+            assert_eq!(
+                syscall(
+                    &mut state,
+                    Nr::mmap as u64,
+                    &[0, 0, 3, MAP_ANONYMOUS | 0x02, u64::MAX, 0]
+                ),
+                einval
+            );
+        }
+    }
+
+    #[test]
+    fn clock_gettime_uses_the_timespec_of_each_xlen() {
+        let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let recent = before.as_secs()..before.as_secs() + 60;
+
+        // RV64: clock_gettime fills two longs, and there is no clock_gettime64.
+        let mut state = process("rv64imac", Personality::Linux);
+        state.memory.write(BASE + 0x100, &[0xff; 24]).unwrap();
+        let args = [CLOCK_REALTIME, BASE + 0x100];
+        assert_eq!(syscall(&mut state, Nr::clock_gettime as u64, &args), 0);
+        assert!(recent.contains(&state.memory.read_u64(BASE + 0x100).unwrap()));
+        assert!(state.memory.read_u64(BASE + 0x108).unwrap() < 1_000_000_000);
+        assert_eq!(state.memory.read_u64(BASE + 0x110), Ok(u64::MAX));
+        let enosys = state.zext_xlen(-ENOSYS);
+        assert_eq!(
+            syscall(&mut state, Nr::clock_gettime64 as u64, &args),
+            enosys
+        );
+
+        // RV32: only clock_gettime64, which fills 64-bit fields despite the 32-bit longs.
+        let mut state = process("rv32imac", Personality::Linux);
+        state.memory.write(BASE + 0x100, &[0xff; 24]).unwrap();
+        let enosys = state.zext_xlen(-ENOSYS);
+        assert_eq!(syscall(&mut state, Nr::clock_gettime as u64, &args), enosys);
+        assert_eq!(syscall(&mut state, Nr::clock_gettime64 as u64, &args), 0);
+        assert!(recent.contains(&state.memory.read_u64(BASE + 0x100).unwrap()));
+        assert!(state.memory.read_u64(BASE + 0x108).unwrap() < 1_000_000_000);
+        assert_eq!(state.memory.read_u64(BASE + 0x110), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn newlib_adds_the_path_based_calls_and_its_open_flags() {
+        let mut state = process("rv64imac", Personality::Newlib);
+        let path = std::env::temp_dir().join(format!("jisaku-newlib-{}", std::process::id()));
+        write_path(&mut state, BASE + 0x100, &path);
+        state.memory.write(BASE + 0x200, b"hello").unwrap();
+
+        // newlib's O_CREAT | O_TRUNC | O_WRONLY, which Linux would read as other flags.
+        let flags = NEWLIB_O_CREAT | NEWLIB_O_TRUNC | O_WRONLY;
+        let open = NewlibSyscall::open as u64;
+        let fd = syscall(&mut state, open, &[BASE + 0x100, flags, 0o600]);
+        assert_eq!(fd, 3);
+        // The Linux numbers below 1024 are shared.
+        assert_eq!(
+            syscall(&mut state, Nr::write as u64, &[fd, BASE + 0x200, 5]),
+            5
+        );
+        assert_eq!(syscall(&mut state, Nr::close as u64, &[fd]), 0);
+
+        let stat = NewlibSyscall::stat as u64;
+        assert_eq!(syscall(&mut state, stat, &[BASE + 0x100, BASE + 0x300]), 0);
+        assert_eq!(state.memory.read_u64(BASE + 0x300 + 48), Ok(5));
+        let unlink = NewlibSyscall::unlink as u64;
+        assert_eq!(syscall(&mut state, unlink, &[BASE + 0x100]), 0);
+        assert!(!path.exists());
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let time = syscall(&mut state, NewlibSyscall::time as u64, &[BASE + 0x400]);
+        assert!((now.as_secs()..now.as_secs() + 60).contains(&time));
+        assert_eq!(state.memory.read_u64(BASE + 0x400), Ok(time));
+
+        // riscv-pk keeps fstat on RV32, with the same 64-bit `kernel_stat` layout.
+        let mut state = process("rv32imac", Personality::Newlib);
+        assert_eq!(syscall(&mut state, Nr::fstat as u64, &[1, BASE + 0x300]), 0);
+        assert_eq!(state.memory.read_u32(BASE + 0x300 + 56), Ok(1024));
+
+        // Linux has nothing at 1024 and up.
+        let mut state = process("rv64imac", Personality::Linux);
+        write_path(&mut state, BASE + 0x100, &path);
+        let enosys = state.zext_xlen(-ENOSYS);
+        assert_eq!(
+            syscall(&mut state, open, &[BASE + 0x100, flags, 0o600]),
+            enosys
+        );
+        assert!(!path.exists());
+    }
+}
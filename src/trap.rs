@@ -0,0 +1,252 @@
+use crate::{
+    csr::{
+        MSTATUS_MIE, MSTATUS_MPIE, MSTATUS_MPP, MSTATUS_MPRV, MSTATUS_SIE, MSTATUS_SPIE,
+        MSTATUS_SPP,
+    },
+    processor::{Privilege, Reg, State},
+};
+
+/// Synchronous exceptions, carrying the value reported in `mtval`/`stval`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Exception {
+    InstructionAddressMisaligned(u64),
+    InstructionAccessFault(u64),
+    IllegalInstruction(u64),
+    Breakpoint(u64),
+    LoadAddressMisaligned(u64),
+    LoadAccessFault(u64),
+    StoreAddressMisaligned(u64),
+    StoreAccessFault(u64),
+    EnvironmentCallFromUMode,
+    EnvironmentCallFromSMode,
+    EnvironmentCallFromMMode,
+    InstructionPageFault(u64),
+    LoadPageFault(u64),
+    StorePageFault(u64),
+}
+impl Exception {
+    pub fn cause(&self) -> u64 {
+        use Exception::*;
+        match self {
+            InstructionAddressMisaligned(_) => 0,
+            InstructionAccessFault(_) => 1,
+            IllegalInstruction(_) => 2,
+            Breakpoint(_) => 3,
+            LoadAddressMisaligned(_) => 4,
+            LoadAccessFault(_) => 5,
+            StoreAddressMisaligned(_) => 6,
+            StoreAccessFault(_) => 7,
+            EnvironmentCallFromUMode => 8,
+            EnvironmentCallFromSMode => 9,
+            EnvironmentCallFromMMode => 11,
+            InstructionPageFault(_) => 12,
+            LoadPageFault(_) => 13,
+            StorePageFault(_) => 15,
+        }
+    }
+    pub fn tval(&self) -> u64 {
+        use Exception::*;
+        match *self {
+            InstructionAddressMisaligned(tval)
+            | InstructionAccessFault(tval)
+            | IllegalInstruction(tval)
+            | Breakpoint(tval)
+            | LoadAddressMisaligned(tval)
+            | LoadAccessFault(tval)
+            | StoreAddressMisaligned(tval)
+            | StoreAccessFault(tval)
+            | InstructionPageFault(tval)
+            | LoadPageFault(tval)
+            | StorePageFault(tval) => tval,
+            EnvironmentCallFromUMode | EnvironmentCallFromSMode | EnvironmentCallFromMMode => 0,
+        }
+    }
+    /// AMOs need write permission, so faults on their load half are reported as store faults.
+    pub fn into_store_fault(self) -> Self {
+        use Exception::*;
+        match self {
+            LoadAccessFault(tval) => StoreAccessFault(tval),
+            LoadPageFault(tval) => StorePageFault(tval),
+            exception => exception,
+        }
+    }
+}
+
+impl State {
+    /// Delivers a synchronous exception raised by the instruction at `pc`.
+    pub fn take_trap(&mut self, exception: Exception) {
+        self.trap(exception.cause(), false, exception.tval());
+    }
+
+    /// Takes the highest-priority interrupt that is pending, enabled and not masked by the
+    /// current privilege level. Returns whether one was taken.
+    pub fn take_interrupt(&mut self) -> bool {
+        let pending = (self.mip & self.mie) as u64;
+        if pending == 0 {
+            return false;
+        }
+        let prv = self.prv;
+        let mstatus = self.mstatus as u64;
+        let m_enabled = prv < Privilege::Machine || mstatus & MSTATUS_MIE != 0;
+        let s_enabled = prv < Privilege::Supervisor
+            || (prv == Privilege::Supervisor && mstatus & MSTATUS_SIE != 0);
+        let mut enabled = 0;
+        if m_enabled {
+            enabled |= pending & !self.mideleg as u64;
+        }
+        if s_enabled {
+            enabled |= pending & self.mideleg as u64;
+        }
+        // MEI, MSI, MTI, SEI, SSI, STI.
+        let Some(cause) = [11, 3, 7, 9, 1, 5]
+            .into_iter()
+            .find(|cause| enabled & 1 << cause != 0)
+        else {
+            return false;
+        };
+        self.trap(cause, true, 0);
+        true
+    }
+
+    fn trap(&mut self, cause: u64, interrupt: bool, tval: u64) {
+        let prv = self.prv;
+        let epc = self.pc;
+        // Any trap breaks the LR/SC pairing.
+        self.reservation = None;
+
+        let deleg = if interrupt {
+            self.mideleg
+        } else {
+            self.medeleg
+        } as u64;
+        let mut mstatus = self.mstatus as u64;
+        if prv <= Privilege::Supervisor && deleg & 1 << cause != 0 {
+            self.scause = self.encode_cause(cause, interrupt);
+            self.sepc = epc;
+            self.stval = tval as Reg;
+            mstatus = set_field(mstatus, MSTATUS_SPIE, (mstatus & MSTATUS_SIE != 0) as u64);
+            mstatus = set_field(mstatus, MSTATUS_SPP, prv as u64);
+            mstatus &= !MSTATUS_SIE;
+            self.prv = Privilege::Supervisor;
+            self.pc = trap_vector(self.stvec, cause, interrupt);
+        } else {
+            self.mcause = self.encode_cause(cause, interrupt);
+            self.mepc = epc;
+            self.mtval = tval as Reg;
+            mstatus = set_field(mstatus, MSTATUS_MPIE, (mstatus & MSTATUS_MIE != 0) as u64);
+            mstatus = set_field(mstatus, MSTATUS_MPP, prv as u64);
+            mstatus &= !MSTATUS_MIE;
+            self.prv = Privilege::Machine;
+            self.pc = trap_vector(self.mtvec, cause, interrupt);
+        }
+        self.mstatus = mstatus as Reg;
+        self.pc = self.sext_xlen(self.pc);
+    }
+
+    fn encode_cause(&self, cause: u64, interrupt: bool) -> Reg {
+        let interrupt = (interrupt as u64) << (self.xlen_bits() - 1);
+        self.sext_xlen((interrupt | cause) as Reg)
+    }
+
+    /// Returns from an M-mode trap handler.
+    pub fn mret(&mut self) {
+        let mut mstatus = self.mstatus as u64;
+        let mpp = Privilege::from_bits(get_field(mstatus, MSTATUS_MPP));
+        mstatus = set_field(mstatus, MSTATUS_MIE, get_field(mstatus, MSTATUS_MPIE));
+        mstatus |= MSTATUS_MPIE;
+        mstatus = set_field(mstatus, MSTATUS_MPP, Privilege::User as u64);
+        if mpp != Privilege::Machine {
+            mstatus &= !MSTATUS_MPRV;
+        }
+        self.mstatus = mstatus as Reg;
+        self.prv = mpp;
+        self.pc = self.mepc;
+    }
+
+    /// Returns from an S-mode trap handler.
+    pub fn sret(&mut self) {
+        let mut mstatus = self.mstatus as u64;
+        let spp = Privilege::from_bits(get_field(mstatus, MSTATUS_SPP));
+        mstatus = set_field(mstatus, MSTATUS_SIE, get_field(mstatus, MSTATUS_SPIE));
+        mstatus |= MSTATUS_SPIE;
+        mstatus = set_field(mstatus, MSTATUS_SPP, Privilege::User as u64);
+        mstatus &= !MSTATUS_MPRV;
+        self.mstatus = mstatus as Reg;
+        self.prv = spp;
+        self.pc = self.sepc;
+    }
+}
+
+/// In vectored mode, interrupts jump to `BASE + 4 * cause`; exceptions always use `BASE`.
+fn trap_vector(tvec: Reg, cause: u64, interrupt: bool) -> Reg {
+    let base = tvec & !0b11;
+    if tvec & 0b11 == 1 && interrupt {
+        base.wrapping_add(4 * cause as Reg)
+    } else {
+        base
+    }
+}
+
+pub fn get_field(reg: u64, mask: u64) -> u64 {
+    (reg & mask) >> mask.trailing_zeros()
+}
+
+pub fn set_field(reg: u64, mask: u64, value: u64) -> u64 {
+    reg & !mask | (value << mask.trailing_zeros()) & mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        machine::tests::{machine, BASE},
+        processor::XprName,
+    };
+
+    #[test]
+    fn delegated_traps_and_returns_switch_privilege() {
+        let mut machine = machine(&[
+            0x0000_0073, // ecall, from U-mode
+            0x0010_0513, // addi a0, zero, 1
+            0x0000_0073, // stvec: ecall, from S-mode
+            0x1413_1073, // csrw sepc, t1
+            0x1020_0073, // sret
+            0x3412_9073, // mtvec: csrw mepc, t0
+            0x3020_0073, // mret
+        ]);
+        machine.state.medeleg = 1 << 8;
+        machine.state.stvec = (BASE + 8) as i64;
+        machine.state.mtvec = (BASE + 20) as i64;
+        machine.state.mstatus = MSTATUS_MIE as i64;
+        machine.state.prv = Privilege::User;
+        machine.set_reg(XprName::t0, (BASE + 12) as i64);
+        machine.set_reg(XprName::t1, (BASE + 4) as i64);
+
+        // Only ecall from U-mode is delegated to S-mode.
+        machine.run(1);
+        assert_eq!(machine.state.prv, Privilege::Supervisor);
+        assert_eq!(machine.state.scause, 8);
+        assert_eq!(machine.state.sepc, BASE as i64);
+        assert_eq!(get_field(machine.state.mstatus as u64, MSTATUS_SPP), 0);
+        machine.run(1);
+        assert_eq!(machine.state.prv, Privilege::Machine);
+        assert_eq!(machine.state.mcause, 9);
+        assert_eq!(machine.state.mepc, (BASE + 8) as i64);
+        let mstatus = machine.state.mstatus as u64;
+        assert_eq!(
+            get_field(mstatus, MSTATUS_MPP),
+            Privilege::Supervisor as u64
+        );
+        assert_eq!(mstatus & (MSTATUS_MIE | MSTATUS_MPIE), MSTATUS_MPIE);
+
+        // mret restores S-mode and MIE, then sret drops back to U-mode.
+        machine.run(2);
+        assert_eq!(machine.state.prv, Privilege::Supervisor);
+        assert_eq!(machine.pc(), BASE + 12);
+        assert_eq!(machine.state.mstatus as u64 & MSTATUS_MIE, MSTATUS_MIE);
+        machine.run(3);
+        assert_eq!(machine.state.prv, Privilege::User);
+        assert_eq!(machine.reg(XprName::a0), 1);
+        assert_eq!(machine.pc(), BASE + 8);
+    }
+}
@@ -2,3 +2,13 @@
 pub fn x<N: Into<u64>>(value: N, from: usize, size: usize) -> i64 {
     ((value.into() >> from) & ((1 << size) - 1)) as i64
 }
+
+/// Prints a line of the execution trace when `--trace` is on.
+macro_rules! trace {
+    ($state:expr, $($arg:tt)*) => {
+        if $state.trace {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use trace;
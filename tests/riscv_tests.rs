@@ -0,0 +1,118 @@
+//! Runs the riscv-tests ISA suite against the simulator binary.
+//!
+//! Only a small smoke test, `test/riscv-tests/rv64ui-p-smoke`, is checked in. Point
+//! `RISCV_TESTS_DIR` at a directory holding the built tests (`riscv-tests/isa` after
+//! `make`), or place them beside the smoke test under `test/riscv-tests`. If no tests are
+//! found the suite is skipped with a notice on stderr. Arguments filter tests by substring,
+//! as with the default harness.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+const PREFIXES: &[&str] = &[
+    "rv64ui-p-",
+    "rv64um-p-",
+    "rv64ua-p-",
+    "rv64uf-p-",
+    "rv64ud-p-",
+    "rv64uc-p-",
+    "rv64si-p-",
+    "rv64mi-p-",
+];
+
+/// Every test finishes in well under this; hitting it means the test hung.
+const MAX_INSTRUCTIONS: u64 = 10_000_000;
+
+fn tests_dir() -> PathBuf {
+    env::var_os("RISCV_TESTS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("test/riscv-tests"))
+}
+
+/// ELF files with one of the prefixes; the `.dump` disassemblies beside them are skipped.
+fn discover(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut tests: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            path.extension().is_none() && PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+        })
+        .collect();
+    tests.sort();
+    tests
+}
+
+/// riscv-tests report through HTIF, which the simulator turns into its exit status: 0 on
+/// success, otherwise the number of the failing case.
+fn run(test: &Path) -> Result<(), String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_jisaku-riscv-sim"))
+        .arg("--max-instructions")
+        .arg(MAX_INSTRUCTIONS.to_string())
+        .arg(test)
+        .output()
+        .map_err(|e| format!("cannot run the simulator: {}", e))?;
+    match output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(format!(
+            "exit status {}\n{}",
+            code,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )),
+        None => Err("killed by a signal".into()),
+    }
+}
+
+fn main() {
+    // Flags such as --nocapture that cargo forwards are not filters.
+    let filters: Vec<String> = env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .collect();
+    let dir = tests_dir();
+    let tests: Vec<PathBuf> = discover(&dir)
+        .into_iter()
+        .filter(|test| {
+            let name = test.file_name().unwrap_or_default().to_string_lossy();
+            filters.is_empty() || filters.iter().any(|filter| name.contains(filter.as_str()))
+        })
+        .collect();
+    if tests.is_empty() {
+        eprintln!("skipped: no ELFs found in {}", dir.display());
+        return;
+    }
+
+    println!("\nrunning {} tests", tests.len());
+    let mut failures = vec![];
+    for test in &tests {
+        let name = test.file_name().unwrap_or_default().to_string_lossy();
+        match run(test) {
+            Ok(()) => println!("test {} ... ok", name),
+            Err(reason) => {
+                println!("test {} ... FAILED", name);
+                failures.push((name, reason));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        println!("\nfailures:");
+        for (name, reason) in &failures {
+            println!("    {}: {}", name, reason);
+        }
+    }
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        if failures.is_empty() { "ok" } else { "FAILED" },
+        tests.len() - failures.len(),
+        failures.len()
+    );
+    if !failures.is_empty() {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,205 @@
+//! Keeps the checked-in `test/riscv-tests/rv64ui-p-smoke` in step with its source.
+//!
+//! The ELF is built from `rv64ui-p-smoke.S` by assembling it with `llvm-mc` and laying the
+//! single `.text` section out at `BASE`, as `-nostdlib -static -Ttext=0x80000000` would.
+//! With `REGENERATE_SMOKE` set the result replaces the checked-in file; otherwise the two
+//! must match byte for byte. Without `llvm-mc` only the toolchain-free checks run.
+
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use goblin::elf::{
+    program_header::{PF_R, PF_W, PF_X, PT_LOAD},
+    section_header::{SHF_ALLOC, SHF_EXECINSTR, SHT_PROGBITS, SHT_STRTAB, SHT_SYMTAB},
+    sym::{STB_GLOBAL, STB_LOCAL, STT_FILE, STT_SECTION},
+    Elf,
+};
+
+const BASE: u64 = 0x8000_0000;
+/// File offset of `.text`, so that it is page-aligned like its load address.
+const TEXT_OFFSET: u64 = 0x1000;
+
+fn smoke_path(extension: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test/riscv-tests/rv64ui-p-smoke")
+        .with_extension(extension)
+}
+
+/// Names the source exports with `.globl`.
+fn source_globals() -> BTreeSet<String> {
+    fs::read_to_string(smoke_path("S"))
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix(".globl"))
+        .map(|name| name.trim().to_string())
+        .collect()
+}
+
+/// Assembles the source, or returns `None` if `llvm-mc` is not installed.
+fn assemble() -> Option<Vec<u8>> {
+    let object = env::temp_dir().join(format!("jisaku-smoke-{}.o", std::process::id()));
+    let status = Command::new("llvm-mc")
+        .args(["-triple=riscv64", "-mattr=-relax", "-filetype=obj", "-o"])
+        .arg(&object)
+        .arg(smoke_path("S"))
+        .status()
+        .ok()?;
+    assert!(status.success(), "llvm-mc failed on rv64ui-p-smoke.S");
+    let bytes = fs::read(&object).unwrap();
+    fs::remove_file(&object).unwrap();
+    Some(bytes)
+}
+
+/// Links an object with one `.text` section and no relocations into an executable with a
+/// single RWX segment at `BASE`, keeping each symbol's binding.
+fn link(object: &[u8]) -> Vec<u8> {
+    let elf = Elf::parse(object).unwrap();
+    let (text_index, text) = elf
+        .section_headers
+        .iter()
+        .enumerate()
+        .find(|(_, sh)| elf.shdr_strtab.get_at(sh.sh_name) == Some(".text"))
+        .expect("no .text section");
+    assert!(
+        elf.shdr_relocs.is_empty(),
+        "rv64ui-p-smoke.S must assemble without relocations"
+    );
+    let code = &object[text.file_range().unwrap()];
+
+    // Locals first, as the ELF spec requires.
+    let mut symbols: Vec<_> = elf
+        .syms
+        .iter()
+        .filter(|sym| sym.st_shndx == text_index && sym.st_name != 0)
+        .filter(|sym| !matches!(sym.st_type(), STT_SECTION | STT_FILE))
+        .collect();
+    symbols.sort_by_key(|sym| sym.st_bind() != STB_LOCAL);
+    let mut strtab = vec![0];
+    let mut symtab = vec![0; 24];
+    for sym in &symbols {
+        symtab.extend((strtab.len() as u32).to_le_bytes());
+        strtab.extend(elf.strtab.get_at(sym.st_name).unwrap().bytes());
+        strtab.push(0);
+        symtab.extend([sym.st_info, sym.st_other]);
+        symtab.extend(1u16.to_le_bytes());
+        symtab.extend((BASE + sym.st_value).to_le_bytes());
+        symtab.extend(sym.st_size.to_le_bytes());
+    }
+    let first_global = 1 + symbols
+        .iter()
+        .filter(|sym| sym.st_bind() == STB_LOCAL)
+        .count();
+    let entry = symbols
+        .iter()
+        .find(|sym| elf.strtab.get_at(sym.st_name) == Some("_start"))
+        .map_or(BASE, |sym| BASE + sym.st_value);
+    let shstrtab = b"\0.text\0.symtab\0.strtab\0.shstrtab\0";
+
+    let symtab_off = (TEXT_OFFSET + code.len() as u64).next_multiple_of(8);
+    let strtab_off = symtab_off + symtab.len() as u64;
+    let shstrtab_off = strtab_off + strtab.len() as u64;
+    let shoff = (shstrtab_off + shstrtab.len() as u64).next_multiple_of(8);
+
+    let mut out = b"\x7fELF\x02\x01\x01".to_vec();
+    out.resize(16, 0);
+    out.extend(2u16.to_le_bytes());
+    out.extend(elf.header.e_machine.to_le_bytes());
+    out.extend(1u32.to_le_bytes());
+    out.extend(entry.to_le_bytes());
+    out.extend(64u64.to_le_bytes());
+    out.extend(shoff.to_le_bytes());
+    out.extend(elf.header.e_flags.to_le_bytes());
+    for half in [64u16, 56, 1, 64, 5, 4] {
+        out.extend(half.to_le_bytes());
+    }
+
+    let len = code.len() as u64;
+    out.extend(PT_LOAD.to_le_bytes());
+    out.extend((PF_R | PF_W | PF_X).to_le_bytes());
+    for field in [TEXT_OFFSET, BASE, BASE, len, len, 0x1000] {
+        out.extend(field.to_le_bytes());
+    }
+
+    out.resize(TEXT_OFFSET as usize, 0);
+    out.extend(code);
+    out.resize(symtab_off as usize, 0);
+    out.extend(&symtab);
+    out.extend(&strtab);
+    out.extend(shstrtab);
+    out.resize(shoff as usize, 0);
+
+    // Null, .text, .symtab (linked to .strtab, with the first global as its info), .strtab
+    // and .shstrtab.
+    let flags = (SHF_ALLOC | SHF_EXECINSTR) as u64;
+    let (sym_len, str_len) = (symtab.len() as u64, strtab.len() as u64);
+    let (shstr_len, globals) = (shstrtab.len() as u64, first_global as u32);
+    let sections = [
+        (0, 0, 0, 0, 0, 0, 0, 0, 0, 0),
+        (1, SHT_PROGBITS, flags, BASE, TEXT_OFFSET, len, 0, 0, 4, 0),
+        (7, SHT_SYMTAB, 0, 0, symtab_off, sym_len, 3, globals, 8, 24),
+        (15, SHT_STRTAB, 0, 0, strtab_off, str_len, 0, 0, 1, 0),
+        (23, SHT_STRTAB, 0, 0, shstrtab_off, shstr_len, 0, 0, 1, 0),
+    ];
+    for (name, kind, flags, addr, offset, size, link, info, align, entsize) in sections {
+        out.extend((name as u32).to_le_bytes());
+        out.extend(kind.to_le_bytes());
+        for field in [flags, addr, offset, size] {
+            out.extend(field.to_le_bytes());
+        }
+        out.extend((link as u32).to_le_bytes());
+        out.extend(info.to_le_bytes());
+        for field in [align, entsize] {
+            out.extend((field as u64).to_le_bytes());
+        }
+    }
+    out
+}
+
+#[test]
+fn smoke_elf_is_built_from_its_source() {
+    let Some(object) = assemble() else {
+        eprintln!("skipped: llvm-mc not found, cannot rebuild rv64ui-p-smoke");
+        return;
+    };
+    let built = link(&object);
+    let path = smoke_path("");
+    if env::var_os("REGENERATE_SMOKE").is_some() {
+        // Renamed into place so the other test never reads a half-written file.
+        let staged = path.with_extension("new");
+        fs::write(&staged, &built).unwrap();
+        fs::rename(&staged, &path).unwrap();
+    }
+    assert!(
+        fs::read(&path).unwrap() == built,
+        "{} does not match rv64ui-p-smoke.S; rebuild it with \
+         REGENERATE_SMOKE=1 cargo test --test smoke_elf",
+        path.display()
+    );
+}
+
+#[test]
+fn smoke_elf_exports_what_its_source_declares() {
+    let bytes = fs::read(smoke_path("")).unwrap();
+    let elf = Elf::parse(&bytes).unwrap();
+    let globals: BTreeSet<String> = elf
+        .syms
+        .iter()
+        .filter(|sym| sym.st_bind() == STB_GLOBAL)
+        .map(|sym| elf.strtab.get_at(sym.st_name).unwrap().to_string())
+        .collect();
+    assert_eq!(globals, source_globals());
+
+    let segments: Vec<_> = elf
+        .program_headers
+        .iter()
+        .filter(|ph| ph.p_type == PT_LOAD)
+        .collect();
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].p_vaddr, BASE);
+    // Everything, tohost included, is initialised data in the file image.
+    assert_eq!(segments[0].p_memsz, segments[0].p_filesz);
+}